@@ -1,3 +1,4 @@
+pub mod bot;
 pub mod client;
 pub mod server;
 pub mod types;
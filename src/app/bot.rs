@@ -0,0 +1,433 @@
+/// Headless players that fill the rooms of a server, for load testing and for demo play
+///
+/// Every bot is a guest user that joins a room over the same gRPC client as the TUI, then types the prompt
+/// of the game at the configured speed, making mistakes that it has to correct along the way
+use std::time::Duration;
+
+use rand::Rng;
+use tokio::sync::mpsc;
+use tokio_stream::StreamExt;
+use tracing::Instrument;
+
+use crate::app::{
+    client::{
+        network::{self, types::ClientError, with_request_id, GrpcClient},
+        types::ClientTlsConfig,
+    },
+    server::grpc::server::{
+        room_service_request, GameServiceRequest, PingRequest, ResumableGame, RoomServiceRequest,
+    },
+    types::RoomServiceResponseType,
+};
+
+/// Average number of characters in a word, used to convert the words per minute into keystrokes
+const CHARACTERS_PER_WORD: f64 = 5.0;
+/// The delay between two keystrokes varies by up to this fraction of the average delay
+const KEYSTROKE_JITTER: f64 = 0.3;
+
+#[derive(clap::Parser, Debug, Clone)]
+#[command(version, about = "Headless players for load testing and demo play", long_about = None)]
+pub struct BotArgs {
+    /// Address of the server, the one in config/client.toml is used if not passed
+    ///
+    /// The TLS settings are always read from config/client.toml
+    #[arg(long)]
+    pub server_url: Option<String>,
+    /// Number of bots that play concurrently
+    #[arg(short = 'n', long, default_value_t = 1)]
+    pub bots: u32,
+    /// Delay between starting two bots, in milliseconds
+    #[arg(long, default_value_t = 200)]
+    pub spawn_interval_ms: u64,
+    /// Typing speed of the bots in words per minute
+    #[arg(long, default_value_t = 60.0)]
+    pub wpm: f64,
+    /// Fraction of the keystrokes that are mistakes, between 0 and 1
+    #[arg(long, default_value_t = 0.05)]
+    pub error_rate: f64,
+    /// Join this room instead of the matchmaking
+    #[arg(long, conflicts_with = "create_room")]
+    pub room_id: Option<String>,
+    /// Join the room of this invite code instead of the matchmaking
+    #[arg(long, conflicts_with_all = ["create_room", "room_id"])]
+    pub invite_code: Option<String>,
+    /// The bots play in pairs, one bot creates a room and the other bot joins it with its invite code
+    ///
+    /// With an odd number of bots, the last bot creates a room and waits for another player to join
+    #[arg(long, default_value_t = false)]
+    pub create_room: bool,
+    /// The rooms created by the bots are listed in the room browser of the clients
+    #[arg(long, default_value_t = false, requires = "create_room")]
+    pub public: bool,
+    /// Number of games that each bot plays, the bots keep playing until they are stopped if not passed
+    #[arg(long)]
+    pub games: Option<u32>,
+    /// Interval between the progress updates sent to the server, in milliseconds
+    #[arg(long, default_value_t = 500)]
+    pub progress_interval_ms: u64,
+}
+
+impl BotArgs {
+    /// The room request of a bot in the given role, a joiner passes the invite code of its host
+    fn get_room_request(
+        &self,
+        client_id: String,
+        role: &RoomRole,
+        invite_code: Option<String>,
+    ) -> RoomServiceRequest {
+        // The matchmaking joins the common room, which is used when no room id is passed
+        let request_type = match role {
+            RoomRole::Host { .. } => room_service_request::RequestType::CreateRoom,
+            RoomRole::Join | RoomRole::Joiner { .. } => room_service_request::RequestType::JoinRoom,
+        };
+
+        RoomServiceRequest {
+            client_id,
+            room_id: self.room_id.clone(),
+            request_type: request_type.into(),
+            invite_code: invite_code.or_else(|| self.invite_code.clone()),
+            single_use_invite: false,
+            is_public: self.public,
+        }
+    }
+
+    /// Average delay between two keystrokes
+    fn get_keystroke_delay(&self) -> Duration {
+        let characters_per_second = self.wpm.max(1.0) * CHARACTERS_PER_WORD / 60.0;
+        Duration::from_secs_f64(1.0 / characters_per_second)
+    }
+}
+
+/// Start all the bots and wait until they are done
+pub async fn run_bots(server_url: String, tls_config: Option<ClientTlsConfig>, args: BotArgs) {
+    let mut join_handles = Vec::with_capacity(args.bots as usize);
+    // The invite codes of the last host, received by the next bot
+    let mut pending_invite_receiver = None;
+
+    for bot_index in 0..args.bots {
+        if bot_index > 0 {
+            tokio::time::sleep(Duration::from_millis(args.spawn_interval_ms)).await;
+        }
+
+        let role = if !args.create_room {
+            RoomRole::Join
+        } else if bot_index + 1 == args.bots && bot_index % 2 == 0 {
+            tracing::warn!(
+                "The last bot has no other bot to play with, its room waits for another player"
+            );
+            RoomRole::Host {
+                invite_sender: None,
+            }
+        } else if bot_index % 2 == 0 {
+            let (invite_sender, invite_receiver) = mpsc::channel(1);
+            pending_invite_receiver = Some(invite_receiver);
+
+            RoomRole::Host {
+                invite_sender: Some(invite_sender),
+            }
+        } else {
+            RoomRole::Joiner {
+                invite_receiver: pending_invite_receiver
+                    .take()
+                    .expect("The host of the pair is started first"),
+            }
+        };
+
+        let mut bot = Bot {
+            server_url: server_url.clone(),
+            tls_config: tls_config.clone(),
+            args: args.clone(),
+            role,
+        };
+
+        let span = tracing::info_span!("bot", bot_index);
+        join_handles.push(tokio::spawn(
+            async move {
+                if let Err(error) = bot.run().await {
+                    tracing::error!(%error, "The bot has stopped");
+                }
+            }
+            .instrument(span),
+        ));
+    }
+
+    for join_handle in join_handles {
+        if let Err(error) = join_handle.await {
+            tracing::error!(?error, "The bot task failed");
+        }
+    }
+}
+
+/// How a bot gets into the room of each game
+enum RoomRole {
+    /// Join the room or the invite code passed on the command line, or the matchmaking
+    Join,
+    /// Create a room for every game and send its invite code to the joiner of the pair, if any
+    Host {
+        invite_sender: Option<mpsc::Sender<String>>,
+    },
+    /// Join the rooms created by the host of the pair
+    Joiner {
+        invite_receiver: mpsc::Receiver<String>,
+    },
+}
+
+struct Bot {
+    server_url: String,
+    tls_config: Option<ClientTlsConfig>,
+    args: BotArgs,
+    role: RoomRole,
+}
+
+impl Bot {
+    async fn run(&mut self) -> Result<(), ClientError> {
+        let mut client = network::connect(&self.server_url, self.tls_config.as_ref()).await?;
+
+        // Every bot plays as a new guest user
+        let (ping_request, request_span) = with_request_id(PingRequest { user_id: None }, "ping");
+        let ping_response = client
+            .ping(ping_request)
+            .instrument(request_span)
+            .await?
+            .into_inner();
+
+        let user_id = ping_response.user_id;
+        tracing::info!("Playing as {} ({user_id})", ping_response.user_name);
+
+        let mut games_played = 0;
+        while self.args.games.is_none_or(|games| games_played < games) {
+            self.wait_for_game(&mut client, &user_id).await?;
+
+            let resumable_game = self.get_game(&mut client, &user_id).await?;
+            self.play_game(&mut client, &user_id, resumable_game)
+                .await?;
+
+            games_played += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Join a room and wait until all the players have joined
+    async fn wait_for_game(
+        &mut self,
+        client: &mut GrpcClient,
+        user_id: &str,
+    ) -> Result<(), ClientError> {
+        let invite_code = match &mut self.role {
+            RoomRole::Joiner { invite_receiver } => Some(invite_receiver.recv().await.ok_or(
+                ClientError::Connection("The host of the room has stopped".to_string()),
+            )?),
+            RoomRole::Join | RoomRole::Host { .. } => None,
+        };
+
+        let room_request = self
+            .args
+            .get_room_request(user_id.to_string(), &self.role, invite_code);
+        let (room_request, request_span) = with_request_id(room_request, "room_service");
+
+        let mut room_stream = client
+            .room_service(room_request)
+            .instrument(request_span)
+            .await?
+            .into_inner();
+
+        while let Some(message) = room_stream.next().await {
+            let message = message?;
+
+            match RoomServiceResponseType::from_u8(message.message_type as u8) {
+                Some(RoomServiceResponseType::Init) => match message.invite_code {
+                    Some(invite_code) => {
+                        tracing::info!(
+                            "Waiting in the room {}, invite code {invite_code}",
+                            message.room_id
+                        );
+
+                        // The joiner only joins once the room exists
+                        if let RoomRole::Host {
+                            invite_sender: Some(invite_sender),
+                        } = &self.role
+                        {
+                            invite_sender.send(invite_code).await.map_err(|_| {
+                                ClientError::Connection(
+                                    "The joiner of the room has stopped".to_string(),
+                                )
+                            })?;
+                        }
+                    }
+                    None => tracing::info!("Waiting in the room {}", message.room_id),
+                },
+                Some(RoomServiceResponseType::UserJoined | RoomServiceResponseType::UserLeft) => {
+                    tracing::info!(
+                        "{} users are in the room {}",
+                        message.user_details.len(),
+                        message.room_id
+                    );
+                }
+                Some(RoomServiceResponseType::Chat) => {
+                    if let Some(chat_message) = message.chat_message {
+                        tracing::debug!("{}: {}", chat_message.user_name, chat_message.message);
+                    }
+                }
+                // The room is closed once the game starts, so the room stream is no longer needed
+                Some(RoomServiceResponseType::GameStart) => return Ok(()),
+                Some(RoomServiceResponseType::ServerShutdown) => {
+                    return Err(ClientError::Unavailable(
+                        "The server is shutting down".to_string(),
+                    ))
+                }
+                None => {
+                    return Err(ClientError::InvalidResponse(format!(
+                        "Unknown message type {}",
+                        message.message_type
+                    )))
+                }
+            }
+        }
+
+        Err(ClientError::Connection(
+            "The room stream was closed before the game started".to_string(),
+        ))
+    }
+
+    /// The game that the user is playing is offered on ping, along with its prompt
+    async fn get_game(
+        &self,
+        client: &mut GrpcClient,
+        user_id: &str,
+    ) -> Result<ResumableGame, ClientError> {
+        let ping_request = PingRequest {
+            user_id: Some(user_id.to_string()),
+        };
+        let (ping_request, request_span) = with_request_id(ping_request, "ping");
+
+        client
+            .ping(ping_request)
+            .instrument(request_span)
+            .await?
+            .into_inner()
+            .resumable_game
+            .ok_or(ClientError::InvalidResponse(
+                "The game has started, but the user is not playing it".to_string(),
+            ))
+    }
+
+    /// Type the prompt over the game stream, the progress is sent at every progress interval
+    async fn play_game(
+        &self,
+        client: &mut GrpcClient,
+        user_id: &str,
+        game: ResumableGame,
+    ) -> Result<(), ClientError> {
+        let prompt_length = game.prompt.chars().count() as u32;
+        tracing::info!(
+            "Playing the game {} with a prompt of {prompt_length} characters",
+            game.game_id
+        );
+
+        let get_request = |progress: u32, keystroke_intervals_ms: Vec<u32>| GameServiceRequest {
+            client_id: user_id.to_string(),
+            game_id: game.game_id.clone(),
+            progress,
+            keystroke_intervals_ms,
+        };
+
+        let (request_sender, request_receiver) = mpsc::channel::<GameServiceRequest>(16);
+        let mut progress = game.progress;
+
+        // The first message identifies the player and the game
+        let _ = request_sender.send(get_request(progress, vec![])).await;
+
+        let (game_request, request_span) = with_request_id(
+            tokio_stream::wrappers::ReceiverStream::new(request_receiver),
+            "game_service",
+        );
+        let mut game_stream = client
+            .game_service(game_request)
+            .instrument(request_span)
+            .await?
+            .into_inner();
+
+        // The status of the other players is not needed, but the stream is read so that the server is not blocked
+        let status_handle = tokio::spawn(
+            async move {
+                while let Some(message) = game_stream.next().await {
+                    match message {
+                        Ok(message) => tracing::debug!(?message.players_status),
+                        Err(status) => {
+                            tracing::error!(error = %ClientError::from(status));
+                            break;
+                        }
+                    }
+                }
+            }
+            .in_current_span(),
+        );
+
+        let started_at = tokio::time::Instant::now();
+        let mut last_sent_at = started_at;
+        let mut last_typed_at = started_at;
+        let mut keystrokes = 0;
+        let mut keystroke_intervals = Vec::new();
+        let progress_interval = Duration::from_millis(self.args.progress_interval_ms);
+
+        while progress < prompt_length {
+            keystrokes += self.type_character().await;
+            progress += 1;
+
+            // The server only sees the batches, the time taken by every character is sent along with them
+            let typed_at = tokio::time::Instant::now();
+            let interval = typed_at.duration_since(last_typed_at).as_millis();
+            keystroke_intervals.push(u32::try_from(interval).unwrap_or(u32::MAX));
+            last_typed_at = typed_at;
+
+            if progress == prompt_length || last_sent_at.elapsed() >= progress_interval {
+                let request = get_request(progress, std::mem::take(&mut keystroke_intervals));
+                if request_sender.send(request).await.is_err() {
+                    break;
+                }
+                last_sent_at = tokio::time::Instant::now();
+            }
+        }
+
+        // Closing the request stream ends the game stream as well
+        drop(request_sender);
+        if let Err(error) = status_handle.await {
+            tracing::error!(?error, "The game stream task failed");
+        }
+
+        let minutes = started_at.elapsed().as_secs_f64() / 60.0;
+        tracing::info!(
+            "Finished the game {} at {:.0} WPM with {keystrokes} keystrokes",
+            game.game_id,
+            f64::from(prompt_length - game.progress)
+                / CHARACTERS_PER_WORD
+                / minutes.max(f64::EPSILON)
+        );
+
+        Ok(())
+    }
+
+    /// Wait for the keystrokes needed to type the next character, returns the number of keystrokes
+    async fn type_character(&self) -> u32 {
+        let mut keystrokes = 0;
+
+        loop {
+            self.press_key().await;
+            keystrokes += 1;
+
+            if !rand::thread_rng().gen_bool(self.args.error_rate.clamp(0.0, 1.0)) {
+                return keystrokes;
+            }
+
+            // The wrong character has to be deleted before typing the character again
+            self.press_key().await;
+            keystrokes += 1;
+        }
+    }
+
+    async fn press_key(&self) {
+        let jitter = rand::thread_rng().gen_range(-KEYSTROKE_JITTER..=KEYSTROKE_JITTER);
+        tokio::time::sleep(self.args.get_keystroke_delay().mul_f64(1.0 + jitter)).await;
+    }
+}
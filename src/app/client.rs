@@ -1,6 +1,11 @@
 pub mod components;
+pub mod focus;
+pub mod keymap;
 pub mod layout;
+#[cfg(feature = "client_logs")]
+pub mod logging;
 pub mod model;
 pub mod network;
+pub mod theme;
 pub mod transformers;
 pub mod types;
@@ -2,7 +2,9 @@ pub mod bottom_bar;
 pub mod help;
 pub mod menu;
 pub mod network_receptor;
+pub mod room_browser;
 pub mod room_details;
+pub mod tabs;
 pub mod transformers;
 
 /// All the components must implement methods on these two types, so re export them
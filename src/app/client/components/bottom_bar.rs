@@ -1,16 +1,34 @@
 /// The bottom bar is used to show network activity
 /// After every user event, the bottom bar is updated with the response of the network activity
-use tui_realm_stdlib::{Container, Label};
+///
+/// The recent messages are kept in a history, which can be expanded into a scrollable log panel
+use std::collections::VecDeque;
+
+use tui_realm_stdlib::{Container, Label, List};
 use tuirealm::{
-    props::{Color, Layout},
+    event::KeyEvent,
+    props::{Alignment, BorderType, Color, Layout, TextSpan},
     tui::layout::Constraint,
-    Component, MockComponent,
+    Component, Event, MockComponent,
 };
 
-use crate::app::client::network::types::UserEvent;
+use crate::app::{
+    client::{
+        keymap::{Action, Keymap},
+        network::types::{ConnectionState, UserEvent},
+        theme::Theme,
+        types::{FocusChange, Panel},
+    },
+    server::grpc::invite_code,
+    utils,
+};
 
 use super::Msg;
 
+/// Number of messages kept in the log history, the oldest messages are dropped first
+const LOG_HISTORY_CAPACITY: usize = 500;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum MessageType {
     Success,
     Info,
@@ -18,24 +36,72 @@ enum MessageType {
 }
 
 impl MessageType {
-    fn get_color(&self) -> Color {
+    fn get_color(&self, theme: &Theme) -> Color {
         match self {
-            MessageType::Info => Color::Gray,
-            MessageType::Error => Color::Red,
-            MessageType::Success => Color::Green,
+            MessageType::Info => theme.info,
+            MessageType::Error => theme.error,
+            MessageType::Success => theme.success,
         }
     }
+
+    fn get_label(&self) -> &'static str {
+        match self {
+            MessageType::Info => "INFO",
+            MessageType::Error => "ERROR",
+            MessageType::Success => "OK",
+        }
+    }
+}
+
+struct LogEntry {
+    /// Time of the message in milliseconds since unix epoch
+    timestamp: u64,
+    message_type: MessageType,
+    text: String,
+}
+
+impl LogEntry {
+    fn to_row(&self, theme: &Theme) -> Vec<TextSpan> {
+        vec![
+            TextSpan::from(format!("{} ", format_time(self.timestamp))).fg(theme.muted),
+            TextSpan::from(format!("{:<6}", self.message_type.get_label()))
+                .fg(self.message_type.get_color(theme)),
+            TextSpan::from(self.text.as_str()).fg(theme.text),
+        ]
+    }
+}
+
+/// Format the time of the day in UTC as HH:MM:SS
+fn format_time(timestamp_millis: u64) -> String {
+    let seconds_of_day = timestamp_millis / 1000 % 86400;
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    )
 }
 
-#[derive(MockComponent)]
 pub struct BottomBar {
     component: tui_realm_stdlib::Container,
+    logs: VecDeque<LogEntry>,
+    title: String,
+    /// The log panel is expanded while the bottom bar has the focus
+    is_expanded: bool,
+    /// Only the messages of this type are shown in the log panel, all messages if not set
+    severity_filter: Option<MessageType>,
+    /// Number of messages scrolled back from the most recent message
+    scroll_offset: usize,
+    keymap: Keymap,
+    theme: Theme,
 }
 
-impl Default for BottomBar {
-    fn default() -> Self {
+impl BottomBar {
+    pub fn new(keymap: Keymap, theme: Theme) -> Self {
+        let title = format!("Network Logs - [ {} ]", keymap.get_keys(Action::FocusLog));
         let container = Container::default()
-            .title("Network Logs", tuirealm::props::Alignment::Left)
+            .title(title.as_str(), Alignment::Left)
+            .borders(theme.get_borders(false))
             .layout(
                 Layout::default()
                     .constraints(&[Constraint::Percentage(100)])
@@ -46,7 +112,79 @@ impl Default for BottomBar {
 
         Self {
             component: container,
+            logs: VecDeque::with_capacity(LOG_HISTORY_CAPACITY),
+            title,
+            is_expanded: false,
+            severity_filter: None,
+            scroll_offset: 0,
+            keymap,
+            theme,
+        }
+    }
+}
+
+impl MockComponent for BottomBar {
+    fn view(&mut self, frame: &mut tuirealm::Frame, area: tuirealm::tui::prelude::Rect) {
+        if !self.is_expanded {
+            self.component.view(frame, area);
+            return;
+        }
+
+        let filtered_logs = self
+            .logs
+            .iter()
+            .filter(|log| {
+                self.severity_filter
+                    .is_none_or(|severity| log.message_type == severity)
+            })
+            .collect::<Vec<_>>();
+
+        // Show the page of messages that ends at the scroll offset, the most recent message is at the bottom
+        let visible_rows = usize::from(area.height.saturating_sub(2));
+        self.scroll_offset = self
+            .scroll_offset
+            .min(filtered_logs.len().saturating_sub(visible_rows));
+
+        let end = filtered_logs.len() - self.scroll_offset;
+        let start = end.saturating_sub(visible_rows);
+
+        let rows = filtered_logs[start..end]
+            .iter()
+            .map(|log| log.to_row(&self.theme))
+            .collect::<Vec<_>>();
+
+        let filter = self
+            .severity_filter
+            .map_or("ALL", |severity| severity.get_label());
+
+        let mut log_panel = List::default()
+            .title(format!("{} - filter {filter}", self.title), Alignment::Left)
+            .borders(self.theme.get_borders(true).modifiers(BorderType::Rounded))
+            .rows(rows);
+
+        log_panel.view(frame, area);
+    }
+
+    fn query(&self, attr: tuirealm::Attribute) -> Option<tuirealm::AttrValue> {
+        self.component.query(attr)
+    }
+
+    fn attr(&mut self, attr: tuirealm::Attribute, value: tuirealm::AttrValue) {
+        if let (tuirealm::Attribute::Focus, tuirealm::AttrValue::Flag(is_focused)) = (attr, &value)
+        {
+            self.is_expanded = *is_focused;
+            self.scroll_offset = 0;
         }
+
+        self.component.attr(attr, value)
+    }
+
+    fn state(&self) -> tuirealm::State {
+        self.component.state()
+    }
+
+    fn perform(&mut self, cmd: tuirealm::command::Cmd) -> tuirealm::command::CmdResult {
+        self.component.perform(cmd)
     }
 }
 
@@ -54,44 +192,180 @@ impl BottomBar {
     fn set_text(&mut self, text: String, message_type: MessageType) {
         let text_field = Box::new(
             Label::default()
-                .text(text)
-                .foreground(message_type.get_color()),
+                .text(text.as_str())
+                .foreground(message_type.get_color(&self.theme)),
         );
         self.component.children[0] = text_field;
+
+        if self.logs.len() == LOG_HISTORY_CAPACITY {
+            self.logs.pop_front();
+        }
+
+        self.logs.push_back(LogEntry {
+            timestamp: utils::current_timestamp_millis(),
+            message_type,
+            text,
+        });
+    }
+
+    /// The state of the connection is always visible in the title
+    fn set_connection_state(&mut self, connection_state: &ConnectionState) {
+        let state = match connection_state {
+            ConnectionState::Connecting => "Connecting",
+            ConnectionState::Connected => "Connected",
+            ConnectionState::Reconnecting { .. } => "Reconnecting",
+        };
+
+        self.title = format!(
+            "Network Logs ({state}) - [ {} ]",
+            self.keymap.get_keys(Action::FocusLog)
+        );
+        self.component.attr(
+            tuirealm::Attribute::Title,
+            tuirealm::AttrValue::Title((self.title.clone(), Alignment::Left)),
+        );
+    }
+
+    /// Show all the messages, then only the messages of each severity
+    fn cycle_severity_filter(&mut self) {
+        self.severity_filter = match self.severity_filter {
+            None => Some(MessageType::Error),
+            Some(MessageType::Error) => Some(MessageType::Info),
+            Some(MessageType::Info) => Some(MessageType::Success),
+            Some(MessageType::Success) => None,
+        };
+        self.scroll_offset = 0;
+    }
+
+    fn on_keyboard_event(&mut self, key_event: KeyEvent) -> Option<Msg> {
+        // The bottom bar listens to all the events, but only handles the keys while focused
+        if !self.is_expanded {
+            return None;
+        }
+
+        match self.keymap.get_action(&key_event)? {
+            Action::Back => Some(Msg::Focus(FocusChange::Return)),
+            Action::CycleLogFilter => {
+                self.cycle_severity_filter();
+                Some(Msg::BottomBarUpdate)
+            }
+            Action::ScrollLogUp => {
+                // The offset is limited to the available messages when the panel is drawn
+                self.scroll_offset = self.scroll_offset.saturating_add(1);
+                Some(Msg::BottomBarUpdate)
+            }
+            Action::ScrollLogDown => {
+                self.scroll_offset = self.scroll_offset.saturating_sub(1);
+                Some(Msg::BottomBarUpdate)
+            }
+            _ => self.keymap.get_global_msg(&key_event, Panel::Log),
+        }
     }
 }
 
 impl Component<Msg, UserEvent> for BottomBar {
     fn on(&mut self, event: tuirealm::Event<UserEvent>) -> Option<Msg> {
-        if let tuirealm::Event::User(user_event) = event {
-            match user_event {
-                UserEvent::InfoMessage(info_message) => {
-                    self.set_text(info_message, MessageType::Success);
-                }
-                UserEvent::NetworkError(network_error) => {
-                    self.set_text(network_error, MessageType::Error);
-                }
-                UserEvent::RoomCreated { room_id, .. } => {
-                    let text_message =
-                        format!("Joined room with id {room_id}. Waiting for other players to join");
+        let user_event = match event {
+            Event::User(user_event) => user_event,
+            Event::Keyboard(key_event) => return self.on_keyboard_event(key_event),
+            _ => return None,
+        };
 
-                    self.set_text(text_message, MessageType::Success);
-                }
-                UserEvent::GameStart { .. } => {
-                    self.set_text(
-                        "All users have joined, game will start now".to_string(),
-                        MessageType::Info,
-                    );
-                }
-                UserEvent::UserJoined { users } => {
+        match user_event {
+            UserEvent::InfoMessage(info_message) => {
+                self.set_text(info_message, MessageType::Success);
+            }
+            UserEvent::NetworkError(network_error) => {
+                self.set_text(network_error.to_string(), MessageType::Error);
+            }
+            UserEvent::RoomCreated { invite_code, .. } => {
+                // The private rooms are only joined with their invite code, the room id is not shown
+                let text_message = match invite_code {
+                    Some(invite_code) => format!(
+                        "Joined the room. Invite the other players with the code {invite_code} or {}",
+                        invite_code::get_join_link(&invite_code)
+                    ),
+                    None => "Joined the room. Waiting for other players to join".to_string(),
+                };
+
+                self.set_text(text_message, MessageType::Success);
+            }
+            UserEvent::GameStart { .. } => {
+                self.set_text(
+                    "All users have joined, game will start now".to_string(),
+                    MessageType::Info,
+                );
+            }
+            UserEvent::UserJoined { users } => {
+                let text = format!(
+                    "New user has joined the party, the number of users are {}",
+                    users.len()
+                );
+                self.set_text(text, MessageType::Info)
+            }
+            UserEvent::UserLeft { users } => {
+                let text = format!(
+                    "A user has left the room, the number of users are {}",
+                    users.len()
+                );
+                self.set_text(text, MessageType::Info)
+            }
+            UserEvent::RoomLeft => {
+                self.set_text("Left the room".to_string(), MessageType::Info);
+            }
+            UserEvent::ServerShutdown => {
+                self.set_text(
+                    "The server is shutting down, the room will be closed".to_string(),
+                    MessageType::Error,
+                );
+            }
+            UserEvent::ConnectionState(connection_state) => {
+                self.set_connection_state(&connection_state);
+
+                if let ConnectionState::Reconnecting { attempt, retry_in } = connection_state {
                     let text = format!(
-                        "New user has joined the party, the number of users are {}",
-                        users.len()
+                        "Connection to the server lost, retrying in {:.1}s (attempt {attempt})",
+                        retry_in.as_secs_f32()
                     );
-                    self.set_text(text, MessageType::Info)
+                    self.set_text(text, MessageType::Error);
                 }
             }
-        };
+            UserEvent::GameRejoined { game_id, progress } => {
+                let text = format!("Rejoined the game {game_id} at {progress} characters");
+                self.set_text(text, MessageType::Success);
+            }
+            // Global stats and the chat are shown in the details panel
+            UserEvent::GlobalStats { .. }
+            | UserEvent::ChatMessage { .. }
+            | UserEvent::RoomList { .. } => return None,
+        }
+
         Some(Msg::BottomBarUpdate)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tuirealm::{
+        event::{Key, KeyModifiers},
+        AttrValue, Attribute,
+    };
+
+    use super::*;
+    use crate::app::client::{
+        keymap::KeymapConfig,
+        theme::{Theme, ThemeConfig},
+    };
+
+    #[test]
+    fn keys_are_only_handled_while_focused() {
+        let keymap = Keymap::new(&KeymapConfig::default());
+        let mut bottom_bar = BottomBar::new(keymap, Theme::new(&ThemeConfig::default()));
+        let back = Event::Keyboard(KeyEvent::new(Key::Esc, KeyModifiers::NONE));
+
+        assert_eq!(bottom_bar.on(back.clone()), None);
+
+        bottom_bar.attr(Attribute::Focus, AttrValue::Flag(true));
+        assert_eq!(bottom_bar.on(back), Some(Msg::Focus(FocusChange::Return)));
+    }
+}
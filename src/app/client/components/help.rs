@@ -1,6 +1,8 @@
 use tui_realm_stdlib::Table;
 use tuirealm::{props::TextSpan, Component, MockComponent};
 
+use crate::app::client::{keymap::Keymap, theme::Theme};
+
 use super::{Msg, UserEvent};
 
 #[derive(MockComponent)]
@@ -8,15 +10,21 @@ pub struct Help {
     component: Table,
 }
 
-impl Default for Help {
-    fn default() -> Self {
+impl Help {
+    /// The help is generated from the keymap, so that it always shows the active bindings
+    pub fn new(keymap: &Keymap, theme: Theme) -> Self {
+        let rows = keymap
+            .get_help()
+            .into_iter()
+            .map(|(keys, description)| vec![TextSpan::from(keys), TextSpan::from(description)])
+            .collect::<Vec<_>>();
+
         let component = Table::default()
             .title("Navigation", tuirealm::props::Alignment::Center)
-            .table(vec![
-                vec![TextSpan::from("Arrow keys"), TextSpan::from("Navigate")],
-                vec![TextSpan::from("Return / Enter"), TextSpan::from("Select")],
-                vec![TextSpan::from("M / m"), TextSpan::from("Menu")],
-            ]);
+            .borders(theme.get_borders(false))
+            .inactive(theme.get_inactive_style())
+            .foreground(theme.text)
+            .table(rows);
 
         Self { component }
     }
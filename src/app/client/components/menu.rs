@@ -9,7 +9,14 @@ use tuirealm::{
 
 use super::{Msg, UserEvent};
 
-use crate::app::client::types::{MenuMessage, MenuSelection};
+use crate::app::client::{
+    keymap::{Action, Keymap},
+    theme::Theme,
+    types::{MenuMessage, MenuSelection, Panel, RoomBrowserMessage},
+};
+
+/// Room for an invite code written with spaces or dashes, such as `ABC-DEF`
+const INVITE_INPUT_LENGTH: usize = 16;
 
 #[derive(Default, Debug, PartialEq, Eq)]
 #[repr(u8)]
@@ -17,7 +24,10 @@ enum Menus {
     #[default]
     NewGame = 0,
     CreateRoom = 1,
-    JoinRoom = 2,
+    CreateSingleUseRoom = 2,
+    CreatePublicRoom = 3,
+    JoinRoom = 4,
+    BrowseRooms = 5,
 }
 
 impl Menus {
@@ -25,7 +35,10 @@ impl Menus {
         match int_value {
             0 => Self::NewGame,
             1 => Self::CreateRoom,
-            2 => Self::JoinRoom,
+            2 => Self::CreateSingleUseRoom,
+            3 => Self::CreatePublicRoom,
+            4 => Self::JoinRoom,
+            5 => Self::BrowseRooms,
             _ => panic!("Unexpected value received when converting u8 to menus"),
         }
     }
@@ -34,17 +47,25 @@ impl Menus {
         match self {
             Menus::NewGame => "Create a game with random players who are online",
             Menus::CreateRoom => "Create a private room, invite your friends",
-            Menus::JoinRoom => "Join a private room",
+            Menus::CreateSingleUseRoom => {
+                "Create a private room, its invite code can only be used once"
+            }
+            Menus::CreatePublicRoom => "Create a room that anyone can join from the room browser",
+            Menus::JoinRoom => "Join a private room with its invite code",
+            Menus::BrowseRooms => "Browse the public rooms that are waiting for players",
         }
     }
 }
 
-impl ToString for Menus {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Menus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Menus::NewGame => String::from("New Game"),
-            Menus::CreateRoom => String::from("Create Room"),
-            Menus::JoinRoom => String::from("Join Room"),
+            Menus::NewGame => write!(f, "New Game"),
+            Menus::CreateRoom => write!(f, "Create Room"),
+            Menus::CreateSingleUseRoom => write!(f, "Single Use Room"),
+            Menus::CreatePublicRoom => write!(f, "Public Room"),
+            Menus::JoinRoom => write!(f, "Join Room"),
+            Menus::BrowseRooms => write!(f, "Browse Rooms"),
         }
     }
 }
@@ -54,6 +75,8 @@ pub struct Menu {
     input_field: Input,
     helper_label: Paragraph,
     is_input_field_active: bool,
+    keymap: Keymap,
+    theme: Theme,
 }
 
 impl MockComponent for Menu {
@@ -80,7 +103,12 @@ impl MockComponent for Menu {
     }
 
     fn attr(&mut self, attr: tuirealm::Attribute, value: tuirealm::AttrValue) {
-        self.component.attr(attr, value)
+        // The focus goes to the input field while the user is entering the room id
+        if attr == tuirealm::Attribute::Focus && self.is_input_field_active {
+            self.input_field.attr(attr, value)
+        } else {
+            self.component.attr(attr, value)
+        }
     }
 
     fn state(&self) -> tuirealm::State {
@@ -92,84 +120,108 @@ impl MockComponent for Menu {
     }
 }
 
-impl Default for Menu {
-    fn default() -> Self {
-        let choices = [Menus::NewGame, Menus::CreateRoom, Menus::JoinRoom]
-            .iter()
-            .map(|item| item.to_string())
-            .collect::<Vec<_>>();
+/// The helper text is shown next to the menu while the room id is not being entered
+fn get_helper_label(text: &str, theme: &Theme) -> Paragraph {
+    Paragraph::default()
+        .text(&[TextSpan::from(text)])
+        .foreground(theme.text)
+        .borders(theme.get_borders(false).modifiers(BorderType::Rounded))
+}
+
+impl Menu {
+    fn set_input_field_active(&mut self, is_active: bool) {
+        self.is_input_field_active = is_active;
+        self.input_field.attr(
+            tuirealm::Attribute::Focus,
+            tuirealm::AttrValue::Flag(is_active),
+        );
+        self.component.attr(
+            tuirealm::Attribute::Focus,
+            tuirealm::AttrValue::Flag(!is_active),
+        );
+    }
+}
+
+impl Menu {
+    pub fn new(keymap: Keymap, theme: Theme) -> Self {
+        let choices = [
+            Menus::NewGame,
+            Menus::CreateRoom,
+            Menus::CreateSingleUseRoom,
+            Menus::CreatePublicRoom,
+            Menus::JoinRoom,
+            Menus::BrowseRooms,
+        ]
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>();
         let component = Radio::default()
             .choices(&choices)
-            .borders(
-                tuirealm::props::Borders::default()
-                    .modifiers(BorderType::Rounded)
-                    .color(tuirealm::props::Color::Green),
-            )
-            .title("Menu - [ M ]", tuirealm::props::Alignment::Left);
+            .foreground(theme.text)
+            .borders(theme.get_borders(true).modifiers(BorderType::Rounded))
+            .inactive(theme.get_inactive_style())
+            .title(
+                format!("Menu - [ {} ]", keymap.get_keys(Action::FocusMenu)),
+                tuirealm::props::Alignment::Left,
+            );
 
         let input_field = Input::default()
-            .title("Enter room id", tui_layout::Alignment::Left)
-            .borders(
-                tuirealm::props::Borders::default()
-                    .modifiers(BorderType::Rounded)
-                    .color(tuirealm::props::Color::Green),
-            )
-            .input_type(tuirealm::props::InputType::Number);
-
-        let helper_label = Paragraph::default()
-            .text(&[TextSpan::from(
-                "Create a game with random players who are online",
-            )])
-            .borders(tuirealm::props::Borders::default().modifiers(BorderType::Rounded));
+            .title("Enter invite code", tui_layout::Alignment::Left)
+            .foreground(theme.text)
+            .borders(theme.get_borders(true).modifiers(BorderType::Rounded))
+            .inactive(theme.get_inactive_style())
+            .input_type(tuirealm::props::InputType::Text)
+            .input_len(INVITE_INPUT_LENGTH);
+
+        let helper_label = get_helper_label(Menus::NewGame.get_helper_text(), &theme);
 
         Self {
             component,
             input_field,
             helper_label,
             is_input_field_active: false,
+            keymap,
+            theme,
         }
     }
 }
 
 impl Component<Msg, UserEvent> for Menu {
     fn on(&mut self, event: tuirealm::Event<UserEvent>) -> Option<Msg> {
-        let cmd = match event {
-            Event::Keyboard(KeyEvent {
-                code: Key::Left,
-                modifiers: KeyModifiers::NONE,
-            }) => Cmd::Move(tuirealm::command::Direction::Left),
-
-            Event::Keyboard(KeyEvent {
-                code: Key::Right,
-                modifiers: KeyModifiers::NONE,
-            }) => Cmd::Move(tuirealm::command::Direction::Right),
-
-            Event::Keyboard(KeyEvent {
-                code: Key::Enter,
-                modifiers: KeyModifiers::NONE,
-            }) => Cmd::Submit,
-
-            Event::Keyboard(KeyEvent {
-                code: Key::Esc,
-                modifiers: KeyModifiers::NONE,
-            }) => return Some(Msg::AppClose),
-
-            Event::Keyboard(KeyEvent {
-                code: Key::Delete,
-                modifiers: KeyModifiers::NONE,
-            }) => Cmd::Delete,
-
-            Event::Keyboard(KeyEvent {
-                code: Key::Backspace,
-                modifiers: KeyModifiers::NONE,
-            }) => Cmd::Delete,
-
-            Event::Keyboard(KeyEvent {
-                code: Key::Char(character),
-                modifiers: KeyModifiers::NONE,
-            }) => Cmd::Type(character),
-
-            _ => Cmd::None,
+        let Event::Keyboard(key_event) = event else {
+            return None;
+        };
+
+        let cmd = match (self.keymap.get_action(&key_event), key_event) {
+            // Characters are typed into the input field, even if they are bound to an action
+            (
+                _,
+                KeyEvent {
+                    code: Key::Char(character),
+                    modifiers: KeyModifiers::NONE,
+                },
+            ) if self.is_input_field_active => Cmd::Type(character),
+
+            // Back out of the input field, or quit from the menu
+            (Some(Action::Back), _) if self.is_input_field_active => {
+                self.set_input_field_active(false);
+                return Some(Msg::Menu(MenuMessage::MenuChange));
+            }
+            (Some(Action::Back), _) => return Some(Msg::AppClose),
+
+            (Some(Action::Left), _) => Cmd::Move(tuirealm::command::Direction::Left),
+            (Some(Action::Right), _) => Cmd::Move(tuirealm::command::Direction::Right),
+            (Some(Action::Select), _) => Cmd::Submit,
+
+            (
+                _,
+                KeyEvent {
+                    code: Key::Delete | Key::Backspace,
+                    modifiers: KeyModifiers::NONE,
+                },
+            ) => Cmd::Delete,
+
+            _ => return self.keymap.get_global_msg(&key_event, Panel::Menu),
         };
 
         // If the input field is active, then forward the command to input field
@@ -180,9 +232,9 @@ impl Component<Msg, UserEvent> for Menu {
                 CmdResult::Changed(_) => Some(Msg::Menu(MenuMessage::MenuDataChange)),
                 CmdResult::Submit(submit_state) => {
                     let input_state = submit_state.unwrap_one();
-                    if let StateValue::String(room_id) = input_state {
+                    if let StateValue::String(invite_code) = input_state {
                         Some(Msg::Menu(MenuMessage::MenuSelect(
-                            MenuSelection::JoinRoom { room_id },
+                            MenuSelection::JoinWithInvite { invite_code },
                         )))
                     } else {
                         None
@@ -195,12 +247,7 @@ impl Component<Msg, UserEvent> for Menu {
                 tuirealm::command::CmdResult::Changed(_) => {
                     let menu_state = Menus::from_u8(self.component.states.choice as u8);
                     let menu_text = menu_state.get_helper_text();
-                    let helper_text = Paragraph::default()
-                        .text(&[TextSpan::from(menu_text)])
-                        .borders(
-                            tuirealm::props::Borders::default().modifiers(BorderType::Rounded),
-                        );
-                    self.helper_label = helper_text;
+                    self.helper_label = get_helper_label(menu_text, &self.theme);
                     Some(Msg::Menu(MenuMessage::MenuChange))
                 }
                 tuirealm::command::CmdResult::Submit(_) => {
@@ -209,15 +256,19 @@ impl Component<Msg, UserEvent> for Menu {
                     let menu_update = match menu_state {
                         Menus::NewGame => MenuMessage::MenuSelect(MenuSelection::NewGame),
                         Menus::CreateRoom => MenuMessage::MenuSelect(MenuSelection::CreateRoom),
+                        Menus::CreateSingleUseRoom => {
+                            MenuMessage::MenuSelect(MenuSelection::CreateSingleUseRoom)
+                        }
+                        Menus::CreatePublicRoom => {
+                            MenuMessage::MenuSelect(MenuSelection::CreatePublicRoom)
+                        }
                         Menus::JoinRoom => {
-                            self.is_input_field_active = true;
-                            self.input_field
-                                .attr(tuirealm::Attribute::Focus, tuirealm::AttrValue::Flag(true));
-
-                            self.component
-                                .attr(tuirealm::Attribute::Focus, tuirealm::AttrValue::Flag(false));
+                            self.set_input_field_active(true);
                             MenuMessage::MenuChange
                         }
+                        Menus::BrowseRooms => {
+                            return Some(Msg::RoomBrowser(RoomBrowserMessage::Open))
+                        }
                     };
                     Some(Msg::Menu(menu_update))
                 }
@@ -226,3 +277,29 @@ impl Component<Msg, UserEvent> for Menu {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::client::{keymap::KeymapConfig, theme::ThemeConfig};
+
+    fn press(menu: &mut Menu, key: Key) -> Option<Msg> {
+        menu.on(Event::Keyboard(KeyEvent::new(key, KeyModifiers::NONE)))
+    }
+
+    #[test]
+    fn single_use_rooms_are_created_from_the_menu() {
+        let keymap = Keymap::new(&KeymapConfig::default());
+        let mut menu = Menu::new(keymap, Theme::new(&ThemeConfig::default()));
+
+        press(&mut menu, Key::Right);
+        press(&mut menu, Key::Right);
+
+        assert_eq!(
+            press(&mut menu, Key::Enter),
+            Some(Msg::Menu(MenuMessage::MenuSelect(
+                MenuSelection::CreateSingleUseRoom
+            )))
+        );
+    }
+}
@@ -15,7 +15,7 @@ impl Component<Msg, UserEvent> for NetworkReceptor {
     fn on(&mut self, event: tuirealm::Event<UserEvent>) -> Option<Msg> {
         match event {
             tuirealm::Event::User(user_event) => match user_event {
-                UserEvent::RoomCreated { room_id, users } => {
+                UserEvent::RoomCreated { room_id, users, .. } => {
                     let users = users.into_iter().map(Into::into).collect::<Vec<_>>();
 
                     let app_state_update = AppStateUpdate::RoomUpdate { room_id, users };
@@ -28,14 +28,28 @@ impl Component<Msg, UserEvent> for NetworkReceptor {
                     let app_state_update = AppStateUpdate::GameStart { room_id, users };
                     Some(Msg::StateUpdate(app_state_update))
                 }
-                UserEvent::InfoMessage(_) => todo!(),
-                UserEvent::NetworkError(_) => todo!(),
+                // These events are shown by the bottom bar, the details panel and the room browser
+                UserEvent::GlobalStats { .. }
+                | UserEvent::ChatMessage { .. }
+                | UserEvent::RoomList { .. }
+                | UserEvent::ServerShutdown
+                | UserEvent::ConnectionState(_)
+                | UserEvent::GameRejoined { .. }
+                | UserEvent::InfoMessage(_)
+                | UserEvent::NetworkError(_) => None,
                 UserEvent::UserJoined { users } => {
                     let users = users.into_iter().map(Into::into).collect::<Vec<_>>();
 
                     let app_state_update = AppStateUpdate::UserRoomJoin { users };
                     Some(Msg::StateUpdate(app_state_update))
                 }
+                UserEvent::UserLeft { users } => {
+                    let users = users.into_iter().map(Into::into).collect::<Vec<_>>();
+
+                    let app_state_update = AppStateUpdate::UserRoomLeave { users };
+                    Some(Msg::StateUpdate(app_state_update))
+                }
+                UserEvent::RoomLeft => Some(Msg::StateUpdate(AppStateUpdate::RoomLeft)),
             },
             _ => None,
         }
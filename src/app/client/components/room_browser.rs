@@ -0,0 +1,284 @@
+use tui_realm_stdlib::{Paragraph, Table};
+use tuirealm::{
+    command::{Cmd, CmdResult},
+    props::{BorderType, TextSpan},
+    AttrValue, Attribute, Component, Event, MockComponent, State,
+};
+
+use crate::app::client::{
+    keymap::{Action, Keymap},
+    network::types::PublicRoom,
+    theme::Theme,
+    types::{MenuMessage, MenuSelection, Panel, RoomBrowserMessage},
+};
+
+use super::{Msg, UserEvent};
+
+/// The rooms are asked for again after this many ticks of the application, one tick every second
+const REFRESH_INTERVAL_TICKS: u32 = 3;
+
+/// Lists the public rooms that are waiting for players, the list is refreshed while the browser is open
+pub struct RoomBrowser {
+    /// Not set until the server has sent the rooms
+    rooms: Option<Vec<PublicRoom>>,
+    selected_room: usize,
+    ticks_since_refresh: u32,
+    is_open: bool,
+    is_focused: bool,
+    keymap: Keymap,
+    theme: Theme,
+}
+
+impl RoomBrowser {
+    pub fn new(keymap: Keymap, theme: Theme) -> Self {
+        Self {
+            rooms: None,
+            selected_room: 0,
+            ticks_since_refresh: 0,
+            is_open: false,
+            is_focused: false,
+            keymap,
+            theme,
+        }
+    }
+
+    fn get_title(&self) -> String {
+        format!(
+            "Public Rooms - [ {} ] to join, [ {} ] to close",
+            self.keymap.get_keys(Action::Select),
+            self.keymap.get_keys(Action::Back)
+        )
+    }
+
+    /// The same room stays selected when the rooms before it are gone
+    fn set_rooms(&mut self, rooms: Vec<PublicRoom>) {
+        let selected_room_id = self
+            .rooms
+            .as_ref()
+            .and_then(|rooms| rooms.get(self.selected_room))
+            .map(|room| room.room_id.clone());
+
+        self.selected_room = selected_room_id
+            .and_then(|room_id| rooms.iter().position(|room| room.room_id == room_id))
+            .unwrap_or(self.selected_room)
+            .min(rooms.len().saturating_sub(1));
+        self.rooms = Some(rooms);
+    }
+
+    fn on_key(&mut self, action: Action) -> Option<Msg> {
+        let rooms_count = self.rooms.as_ref().map_or(0, Vec::len).max(1);
+
+        match action {
+            Action::Up => self.selected_room = (self.selected_room + rooms_count - 1) % rooms_count,
+            Action::Down => self.selected_room = (self.selected_room + 1) % rooms_count,
+            Action::Select => {
+                let room = self.rooms.as_ref()?.get(self.selected_room)?;
+
+                return Some(Msg::Menu(MenuMessage::MenuSelect(
+                    MenuSelection::JoinRoom {
+                        room_id: room.room_id.clone(),
+                    },
+                )));
+            }
+            Action::Back => return Some(Msg::RoomBrowser(RoomBrowserMessage::Close)),
+            _ => return None,
+        }
+
+        Some(Msg::ReDraw)
+    }
+}
+
+impl MockComponent for RoomBrowser {
+    fn view(&mut self, frame: &mut tuirealm::Frame, area: tuirealm::tui::prelude::Rect) {
+        let message = match &self.rooms {
+            None => "Looking for public rooms",
+            Some(rooms) if rooms.is_empty() => {
+                "No public room is waiting for players, create one from the menu"
+            }
+            Some(rooms) => {
+                let rows = rooms
+                    .iter()
+                    .map(|room| {
+                        vec![
+                            TextSpan::new(room.host_name.as_deref().unwrap_or("-")),
+                            TextSpan::new(format!("{}/{}", room.player_count, room.room_size)),
+                            TextSpan::new(format_age(room.age_seconds)),
+                        ]
+                    })
+                    .collect::<Vec<_>>();
+
+                let mut room_list = Table::default()
+                    .title(self.get_title(), tuirealm::props::Alignment::Left)
+                    .borders(
+                        self.theme
+                            .get_borders(self.is_focused)
+                            .modifiers(BorderType::Rounded),
+                    )
+                    .inactive(self.theme.get_inactive_style())
+                    .foreground(self.theme.text)
+                    .headers(&["Host", "Players", "Waiting for"])
+                    .widths(&[50, 20, 30])
+                    .highlighted_color(self.theme.highlight)
+                    .scroll(true)
+                    .table(rows)
+                    .selected_line(self.selected_room);
+
+                room_list.attr(Attribute::Focus, AttrValue::Flag(self.is_focused));
+                room_list.view(frame, area);
+                return;
+            }
+        };
+
+        let mut paragraph = Paragraph::default()
+            .title(self.get_title(), tuirealm::props::Alignment::Left)
+            .borders(
+                self.theme
+                    .get_borders(self.is_focused)
+                    .modifiers(BorderType::Rounded),
+            )
+            .foreground(self.theme.text)
+            .text(&[TextSpan::from(message)])
+            .alignment(tuirealm::props::Alignment::Center);
+
+        paragraph.attr(Attribute::Focus, AttrValue::Flag(self.is_focused));
+        paragraph.view(frame, area);
+    }
+
+    fn query(&self, _attr: Attribute) -> Option<AttrValue> {
+        None
+    }
+
+    /// The model opens and closes the browser with the display attribute
+    fn attr(&mut self, attr: Attribute, value: AttrValue) {
+        match (attr, value) {
+            (Attribute::Focus, AttrValue::Flag(is_focused)) => self.is_focused = is_focused,
+            (Attribute::Display, AttrValue::Flag(is_open)) => {
+                self.is_open = is_open;
+                self.rooms = None;
+                self.selected_room = 0;
+                self.ticks_since_refresh = 0;
+            }
+            _ => {}
+        }
+    }
+
+    fn state(&self) -> State {
+        State::None
+    }
+
+    fn perform(&mut self, _cmd: Cmd) -> CmdResult {
+        CmdResult::None
+    }
+}
+
+impl Component<Msg, UserEvent> for RoomBrowser {
+    fn on(&mut self, event: Event<UserEvent>) -> Option<Msg> {
+        match event {
+            Event::Tick if self.is_open => {
+                self.ticks_since_refresh += 1;
+                if self.ticks_since_refresh < REFRESH_INTERVAL_TICKS {
+                    return None;
+                }
+
+                self.ticks_since_refresh = 0;
+                Some(Msg::RoomBrowser(RoomBrowserMessage::Refresh))
+            }
+            Event::User(UserEvent::RoomList { rooms }) if self.is_open => {
+                self.set_rooms(rooms);
+                Some(Msg::ReDraw)
+            }
+            // The browser is no longer needed once the user is in a room
+            Event::User(UserEvent::RoomCreated { .. } | UserEvent::GameStart { .. })
+                if self.is_open =>
+            {
+                Some(Msg::RoomBrowser(RoomBrowserMessage::Close))
+            }
+            // The browser listens to all the events, but only handles the keys while focused
+            Event::Keyboard(key_event) if self.is_focused => {
+                match self.keymap.get_action(&key_event) {
+                    Some(action @ (Action::Up | Action::Down | Action::Select | Action::Back)) => {
+                        self.on_key(action)
+                    }
+                    _ => self.keymap.get_global_msg(&key_event, Panel::RoomBrowser),
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Rounded down to the largest unit, such as `2m` or `1h`
+fn format_age(age_seconds: u64) -> String {
+    match age_seconds {
+        0..=59 => format!("{age_seconds}s"),
+        60..=3599 => format!("{}m", age_seconds / 60),
+        _ => format!("{}h", age_seconds / 3600),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::client::{keymap::KeymapConfig, theme::ThemeConfig};
+
+    fn get_room(room_id: &str) -> PublicRoom {
+        PublicRoom {
+            room_id: room_id.to_string(),
+            room_size: 2,
+            player_count: 1,
+            host_name: None,
+            age_seconds: 0,
+        }
+    }
+
+    fn get_rooms(room_ids: &[&str]) -> Vec<PublicRoom> {
+        room_ids.iter().map(|room_id| get_room(room_id)).collect()
+    }
+
+    fn get_browser() -> RoomBrowser {
+        RoomBrowser::new(
+            Keymap::new(&KeymapConfig::default()),
+            Theme::new(&ThemeConfig::default()),
+        )
+    }
+
+    #[test]
+    fn ages_are_rounded_down_to_the_largest_unit() {
+        assert_eq!(format_age(0), "0s");
+        assert_eq!(format_age(59), "59s");
+        assert_eq!(format_age(60), "1m");
+        assert_eq!(format_age(3599), "59m");
+        assert_eq!(format_age(3600), "1h");
+        assert_eq!(format_age(86_400), "24h");
+    }
+
+    #[test]
+    fn the_selected_room_stays_selected_when_the_rooms_change() {
+        let mut browser = get_browser();
+        browser.set_rooms(get_rooms(&["first", "second", "third"]));
+        browser.selected_room = 2;
+
+        // The rooms before the selected room are gone, and a new room is listed first
+        browser.set_rooms(get_rooms(&["new", "third"]));
+        assert_eq!(browser.selected_room, 1);
+
+        browser.set_rooms(get_rooms(&["third", "new"]));
+        assert_eq!(browser.selected_room, 0);
+    }
+
+    #[test]
+    fn the_selection_stays_in_the_list_when_the_selected_room_is_gone() {
+        let mut browser = get_browser();
+        browser.set_rooms(get_rooms(&["first", "second", "third"]));
+        browser.selected_room = 2;
+
+        browser.set_rooms(get_rooms(&["first", "second"]));
+        assert_eq!(browser.selected_room, 1);
+
+        browser.set_rooms(vec![]);
+        assert_eq!(browser.selected_room, 0);
+
+        browser.set_rooms(get_rooms(&["first"]));
+        assert_eq!(browser.selected_room, 0);
+    }
+}
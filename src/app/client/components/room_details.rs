@@ -1,15 +1,32 @@
-use tui_realm_stdlib::{List, Paragraph, Table};
+use std::collections::VecDeque;
+
+use tui_realm_stdlib::{Input, List, Paragraph, Table};
 use tuirealm::{
     command::{Cmd, CmdResult},
-    props::{BorderType, Layout, TextSpan},
+    event::{Key, KeyEvent, KeyModifiers},
+    props::{BorderType, InputType, Layout, TextSpan},
     tui::layout::{Constraint, Rect},
-    Component, MockComponent,
+    AttrValue, Attribute, Component, MockComponent, StateValue,
 };
 
-use crate::app::client::types::UserDetails;
+use crate::app::client::{
+    keymap::{Action, Keymap},
+    theme::Theme,
+    types::{FocusChange, Panel, UserDetails},
+};
 
 use super::{Msg, UserEvent};
 
+/// Set by the model to tell whether the users list or the details have the focus
+pub const USERS_FOCUSED: Attribute = Attribute::Custom("users-focused");
+/// Set by the model to tell whether the chat input has the focus
+pub const CHAT_FOCUSED: Attribute = Attribute::Custom("chat-focused");
+
+/// Only the latest chat messages of the room are kept
+const MAX_CHAT_MESSAGES: usize = 100;
+/// Same as the limit of the server, longer messages are rejected
+const MAX_CHAT_INPUT_LENGTH: usize = 200;
+
 #[derive(Default)]
 pub struct OwnStates {
     pub users: Vec<UserDetails>,
@@ -17,11 +34,16 @@ pub struct OwnStates {
     pub room_details: Option<RoomDetails>,
     pub is_in_waiting_room: bool,
     pub is_in_game: bool,
+    /// Index of the user selected in the users list
+    pub selected_user: usize,
+    /// Chat messages of the room, with the name of the sender
+    pub chat_messages: VecDeque<(String, String)>,
 }
 
 #[derive(Default, Clone)]
 pub struct RoomDetails {
     room_id: String,
+    invite_code: Option<String>,
     max_players: u32,
     pub current_players: usize,
 }
@@ -30,15 +52,19 @@ pub struct CustomLayout {
     room_details: Rect,
     user_list: Rect,
     user_details: Rect,
+    chat_messages: Rect,
+    chat_input: Rect,
 }
 
 impl CustomLayout {
     fn new(main_screen: Rect) -> Self {
         let layout = Layout::default()
             .constraints(&[
+                Constraint::Percentage(20),
                 Constraint::Percentage(25),
-                Constraint::Percentage(50),
-                Constraint::Percentage(25),
+                Constraint::Percentage(20),
+                Constraint::Min(6),
+                Constraint::Length(3),
             ])
             .direction(tuirealm::tui::layout::Direction::Vertical)
             .chunks(main_screen);
@@ -47,6 +73,8 @@ impl CustomLayout {
             room_details: layout[0],
             user_list: layout[1],
             user_details: layout[2],
+            chat_messages: layout[3],
+            chat_input: layout[4],
         }
     }
 }
@@ -56,18 +84,42 @@ pub struct Details {
     room_details: Box<dyn MockComponent>,
     user_list: Option<List>,
     user_information: Option<Table>,
+    chat_input: Input,
     state: OwnStates,
+    keymap: Keymap,
+    theme: Theme,
+    is_focused: bool,
+    is_users_focused: bool,
+    is_chat_focused: bool,
 }
 
 impl MockComponent for Details {
     fn view(&mut self, frame: &mut tuirealm::Frame, area: tuirealm::tui::prelude::Rect) {
-        let room_details = get_room_details(self.state.room_details.clone());
+        let is_details_focused = self.is_focused && !self.is_users_focused && !self.is_chat_focused;
+        let room_details = get_room_details(
+            self.state.room_details.clone(),
+            &self.keymap,
+            &self.theme,
+            is_details_focused,
+        );
         self.room_details = room_details;
 
         if self.state.is_in_waiting_room {
             let layout = CustomLayout::new(area);
 
-            let users_list = get_users_list(self.state.users.clone());
+            // Users might have left the room since the selection was made
+            self.state.selected_user = self
+                .state
+                .selected_user
+                .min(self.state.users.len().saturating_sub(1));
+
+            let users_list = get_users_list(
+                self.state.users.clone(),
+                self.state.selected_user,
+                &self.keymap,
+                &self.theme,
+                self.is_focused && self.is_users_focused,
+            );
 
             let current_selected_user_index = users_list.states.list_index;
             let current_selected_user = self
@@ -77,7 +129,7 @@ impl MockComponent for Details {
                 .expect("Index out of bounds")
                 .clone();
 
-            let user_details = get_user_information_table(current_selected_user);
+            let user_details = get_user_information_table(current_selected_user, &self.theme);
             self.user_information = Some(user_details);
             self.user_list = Some(users_list);
 
@@ -90,8 +142,33 @@ impl MockComponent for Details {
             }
 
             self.room_details.view(frame, layout.room_details);
+
+            let is_chat_focused = self.is_focused && self.is_chat_focused;
+            let mut chat_messages = get_chat_messages(
+                &self.state.chat_messages,
+                &self.keymap,
+                &self.theme,
+                is_chat_focused,
+            );
+            chat_messages.view(frame, layout.chat_messages);
+
+            self.chat_input
+                .attr(Attribute::Focus, AttrValue::Flag(is_chat_focused));
+            self.chat_input.view(frame, layout.chat_input);
         } else {
-            self.room_details.view(frame, area);
+            let chunks = Layout::default()
+                .constraints(&[Constraint::Percentage(50), Constraint::Percentage(50)])
+                .direction(tuirealm::tui::layout::Direction::Vertical)
+                .chunks(area);
+
+            self.room_details.view(frame, chunks[0]);
+
+            let mut global_information = get_global_information(
+                self.state.global_details.clone(),
+                &self.theme,
+                is_details_focused,
+            );
+            global_information.view(frame, chunks[1]);
         }
     }
 
@@ -100,6 +177,19 @@ impl MockComponent for Details {
     }
 
     fn attr(&mut self, attr: tuirealm::Attribute, value: tuirealm::AttrValue) {
+        match (attr, &value) {
+            (Attribute::Focus, AttrValue::Flag(is_focused)) => self.is_focused = *is_focused,
+            (USERS_FOCUSED, AttrValue::Flag(is_users_focused)) => {
+                self.is_users_focused = *is_users_focused;
+                return;
+            }
+            (CHAT_FOCUSED, AttrValue::Flag(is_chat_focused)) => {
+                self.is_chat_focused = *is_chat_focused;
+                return;
+            }
+            _ => {}
+        }
+
         self.room_details.attr(attr, value)
     }
 
@@ -116,14 +206,24 @@ impl MockComponent for Details {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct GlobalDetails {
-    active_games: u16,
-    active_players: u16,
+    active_games: u32,
+    active_players: u32,
 }
 
-fn get_room_details(room_details: Option<RoomDetails>) -> Box<dyn MockComponent> {
-    if let Some(room_details) = room_details {
+fn get_room_details(
+    room_details: Option<RoomDetails>,
+    keymap: &Keymap,
+    theme: &Theme,
+    is_focused: bool,
+) -> Box<dyn MockComponent> {
+    let title = format!(
+        "Room Details - [ {} ]",
+        keymap.get_keys(Action::FocusDetails)
+    );
+
+    let mut room_details: Box<dyn MockComponent> = if let Some(room_details) = room_details {
         let first_row = vec![
             TextSpan::new("Room id"),
             TextSpan::new(room_details.room_id),
@@ -137,41 +237,70 @@ fn get_room_details(room_details: Option<RoomDetails>) -> Box<dyn MockComponent>
             TextSpan::new("Current Players"),
             TextSpan::new(room_details.current_players.to_string()),
         ];
-        let row_information = vec![first_row, second_row, thrid_row];
+        let mut row_information = vec![first_row, second_row, thrid_row];
+
+        if let Some(invite_code) = room_details.invite_code {
+            row_information.push(vec![
+                TextSpan::new("Invite code"),
+                TextSpan::new(invite_code),
+            ]);
+        }
 
         Box::new(
             Table::default()
-                .title("Room Details", tuirealm::props::Alignment::Left)
+                .title(title, tuirealm::props::Alignment::Left)
+                .borders(theme.get_borders(is_focused))
+                .inactive(theme.get_inactive_style())
+                .foreground(theme.text)
                 .table(row_information),
         )
     } else {
         Box::new(
             Paragraph::default()
-                .title("Room Details", tuirealm::props::Alignment::Center)
+                .title(title, tuirealm::props::Alignment::Center)
+                .borders(theme.get_borders(is_focused))
+                .foreground(theme.text)
                 .text(&[TextSpan::from("Join a room to display room information")])
                 .alignment(tuirealm::props::Alignment::Center),
         )
-    }
+    };
+
+    room_details.attr(Attribute::Focus, AttrValue::Flag(is_focused));
+    room_details
 }
 
-fn get_users_list(user_details: Vec<UserDetails>) -> List {
+fn get_users_list(
+    user_details: Vec<UserDetails>,
+    selected_user: usize,
+    keymap: &Keymap,
+    theme: &Theme,
+    is_focused: bool,
+) -> List {
     let user_details = user_details
         .iter()
         .map(|user_details| vec![TextSpan::new(user_details.user_name.clone())])
         .collect::<Vec<_>>();
 
-    List::default()
-        .title("Users - [U]", tuirealm::props::Alignment::Left)
+    let mut users_list = List::default()
+        .title(
+            format!("Users - [ {} ]", keymap.get_keys(Action::FocusUsers)),
+            tuirealm::props::Alignment::Left,
+        )
         .rows(user_details)
-        .borders(tuirealm::props::Borders::default().modifiers(BorderType::Rounded))
+        .borders(theme.get_borders(is_focused).modifiers(BorderType::Rounded))
+        .inactive(theme.get_inactive_style())
+        .foreground(theme.text)
         .rewind(true)
         .scroll(true)
-        .highlighted_color(tuirealm::props::Color::Gray)
-        .selected_line(0)
+        .highlighted_color(theme.highlight)
+        .selected_line(selected_user);
+
+    users_list.attr(Attribute::Focus, AttrValue::Flag(is_focused));
+    users_list
 }
 
 #[allow(dead_code)]
-fn get_user_information_table(user: UserDetails) -> Table {
+fn get_user_information_table(user: UserDetails, theme: &Theme) -> Table {
     // Display all the keys on col 1
     // Display all the values on col 2
     let first_row = vec![TextSpan::new("User Name"), TextSpan::new(user.user_name)];
@@ -184,11 +313,51 @@ fn get_user_information_table(user: UserDetails) -> Table {
 
     Table::default()
         .title("User Information", tuirealm::props::Alignment::Left)
+        .borders(theme.get_borders(false))
+        .inactive(theme.get_inactive_style())
+        .foreground(theme.text)
         .table(row_information)
 }
 
-#[allow(dead_code)]
-fn get_global_information(global_details: GlobalDetails) -> Table {
+/// The latest messages are shown at the bottom, next to the chat input
+fn get_chat_messages(
+    chat_messages: &VecDeque<(String, String)>,
+    keymap: &Keymap,
+    theme: &Theme,
+    is_focused: bool,
+) -> List {
+    let rows = chat_messages
+        .iter()
+        .map(|(user_name, message)| vec![TextSpan::new(format!("{user_name}: {message}"))])
+        .collect::<Vec<_>>();
+
+    let mut chat_list = List::default()
+        .title(
+            format!("Chat - [ {} ]", keymap.get_keys(Action::FocusChat)),
+            tuirealm::props::Alignment::Left,
+        )
+        .rows(rows)
+        .borders(theme.get_borders(is_focused).modifiers(BorderType::Rounded))
+        .inactive(theme.get_inactive_style())
+        .foreground(theme.text)
+        .scroll(true)
+        .selected_line(chat_messages.len().saturating_sub(1));
+
+    chat_list.attr(Attribute::Focus, AttrValue::Flag(is_focused));
+    chat_list
+}
+
+fn get_chat_input(theme: &Theme) -> Input {
+    Input::default()
+        .title("Message", tuirealm::props::Alignment::Left)
+        .foreground(theme.text)
+        .borders(theme.get_borders(true).modifiers(BorderType::Rounded))
+        .inactive(theme.get_inactive_style())
+        .input_type(InputType::Text)
+        .input_len(MAX_CHAT_INPUT_LENGTH)
+}
+
+fn get_global_information(global_details: GlobalDetails, theme: &Theme, is_focused: bool) -> Table {
     // Display all the keys on col 1
     // Display all the values on col 2
     let first_row = vec![
@@ -203,20 +372,79 @@ fn get_global_information(global_details: GlobalDetails) -> Table {
 
     let row_information = vec![first_row, second_row];
 
-    Table::default()
+    let mut global_information = Table::default()
         .title("Global Stats", tuirealm::props::Alignment::Left)
-        .table(row_information)
+        .borders(theme.get_borders(is_focused))
+        .inactive(theme.get_inactive_style())
+        .foreground(theme.text)
+        .table(row_information);
+
+    global_information.attr(Attribute::Focus, AttrValue::Flag(is_focused));
+    global_information
 }
 
-impl Default for Details {
-    fn default() -> Self {
-        let room_details = get_room_details(None);
+impl Details {
+    pub fn new(keymap: Keymap, theme: Theme) -> Self {
+        let room_details = get_room_details(None, &keymap, &theme, false);
 
         Self {
             room_details,
             user_list: None,
             user_information: None,
+            chat_input: get_chat_input(&theme),
             state: OwnStates::default(),
+            keymap,
+            theme,
+            is_focused: false,
+            is_users_focused: false,
+            is_chat_focused: false,
+        }
+    }
+
+    /// Characters are typed into the chat input, even if they are bound to an action
+    fn on_chat_key(&mut self, key_event: KeyEvent) -> Option<Msg> {
+        let cmd = match (self.keymap.get_action(&key_event), key_event) {
+            (
+                _,
+                KeyEvent {
+                    code: Key::Char(character),
+                    modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+                },
+            ) => Cmd::Type(character),
+            (
+                _,
+                KeyEvent {
+                    code: Key::Backspace,
+                    modifiers: KeyModifiers::NONE,
+                },
+            ) => Cmd::Delete,
+            (
+                _,
+                KeyEvent {
+                    code: Key::Delete,
+                    modifiers: KeyModifiers::NONE,
+                },
+            ) => Cmd::Cancel,
+            (Some(Action::Left), _) => Cmd::Move(tuirealm::command::Direction::Left),
+            (Some(Action::Right), _) => Cmd::Move(tuirealm::command::Direction::Right),
+            (Some(Action::Select), _) => Cmd::Submit,
+            (Some(Action::Back), _) => return Some(Msg::Focus(FocusChange::Panel(Panel::Menu))),
+            _ => return self.keymap.get_global_msg(&key_event, Panel::Chat),
+        };
+
+        match self.chat_input.perform(cmd) {
+            CmdResult::Submit(submit_state) => {
+                let StateValue::String(message) = submit_state.unwrap_one() else {
+                    return None;
+                };
+
+                self.chat_input
+                    .attr(Attribute::Value, AttrValue::String(String::new()));
+
+                let message = message.trim().to_string();
+                (!message.is_empty()).then_some(Msg::SendRoomChat(message))
+            }
+            _ => Some(Msg::ReDraw),
         }
     }
 }
@@ -225,13 +453,18 @@ impl Component<Msg, UserEvent> for Details {
     fn on(&mut self, event: tuirealm::Event<UserEvent>) -> Option<Msg> {
         match event {
             tuirealm::Event::User(user_event) => match user_event {
-                UserEvent::RoomCreated { room_id, users } => {
+                UserEvent::RoomCreated {
+                    room_id,
+                    invite_code,
+                    users,
+                } => {
                     let current_players = users.len();
 
                     self.state.users = users.into_iter().map(UserDetails::from).collect::<Vec<_>>();
 
                     let room_details = RoomDetails {
                         room_id,
+                        invite_code,
                         max_players: 2,
                         current_players,
                     };
@@ -241,7 +474,7 @@ impl Component<Msg, UserEvent> for Details {
                     self.state.is_in_waiting_room = true;
                     None
                 }
-                UserEvent::UserJoined { users } => {
+                UserEvent::UserJoined { users } | UserEvent::UserLeft { users } => {
                     if let Some(room_details) = self.state.room_details.as_mut() {
                         room_details.current_players = users.len();
                     }
@@ -253,6 +486,7 @@ impl Component<Msg, UserEvent> for Details {
                 UserEvent::GameStart { room_id, users } => {
                     let room_details = RoomDetails {
                         room_id,
+                        invite_code: None,
                         max_players: 2,
                         current_players: users.len(),
                     };
@@ -263,9 +497,90 @@ impl Component<Msg, UserEvent> for Details {
 
                     None
                 }
-                UserEvent::InfoMessage(_) | UserEvent::NetworkError(_) => None,
+                UserEvent::ChatMessage { user_name, message } => {
+                    if self.state.chat_messages.len() == MAX_CHAT_MESSAGES {
+                        self.state.chat_messages.pop_front();
+                    }
+                    self.state.chat_messages.push_back((user_name, message));
+
+                    Some(Msg::ReDraw)
+                }
+                UserEvent::RoomLeft => {
+                    self.state.room_details = None;
+                    self.state.users.clear();
+                    self.state.chat_messages.clear();
+                    self.state.is_in_waiting_room = false;
+                    self.state.selected_user = 0;
+
+                    None
+                }
+                UserEvent::GlobalStats {
+                    active_players,
+                    active_games,
+                } => {
+                    self.state.global_details = GlobalDetails {
+                        active_games,
+                        active_players,
+                    };
+
+                    None
+                }
+                UserEvent::InfoMessage(_)
+                | UserEvent::NetworkError(_)
+                | UserEvent::ServerShutdown
+                | UserEvent::ConnectionState(_)
+                | UserEvent::GameRejoined { .. }
+                | UserEvent::RoomList { .. } => None,
             },
+            // The details listen to all the events, but only handle the keys while focused
+            tuirealm::Event::Keyboard(key_event) if self.is_focused && self.is_chat_focused => {
+                self.on_chat_key(key_event)
+            }
+            tuirealm::Event::Keyboard(key_event) if self.is_focused => {
+                // The users list wraps around at both ends
+                let users_count = self.state.users.len().max(1);
+                self.state.selected_user = match self.keymap.get_action(&key_event)? {
+                    Action::Up if self.is_users_focused => {
+                        (self.state.selected_user + users_count - 1) % users_count
+                    }
+                    Action::Down if self.is_users_focused => {
+                        (self.state.selected_user + 1) % users_count
+                    }
+                    Action::Back => return Some(Msg::Focus(FocusChange::Panel(Panel::Menu))),
+                    _ => {
+                        let focused_panel = if self.is_users_focused {
+                            Panel::Users
+                        } else {
+                            Panel::Details
+                        };
+                        return self.keymap.get_global_msg(&key_event, focused_panel);
+                    }
+                };
+
+                Some(Msg::ReDraw)
+            }
             _ => None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::client::{keymap::KeymapConfig, theme::ThemeConfig};
+
+    #[test]
+    fn keys_are_only_handled_while_focused() {
+        let keymap = Keymap::new(&KeymapConfig::default());
+        let mut details = Details::new(keymap, Theme::new(&ThemeConfig::default()));
+        let back = tuirealm::Event::Keyboard(KeyEvent::new(Key::Esc, KeyModifiers::NONE));
+
+        assert_eq!(details.on(back.clone()), None);
+
+        details.attr(Attribute::Focus, AttrValue::Flag(true));
+        assert_eq!(
+            details.on(back),
+            Some(Msg::Focus(FocusChange::Panel(Panel::Menu)))
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use tui_realm_stdlib::Radio;
+use tuirealm::{
+    props::{BorderSides, Borders, PropPayload, PropValue},
+    AttrValue, Component, Event, MockComponent,
+};
+
+use crate::app::client::{theme::Theme, types::SideTab};
+
+use super::{Msg, UserEvent};
+
+/// Shows which side panel is visible when the side panels are collapsed into tabs
+///
+/// The layout depends on the size of the terminal, so the tabs also ask for a redraw when the terminal is resized
+#[derive(MockComponent)]
+pub struct Tabs {
+    component: Radio,
+}
+
+impl Tabs {
+    pub fn new(theme: Theme) -> Self {
+        let component = Radio::default()
+            .choices(&["Details", "Help"])
+            .borders(Borders::default().sides(BorderSides::NONE))
+            .foreground(theme.border_focused);
+
+        Self { component }
+    }
+}
+
+/// The value of the tabs component for the selected tab
+pub fn get_tab_value(selected_tab: SideTab) -> AttrValue {
+    let index = match selected_tab {
+        SideTab::Details => 0,
+        SideTab::Navigation => 1,
+    };
+
+    AttrValue::Payload(PropPayload::One(PropValue::Usize(index)))
+}
+
+impl Component<Msg, UserEvent> for Tabs {
+    fn on(&mut self, event: Event<UserEvent>) -> Option<Msg> {
+        match event {
+            Event::WindowResize(..) => Some(Msg::ReDraw),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,200 @@
+/// Keeps track of the panel that receives the keyboard input
+///
+/// The component of the focused panel is made active, so that the component can highlight its borders
+use super::types::{FocusChange, Id, Panel};
+
+/// The order in which the panels are cycled through
+const PANEL_ORDER: [Panel; 6] = [
+    Panel::Menu,
+    Panel::RoomBrowser,
+    Panel::Users,
+    Panel::Chat,
+    Panel::Details,
+    Panel::Log,
+];
+
+impl Panel {
+    pub fn get_component_id(&self) -> Id {
+        match self {
+            Panel::Menu => Id::Menu,
+            Panel::RoomBrowser => Id::RoomBrowser,
+            Panel::Users | Panel::Chat | Panel::Details => Id::RoomDetails,
+            Panel::Log => Id::BottomBar,
+        }
+    }
+}
+
+pub struct FocusManager {
+    focused: Panel,
+    previous: Panel,
+}
+
+impl Default for FocusManager {
+    fn default() -> Self {
+        Self {
+            focused: Panel::Menu,
+            previous: Panel::Menu,
+        }
+    }
+}
+
+impl FocusManager {
+    pub fn get_focused(&self) -> Panel {
+        self.focused
+    }
+
+    /// Move the focus to another panel and return the new focused panel
+    ///
+    /// The users list and the chat can only be focused while the user is in a room, and the room browser
+    /// while it is open
+    pub fn change_focus(
+        &mut self,
+        focus_change: FocusChange,
+        is_in_room: bool,
+        is_browsing_rooms: bool,
+    ) -> Panel {
+        let is_available = |panel: &Panel| match panel {
+            Panel::Users | Panel::Chat => is_in_room,
+            Panel::RoomBrowser => is_browsing_rooms,
+            _ => true,
+        };
+        let panels = PANEL_ORDER.iter().copied();
+
+        let position = PANEL_ORDER
+            .iter()
+            .position(|panel| *panel == self.focused)
+            .unwrap_or_default();
+
+        let next_panel = match focus_change {
+            FocusChange::Next => panels
+                .cycle()
+                .skip(position + 1)
+                .find(|panel| is_available(panel)),
+            FocusChange::Previous => panels
+                .rev()
+                .cycle()
+                .skip(PANEL_ORDER.len() - position)
+                .find(|panel| is_available(panel)),
+            FocusChange::Panel(panel) => Some(panel).filter(is_available),
+            FocusChange::Return => Some(self.previous).filter(is_available),
+        };
+
+        if let Some(next_panel) = next_panel {
+            if next_panel != self.focused {
+                self.previous = self.focused;
+                self.focused = next_panel;
+            }
+        }
+
+        self.focused
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn focus_on(panel: Panel) -> FocusManager {
+        let mut focus_manager = FocusManager::default();
+        focus_manager.change_focus(FocusChange::Panel(panel), true, true);
+        focus_manager
+    }
+
+    #[test]
+    fn next_and_previous_cycle_through_every_panel() {
+        let mut focus_manager = FocusManager::default();
+
+        for panel in PANEL_ORDER.iter().cycle().skip(1).take(PANEL_ORDER.len()) {
+            assert_eq!(
+                focus_manager.change_focus(FocusChange::Next, true, true),
+                *panel
+            );
+        }
+
+        for panel in PANEL_ORDER.iter().rev() {
+            assert_eq!(
+                focus_manager.change_focus(FocusChange::Previous, true, true),
+                *panel
+            );
+        }
+    }
+
+    #[test]
+    fn unavailable_panels_are_skipped() {
+        let mut focus_manager = FocusManager::default();
+
+        assert_eq!(
+            focus_manager.change_focus(FocusChange::Next, false, false),
+            Panel::Details
+        );
+        assert_eq!(
+            focus_manager.change_focus(FocusChange::Next, false, false),
+            Panel::Log
+        );
+        assert_eq!(
+            focus_manager.change_focus(FocusChange::Next, false, false),
+            Panel::Menu
+        );
+        assert_eq!(
+            focus_manager.change_focus(FocusChange::Previous, false, false),
+            Panel::Log
+        );
+        assert_eq!(
+            focus_manager.change_focus(FocusChange::Previous, false, false),
+            Panel::Details
+        );
+        assert_eq!(
+            focus_manager.change_focus(FocusChange::Previous, false, false),
+            Panel::Menu
+        );
+    }
+
+    #[test]
+    fn unavailable_panels_keep_the_focus_unchanged() {
+        let mut focus_manager = FocusManager::default();
+
+        assert_eq!(
+            focus_manager.change_focus(FocusChange::Panel(Panel::Chat), false, true),
+            Panel::Menu
+        );
+        assert_eq!(
+            focus_manager.change_focus(FocusChange::Panel(Panel::RoomBrowser), true, false),
+            Panel::Menu
+        );
+        assert_eq!(
+            focus_manager.change_focus(FocusChange::Panel(Panel::Chat), true, false),
+            Panel::Chat
+        );
+    }
+
+    #[test]
+    fn return_goes_back_to_the_previous_panel() {
+        let mut focus_manager = focus_on(Panel::Users);
+        focus_manager.change_focus(FocusChange::Panel(Panel::Log), true, true);
+
+        assert_eq!(
+            focus_manager.change_focus(FocusChange::Return, true, true),
+            Panel::Users
+        );
+        assert_eq!(
+            focus_manager.change_focus(FocusChange::Return, true, true),
+            Panel::Log
+        );
+
+        // The previous panel is no longer available once the user has left the room
+        focus_manager.change_focus(FocusChange::Panel(Panel::Chat), true, true);
+        focus_manager.change_focus(FocusChange::Panel(Panel::Log), true, true);
+        assert_eq!(
+            focus_manager.change_focus(FocusChange::Return, false, true),
+            Panel::Log
+        );
+
+        // Focusing the same panel again does not replace the previous panel
+        let mut focus_manager = focus_on(Panel::Details);
+        focus_manager.change_focus(FocusChange::Panel(Panel::Details), true, true);
+        assert_eq!(
+            focus_manager.change_focus(FocusChange::Return, true, true),
+            Panel::Menu
+        );
+    }
+}
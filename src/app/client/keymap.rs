@@ -0,0 +1,489 @@
+/// The keymap translates the key events into the actions of the application
+///
+/// The bindings come from a preset, and each action can be rebound in the `[keymap]` section of client.toml
+///
+/// ```toml
+/// [keymap]
+/// preset = "vi"
+///
+/// [keymap.bindings]
+/// toggle_log_panel = ["ctrl+o"]
+/// ```
+use std::collections::HashMap;
+
+use tuirealm::event::{Key, KeyEvent, KeyModifiers};
+
+use super::types::{FocusChange, Msg, Panel};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Left,
+    Right,
+    Up,
+    Down,
+    Select,
+    Back,
+    Quit,
+    FocusNext,
+    FocusPrevious,
+    FocusMenu,
+    FocusUsers,
+    FocusChat,
+    FocusDetails,
+    FocusLog,
+    NextTab,
+    LeaveRoom,
+    ToggleLogPanel,
+    CycleLogFilter,
+    ScrollLogUp,
+    ScrollLogDown,
+}
+
+impl Action {
+    /// All the actions, in the order they are shown in the help
+    const ALL: [Action; 20] = [
+        Action::Left,
+        Action::Right,
+        Action::Up,
+        Action::Down,
+        Action::Select,
+        Action::Back,
+        Action::Quit,
+        Action::FocusNext,
+        Action::FocusPrevious,
+        Action::FocusMenu,
+        Action::FocusUsers,
+        Action::FocusChat,
+        Action::FocusDetails,
+        Action::FocusLog,
+        Action::NextTab,
+        Action::LeaveRoom,
+        Action::ToggleLogPanel,
+        Action::CycleLogFilter,
+        Action::ScrollLogUp,
+        Action::ScrollLogDown,
+    ];
+
+    fn get_description(&self) -> &'static str {
+        match self {
+            Action::Left => "Previous menu",
+            Action::Right => "Next menu",
+            Action::Up => "Previous user",
+            Action::Down => "Next user",
+            Action::Select => "Select",
+            Action::Back => "Back",
+            Action::Quit => "Quit",
+            Action::FocusNext => "Next panel",
+            Action::FocusPrevious => "Previous panel",
+            Action::FocusMenu => "Menu",
+            Action::FocusUsers => "Users",
+            Action::FocusChat => "Room chat",
+            Action::FocusDetails => "Details",
+            Action::FocusLog => "Network logs",
+            Action::NextTab => "Switch details and help",
+            Action::LeaveRoom => "Leave room",
+            Action::ToggleLogPanel => "Toggle network logs",
+            Action::CycleLogFilter => "Filter logs",
+            Action::ScrollLogUp => "Scroll logs up",
+            Action::ScrollLogDown => "Scroll logs down",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeymapPreset {
+    #[default]
+    Default,
+    Vi,
+    /// `ctrl+f` moves forward as in emacs, so the log filter is cycled with `ctrl+s` instead
+    Emacs,
+}
+
+impl KeymapPreset {
+    fn get_bindings(&self, action: Action) -> &'static [&'static str] {
+        // The bindings of the panels and of the room are the same in every preset
+        match action {
+            Action::FocusNext => return &["tab"],
+            Action::FocusPrevious => return &["shift+tab"],
+            Action::FocusMenu => return &["alt+m"],
+            Action::FocusUsers => return &["alt+u"],
+            Action::FocusChat => return &["alt+c"],
+            Action::FocusDetails => return &["alt+d"],
+            Action::FocusLog => return &["alt+l"],
+            Action::NextTab => return &["alt+t"],
+            Action::LeaveRoom => return &["ctrl+x"],
+            _ => {}
+        }
+
+        match (self, action) {
+            (KeymapPreset::Default, Action::Left) => &["left"],
+            (KeymapPreset::Default, Action::Right) => &["right"],
+            (KeymapPreset::Default, Action::Up) => &["up"],
+            (KeymapPreset::Default, Action::Down) => &["down"],
+            (KeymapPreset::Default, Action::Select) => &["enter"],
+            (KeymapPreset::Default, Action::Back) => &["esc"],
+            (KeymapPreset::Default, Action::Quit) => &["ctrl+c"],
+            (KeymapPreset::Default, Action::ToggleLogPanel) => &["ctrl+l"],
+            (KeymapPreset::Default, Action::CycleLogFilter) => &["ctrl+f"],
+            (KeymapPreset::Default, Action::ScrollLogUp) => &["pageup"],
+            (KeymapPreset::Default, Action::ScrollLogDown) => &["pagedown"],
+
+            (KeymapPreset::Vi, Action::Left) => &["h", "left"],
+            (KeymapPreset::Vi, Action::Right) => &["l", "right"],
+            (KeymapPreset::Vi, Action::Up) => &["k", "up"],
+            (KeymapPreset::Vi, Action::Down) => &["j", "down"],
+            (KeymapPreset::Vi, Action::Select) => &["enter"],
+            (KeymapPreset::Vi, Action::Back) => &["esc"],
+            (KeymapPreset::Vi, Action::Quit) => &["q", "ctrl+c"],
+            (KeymapPreset::Vi, Action::ToggleLogPanel) => &["ctrl+l"],
+            (KeymapPreset::Vi, Action::CycleLogFilter) => &["ctrl+f"],
+            (KeymapPreset::Vi, Action::ScrollLogUp) => &["ctrl+u", "pageup"],
+            (KeymapPreset::Vi, Action::ScrollLogDown) => &["ctrl+d", "pagedown"],
+
+            (KeymapPreset::Emacs, Action::Left) => &["ctrl+b", "left"],
+            (KeymapPreset::Emacs, Action::Right) => &["ctrl+f", "right"],
+            (KeymapPreset::Emacs, Action::Up) => &["ctrl+p", "up"],
+            (KeymapPreset::Emacs, Action::Down) => &["ctrl+n", "down"],
+            (KeymapPreset::Emacs, Action::Select) => &["enter", "ctrl+m"],
+            (KeymapPreset::Emacs, Action::Back) => &["ctrl+g", "esc"],
+            (KeymapPreset::Emacs, Action::Quit) => &["ctrl+q", "ctrl+c"],
+            (KeymapPreset::Emacs, Action::ToggleLogPanel) => &["ctrl+l"],
+            (KeymapPreset::Emacs, Action::CycleLogFilter) => &["ctrl+s"],
+            (KeymapPreset::Emacs, Action::ScrollLogUp) => &["alt+v", "pageup"],
+            (KeymapPreset::Emacs, Action::ScrollLogDown) => &["ctrl+v", "pagedown"],
+
+            (_, Action::FocusNext)
+            | (_, Action::FocusPrevious)
+            | (_, Action::FocusMenu)
+            | (_, Action::FocusUsers)
+            | (_, Action::FocusChat)
+            | (_, Action::FocusDetails)
+            | (_, Action::FocusLog)
+            | (_, Action::NextTab)
+            | (_, Action::LeaveRoom) => &[],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct KeymapConfig {
+    #[serde(default)]
+    pub preset: KeymapPreset,
+    /// The keys of an action listed here replace the keys of the preset for that action
+    #[serde(default)]
+    pub bindings: HashMap<Action, Vec<String>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    /// The keys bound to each action, in the order of `Action::ALL`
+    bindings: Vec<(Action, Vec<KeyEvent>)>,
+    actions: HashMap<KeyEvent, Action>,
+}
+
+impl Keymap {
+    pub fn new(config: &KeymapConfig) -> Self {
+        let mut bindings = Vec::with_capacity(Action::ALL.len());
+        let mut actions = HashMap::new();
+
+        for action in Action::ALL {
+            let keys = match config.bindings.get(&action) {
+                Some(keys) => keys.iter().map(String::as_str).collect::<Vec<_>>(),
+                None => config.preset.get_bindings(action).to_vec(),
+            };
+
+            let mut key_events = Vec::with_capacity(keys.len());
+
+            for key in keys {
+                let Some(key_event) = parse_key(key) else {
+                    tracing::warn!("Invalid key {key} for the action {action:?}, skipping it");
+                    continue;
+                };
+
+                if let Some(bound_action) = actions.get(&key_event) {
+                    tracing::warn!(
+                        "The key {key} is already bound to {bound_action:?}, skipping it for {action:?}"
+                    );
+                    continue;
+                }
+
+                actions.insert(key_event, action);
+                key_events.push(key_event);
+            }
+
+            bindings.push((action, key_events));
+        }
+
+        Self { bindings, actions }
+    }
+
+    /// Get the action bound to the key, if any
+    pub fn get_action(&self, key_event: &KeyEvent) -> Option<Action> {
+        self.actions.get(&normalize_key(*key_event)).copied()
+    }
+
+    /// The keys bound to the action, shown in the titles of the panels
+    pub fn get_keys(&self, action: Action) -> String {
+        self.bindings
+            .iter()
+            .find(|(bound_action, _)| *bound_action == action)
+            .map(|(_, key_events)| format_keys(key_events))
+            .unwrap_or_default()
+    }
+
+    /// The keys and the description of every bound action, used to show the help
+    pub fn get_help(&self) -> Vec<(String, &'static str)> {
+        self.bindings
+            .iter()
+            .filter(|(_, key_events)| !key_events.is_empty())
+            .map(|(action, key_events)| (format_keys(key_events), action.get_description()))
+            .collect()
+    }
+
+    /// Get the message of the actions that work the same in every panel
+    pub fn get_global_msg(&self, key_event: &KeyEvent, focused_panel: Panel) -> Option<Msg> {
+        let focus_change = match self.get_action(key_event)? {
+            Action::Quit => return Some(Msg::AppClose),
+            Action::LeaveRoom => return Some(Msg::LeaveRoom),
+            Action::NextTab => return Some(Msg::NextTab),
+            Action::FocusNext => FocusChange::Next,
+            Action::FocusPrevious => FocusChange::Previous,
+            Action::FocusMenu => FocusChange::Panel(Panel::Menu),
+            Action::FocusUsers => FocusChange::Panel(Panel::Users),
+            Action::FocusChat => FocusChange::Panel(Panel::Chat),
+            Action::FocusDetails => FocusChange::Panel(Panel::Details),
+            Action::FocusLog => FocusChange::Panel(Panel::Log),
+            Action::ToggleLogPanel if focused_panel == Panel::Log => FocusChange::Return,
+            Action::ToggleLogPanel => FocusChange::Panel(Panel::Log),
+            _ => return None,
+        };
+
+        Some(Msg::Focus(focus_change))
+    }
+}
+
+fn format_keys(key_events: &[KeyEvent]) -> String {
+    key_events
+        .iter()
+        .map(format_key)
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
+/// The shift modifier is already part of the character and of the back tab key, so it is ignored
+fn normalize_key(key_event: KeyEvent) -> KeyEvent {
+    match key_event.code {
+        Key::Char(_) | Key::BackTab => KeyEvent::new(
+            key_event.code,
+            key_event.modifiers.difference(KeyModifiers::SHIFT),
+        ),
+        _ => key_event,
+    }
+}
+
+/// Parse a key such as `q`, `enter`, `ctrl+l` or `shift+tab`
+fn parse_key(key: &str) -> Option<KeyEvent> {
+    let key = key.trim();
+    let mut parts = key.split('+').collect::<Vec<_>>();
+    // The plus key itself is written as `+`, so the last part can be empty
+    let code = match parts.pop()? {
+        "" if parts.last() == Some(&"") => {
+            parts.pop();
+            "+"
+        }
+        code => code,
+    };
+
+    let mut modifiers = KeyModifiers::NONE;
+    for modifier in parts {
+        modifiers |= match modifier.to_lowercase().as_str() {
+            "ctrl" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return None,
+        };
+    }
+
+    // The names of the keys are case insensitive, but the characters are not
+    let code = match code.to_lowercase().as_str() {
+        "enter" => Key::Enter,
+        "esc" => Key::Esc,
+        "tab" if modifiers.contains(KeyModifiers::SHIFT) => Key::BackTab,
+        "tab" => Key::Tab,
+        "backtab" => Key::BackTab,
+        "backspace" => Key::Backspace,
+        "delete" => Key::Delete,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "space" => Key::Char(' '),
+        _ => {
+            let mut chars = code.chars();
+            match (chars.next(), chars.next()) {
+                (Some(character), None) => Key::Char(character),
+                _ => return None,
+            }
+        }
+    };
+
+    Some(normalize_key(KeyEvent::new(code, modifiers)))
+}
+
+fn format_key(key_event: &KeyEvent) -> String {
+    let code = match key_event.code {
+        Key::Enter => "Enter".to_string(),
+        Key::Esc => "Esc".to_string(),
+        Key::Tab => "Tab".to_string(),
+        Key::BackTab => "Shift+Tab".to_string(),
+        Key::Backspace => "Backspace".to_string(),
+        Key::Delete => "Delete".to_string(),
+        Key::Left => "Left".to_string(),
+        Key::Right => "Right".to_string(),
+        Key::Up => "Up".to_string(),
+        Key::Down => "Down".to_string(),
+        Key::Home => "Home".to_string(),
+        Key::End => "End".to_string(),
+        Key::PageUp => "PgUp".to_string(),
+        Key::PageDown => "PgDn".to_string(),
+        Key::Char(' ') => "Space".to_string(),
+        Key::Char(character) if key_event.modifiers.is_empty() => character.to_string(),
+        Key::Char(character) => character.to_ascii_uppercase().to_string(),
+        code => format!("{code:?}"),
+    };
+
+    let mut modifiers = String::new();
+    if key_event.modifiers.contains(KeyModifiers::CONTROL) {
+        modifiers.push_str("Ctrl+");
+    }
+    if key_event.modifiers.contains(KeyModifiers::ALT) {
+        modifiers.push_str("Alt+");
+    }
+
+    format!("{modifiers}{code}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRESETS: [KeymapPreset; 3] =
+        [KeymapPreset::Default, KeymapPreset::Vi, KeymapPreset::Emacs];
+
+    fn key(code: Key, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn keys_are_parsed_with_their_modifiers() {
+        assert_eq!(
+            parse_key("q"),
+            Some(key(Key::Char('q'), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            parse_key(" Enter "),
+            Some(key(Key::Enter, KeyModifiers::NONE))
+        );
+        assert_eq!(
+            parse_key("ctrl+l"),
+            Some(key(Key::Char('l'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            parse_key("Ctrl+Alt+PageUp"),
+            Some(key(Key::PageUp, KeyModifiers::CONTROL | KeyModifiers::ALT))
+        );
+        assert_eq!(
+            parse_key("space"),
+            Some(key(Key::Char(' '), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            parse_key("+"),
+            Some(key(Key::Char('+'), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            parse_key("ctrl++"),
+            Some(key(Key::Char('+'), KeyModifiers::CONTROL))
+        );
+    }
+
+    #[test]
+    fn the_shift_modifier_is_part_of_the_key() {
+        assert_eq!(
+            parse_key("shift+tab"),
+            Some(key(Key::BackTab, KeyModifiers::NONE))
+        );
+        assert_eq!(parse_key("backtab"), parse_key("shift+tab"));
+        assert_eq!(
+            parse_key("Q"),
+            Some(key(Key::Char('Q'), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            normalize_key(key(Key::Char('Q'), KeyModifiers::SHIFT)),
+            key(Key::Char('Q'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            normalize_key(key(Key::Up, KeyModifiers::SHIFT)),
+            key(Key::Up, KeyModifiers::SHIFT)
+        );
+    }
+
+    #[test]
+    fn invalid_keys_are_rejected() {
+        for invalid_key in ["", "ctrl+", "super+q", "qq", "ctrl+enterr"] {
+            assert_eq!(parse_key(invalid_key), None, "{invalid_key} is not a key");
+        }
+    }
+
+    #[test]
+    fn every_preset_binds_every_action_to_distinct_keys() {
+        for preset in PRESETS {
+            let keymap = Keymap::new(&KeymapConfig {
+                preset,
+                bindings: HashMap::new(),
+            });
+
+            for action in Action::ALL {
+                let keys = preset.get_bindings(action);
+                let (_, key_events) = keymap
+                    .bindings
+                    .iter()
+                    .find(|(bound_action, _)| *bound_action == action)
+                    .unwrap();
+
+                assert!(!keys.is_empty(), "{action:?} is not bound in {preset:?}");
+                assert_eq!(
+                    key_events.len(),
+                    keys.len(),
+                    "A key of {action:?} is skipped in {preset:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn the_bindings_replace_the_keys_of_the_preset() {
+        let keymap = Keymap::new(&KeymapConfig {
+            preset: KeymapPreset::Emacs,
+            bindings: HashMap::from([(Action::CycleLogFilter, vec!["alt+f".to_string()])]),
+        });
+
+        assert_eq!(
+            keymap.get_action(&key(Key::Char('f'), KeyModifiers::ALT)),
+            Some(Action::CycleLogFilter)
+        );
+        assert_eq!(
+            keymap.get_action(&key(Key::Char('s'), KeyModifiers::CONTROL)),
+            None
+        );
+        assert_eq!(
+            keymap.get_action(&key(Key::Char('f'), KeyModifiers::CONTROL)),
+            Some(Action::Right)
+        );
+    }
+}
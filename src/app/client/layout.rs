@@ -1,4 +1,7 @@
 /// Divide the screen real estate into various chunks, each with one specific purpose
+///
+/// The layout adapts to small terminals: below `COMPACT_WIDTH` columns the side panels are collapsed into tabs,
+/// and below `MIN_HEIGHT_FOR_NAVIGATION` rows the navigation panel is hidden
 // ┌───────────────────────────────────────────────────────────────────────────────────────────────────┐
 // │                                                                                                   │
 // │ ┌───────────────────────────────────────────────────────────────────────────────────────────────┐ │
@@ -32,46 +35,238 @@
 // └───────────────────────────────────────────────────────────────────────────────────────────────────┘
 use tuirealm::tui::layout::{Constraint, Direction, Layout, Rect};
 
+use super::types::SideTab;
+
+/// Height of the bottom bar when only the latest network message is shown
+const BOTTOM_BAR_HEIGHT: u16 = 3;
+/// Height of the bottom bar when it is expanded into the network log panel
+const LOG_PANEL_HEIGHT: u16 = 12;
+/// Below this width the side panels take the whole width, one at a time
+const COMPACT_WIDTH: u16 = 100;
+/// The side panels are never narrower than this when they are next to the action area
+const SIDE_PANEL_MIN_WIDTH: u16 = 32;
+/// Below this height there is only room for the details in the side panels
+const MIN_HEIGHT_FOR_NAVIGATION: u16 = 30;
+
+#[derive(Debug)]
 pub struct CustomLayout {
     pub menu: Rect,
-    pub action_area: Rect,
-    pub details: Rect,
-    pub navigation: Rect,
+    /// Hidden on narrow terminals, where the side panels take its place
+    pub action_area: Option<Rect>,
+    /// Hidden while the navigation tab is selected
+    pub details: Option<Rect>,
+    /// Hidden on short terminals, and while the details tab is selected
+    pub navigation: Option<Rect>,
+    /// Only shown when the side panels are collapsed into tabs
+    pub tabs: Option<Rect>,
     pub bottom_bar: Rect,
 }
 
 impl CustomLayout {
-    pub fn new(main_screen_area: Rect) -> Self {
+    pub fn new(main_screen_area: Rect, is_log_panel_expanded: bool, selected_tab: SideTab) -> Self {
+        let bottom_bar_height = if is_log_panel_expanded {
+            // The log panel leaves at least half of the screen to the other panels
+            LOG_PANEL_HEIGHT.min(main_screen_area.height / 2)
+        } else {
+            BOTTOM_BAR_HEIGHT
+        };
+
         let main_chunks = Layout::default()
             .direction(Direction::Vertical)
             .margin(1)
             .constraints(
                 [
-                    Constraint::Length(3), // Menu
-                    Constraint::Min(10),   // Action area
-                    Constraint::Length(3), // Bottom bar
+                    Constraint::Length(3),                 // Menu
+                    Constraint::Min(0),                    // Side panels and action area
+                    Constraint::Length(bottom_bar_height), // Bottom bar
                 ]
                 .as_ref(),
             )
             .split(main_screen_area);
 
         let middle_chunk = main_chunks[1];
+        let is_navigation_visible = main_screen_area.height >= MIN_HEIGHT_FOR_NAVIGATION;
+
+        let mut layout = Self {
+            menu: main_chunks[0],
+            action_area: None,
+            details: None,
+            navigation: None,
+            tabs: None,
+            bottom_bar: main_chunks[2],
+        };
+
+        if main_screen_area.width < COMPACT_WIDTH {
+            if !is_navigation_visible {
+                layout.details = Some(middle_chunk);
+                return layout;
+            }
+
+            let tab_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(0)])
+                .split(middle_chunk);
+
+            layout.tabs = Some(tab_chunks[0]);
+            match selected_tab {
+                SideTab::Details => layout.details = Some(tab_chunks[1]),
+                SideTab::Navigation => layout.navigation = Some(tab_chunks[1]),
+            }
+
+            return layout;
+        }
+
+        let side_panel_width = (middle_chunk.width / 5).max(SIDE_PANEL_MIN_WIDTH);
         let middle_parts = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(20), Constraint::Percentage(80)])
+            .constraints([Constraint::Length(side_panel_width), Constraint::Min(0)])
             .split(middle_chunk);
 
-        let middle_first_half_split = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
-            .split(middle_parts[0]);
+        layout.action_area = Some(middle_parts[1]);
 
-        Self {
-            menu: main_chunks[0],
-            action_area: middle_parts[1],
-            details: middle_first_half_split[0],
-            navigation: middle_first_half_split[1],
-            bottom_bar: main_chunks[2],
+        if is_navigation_visible {
+            let middle_first_half_split = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
+                .split(middle_parts[0]);
+
+            layout.details = Some(middle_first_half_split[0]);
+            layout.navigation = Some(middle_first_half_split[1]);
+        } else {
+            layout.details = Some(middle_parts[0]);
+        }
+
+        layout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZES: [(u16, u16); 10] = [
+        (20, 8),
+        (40, 12),
+        (80, 24),
+        (80, 40),
+        (99, 30),
+        (100, 29),
+        (100, 30),
+        (120, 24),
+        (200, 50),
+        (400, 120),
+    ];
+
+    fn get_visible_areas(layout: &CustomLayout) -> Vec<Rect> {
+        [
+            Some(layout.menu),
+            layout.action_area,
+            layout.details,
+            layout.navigation,
+            layout.tabs,
+            Some(layout.bottom_bar),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    #[test]
+    fn areas_stay_inside_the_screen_without_overlapping() {
+        for (width, height) in SIZES {
+            for is_log_panel_expanded in [false, true] {
+                for selected_tab in [SideTab::Details, SideTab::Navigation] {
+                    let screen = Rect::new(0, 0, width, height);
+                    let layout = CustomLayout::new(screen, is_log_panel_expanded, selected_tab);
+                    let areas = get_visible_areas(&layout);
+
+                    for (index, area) in areas.iter().enumerate() {
+                        assert_eq!(
+                            screen.union(*area),
+                            screen,
+                            "{area:?} is outside of {screen:?}"
+                        );
+
+                        for other_area in &areas[index + 1..] {
+                            assert!(
+                                !area.intersects(*other_area),
+                                "{area:?} overlaps {other_area:?} on {screen:?}"
+                            );
+                        }
+                    }
+                }
+            }
         }
     }
+
+    #[test]
+    fn side_panels_are_next_to_the_action_area_on_wide_terminals() {
+        let layout = CustomLayout::new(Rect::new(0, 0, 200, 50), false, SideTab::Details);
+
+        let action_area = layout.action_area.unwrap();
+        let details = layout.details.unwrap();
+        let navigation = layout.navigation.unwrap();
+
+        assert!(layout.tabs.is_none());
+        assert_eq!(details.x, navigation.x);
+        assert_eq!(details.right(), action_area.x);
+        assert_eq!(action_area.right(), 199);
+    }
+
+    #[test]
+    fn side_panels_keep_a_readable_width() {
+        for width in [COMPACT_WIDTH, 120, 140] {
+            let layout = CustomLayout::new(Rect::new(0, 0, width, 40), false, SideTab::Details);
+
+            assert_eq!(layout.details.unwrap().width, SIDE_PANEL_MIN_WIDTH);
+        }
+    }
+
+    #[test]
+    fn side_panels_collapse_into_tabs_on_narrow_terminals() {
+        let screen = Rect::new(0, 0, 80, 40);
+
+        let layout = CustomLayout::new(screen, false, SideTab::Details);
+        let tabs = layout.tabs.unwrap();
+        let details = layout.details.unwrap();
+
+        assert!(layout.action_area.is_none());
+        assert!(layout.navigation.is_none());
+        assert_eq!(tabs.height, 1);
+        assert_eq!(details.width, tabs.width);
+        assert_eq!(details.y, tabs.bottom());
+
+        let layout = CustomLayout::new(screen, false, SideTab::Navigation);
+
+        assert!(layout.details.is_none());
+        assert_eq!(layout.navigation, Some(details));
+    }
+
+    #[test]
+    fn navigation_is_hidden_on_short_terminals() {
+        for (width, height) in [(80, 24), (120, 24), (200, MIN_HEIGHT_FOR_NAVIGATION - 1)] {
+            let screen = Rect::new(0, 0, width, height);
+
+            for selected_tab in [SideTab::Details, SideTab::Navigation] {
+                let layout = CustomLayout::new(screen, false, selected_tab);
+
+                assert!(layout.navigation.is_none(), "{screen:?}");
+                assert!(layout.tabs.is_none(), "{screen:?}");
+                assert!(layout.details.is_some(), "{screen:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn log_panel_leaves_room_for_the_other_panels() {
+        let layout = CustomLayout::new(Rect::new(0, 0, 80, 24), true, SideTab::Details);
+        assert_eq!(layout.bottom_bar.height, 12);
+        assert_eq!(layout.details.unwrap().height, 7);
+
+        let layout = CustomLayout::new(Rect::new(0, 0, 80, 16), true, SideTab::Details);
+        assert_eq!(layout.bottom_bar.height, 8);
+
+        let layout = CustomLayout::new(Rect::new(0, 0, 200, 50), true, SideTab::Details);
+        assert_eq!(layout.bottom_bar.height, LOG_PANEL_HEIGHT);
+    }
 }
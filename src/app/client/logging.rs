@@ -0,0 +1,45 @@
+/// The client cannot log to stdout while the terminal is used by the interface,
+/// so the logs are written to a file in local storage instead
+use tracing_appender::{non_blocking::WorkerGuard, rolling};
+use tracing_subscriber::EnvFilter;
+
+use crate::app::utils;
+
+/// Directory of the log files, the file of the current day is named `client.log.<date>`
+const LOG_DIRECTORY: &str = "~/.local/state/blazer";
+const LOG_FILE_PREFIX: &str = "client.log";
+/// Number of daily log files kept, older files are removed on rotation
+const MAX_LOG_FILES: usize = 7;
+
+/// Install the global tracing subscriber that writes json lines to a daily rotated file
+///
+/// The returned guard flushes the pending logs when dropped, so it must be kept until the client exits
+pub fn init_tracing(log_level: &str) -> Option<WorkerGuard> {
+    let env_filter = EnvFilter::try_new(log_level).unwrap_or_else(|error| {
+        eprintln!("Invalid log level {log_level}: {error}");
+        EnvFilter::new("info")
+    });
+
+    let file_appender = rolling::Builder::new()
+        .rotation(rolling::Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(utils::replace_home_dir(LOG_DIRECTORY))
+        .map_err(|error| eprintln!("Cannot create the log file: {error}"))
+        .ok()?;
+
+    let (writer, guard) = tracing_appender::non_blocking(file_appender);
+
+    tracing_subscriber::fmt()
+        .with_env_filter(env_filter)
+        .with_writer(writer)
+        .with_ansi(false)
+        .with_file(true)
+        .with_line_number(true)
+        .json()
+        .with_current_span(true)
+        .with_span_list(false)
+        .init();
+
+    Some(guard)
+}
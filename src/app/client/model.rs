@@ -1,22 +1,31 @@
 /// This file contains the application model
-use std::sync::mpsc;
 use std::time::Duration;
 
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
 use tuirealm::terminal::TerminalBridge;
 use tuirealm::{Application, EventListenerCfg, Update};
 
 use crate::app::client::{
     components,
-    types::{self, Id, Msg},
+    types::{self, AppStateUpdate, FocusChange, Id, Msg, Panel, RoomBrowserMessage, SideTab},
 };
 
 use super::{
+    focus::FocusManager,
+    keymap::Keymap,
     layout,
-    network::{types::UserEvent, NetworkClient},
+    network::{
+        types::{ClientError, UserEvent},
+        NetworkClient, NetworkEvents,
+    },
+    theme::Theme,
 };
 
 use super::network;
 use super::types::ClientConfig;
+use crate::app::server::grpc::invite_code;
 
 pub struct Model {
     /// Application
@@ -30,8 +39,22 @@ pub struct Model {
     pub terminal: TerminalBridge,
     /// State of the application
     pub state: types::AppState,
+    /// The runtime that owns the network client
+    pub runtime: tokio::runtime::Runtime,
+    /// Stops all the tasks of the network client
+    pub network_cancellation_token: CancellationToken,
+    /// Used to show the errors of the application along with the network events
+    pub network_event_sender: mpsc::Sender<UserEvent>,
     /// In order to safely close any open connections
-    pub network_join_handler: Option<std::thread::JoinHandle<()>>,
+    pub network_join_handler: Option<tokio::task::JoinHandle<()>>,
+    /// The panel that receives the keyboard input
+    pub focus_manager: FocusManager,
+    /// The side panel that is visible when the side panels are collapsed into tabs
+    pub selected_tab: SideTab,
+    /// Whether the side panels were collapsed into tabs in the last drawn layout
+    pub has_side_tabs: bool,
+    /// The room browser is drawn in the action area while it is open
+    pub is_browsing_rooms: bool,
 }
 
 #[derive(clap::Parser, Debug)]
@@ -41,28 +64,55 @@ pub struct ClientArgs {
     /// If not passed, then use the existing user key in ./local/state/blazerapp.toml
     #[arg(short, long, default_value_t = false)]
     pub create_guest: bool,
+    /// Join the room of an invite once connected, as a `blazer://join/<code>` link or as the code alone
+    #[arg(value_parser = parse_join_link)]
+    pub join_link: Option<String>,
+}
+
+fn parse_join_link(join_link: &str) -> Result<String, String> {
+    let invite_code = join_link
+        .strip_prefix(invite_code::JOIN_LINK_PREFIX)
+        .unwrap_or(join_link);
+
+    invite_code::normalize_invite_code(invite_code)
+        .ok_or_else(|| format!("{join_link} is not a valid invite code or join link"))
 }
 
 impl Model {
     pub fn new(config: ClientConfig, args: ClientArgs) -> Self {
-        let (grpc_sender, grpc_receiver) = mpsc::channel::<network::types::Request>();
-        // start the network client
+        let runtime = tokio::runtime::Runtime::new().expect("Cannot start the tokio runtime");
+
+        let (grpc_sender, grpc_receiver) =
+            mpsc::channel::<network::types::Request>(network::REQUEST_CHANNEL_CAPACITY);
+        let (event_sender, event_receiver) =
+            mpsc::channel::<UserEvent>(network::EVENT_CHANNEL_CAPACITY);
 
-        let mut network_client = NetworkClient::default();
-        let cloned_network_client = network_client.clone();
+        let keymap = Keymap::new(&config.keymap);
+        let theme = Theme::new(&config.theme);
 
-        let join_handler = std::thread::spawn(move || {
-            network_client.start_network_client(grpc_receiver, config, args)
-        });
+        // start the network client
+        let network_cancellation_token = CancellationToken::new();
+        let network_client =
+            NetworkClient::new(event_sender.clone(), network_cancellation_token.clone());
+
+        let join_handler =
+            runtime.spawn(network_client.start_network_client(grpc_receiver, config, args));
 
         Self {
-            app: Self::init_app(cloned_network_client),
+            app: Self::init_app(NetworkEvents::new(event_receiver), keymap, theme),
             grpc_channel: grpc_sender,
             quit: false,
             redraw: true,
             terminal: TerminalBridge::new().expect("Cannot initialize terminal"),
             state: types::AppState::default(),
+            runtime,
+            network_cancellation_token,
+            network_event_sender: event_sender,
             network_join_handler: Some(join_handler),
+            focus_manager: FocusManager::default(),
+            selected_tab: SideTab::default(),
+            has_side_tabs: false,
+            is_browsing_rooms: false,
         }
     }
 }
@@ -72,11 +122,36 @@ impl Model {
         self.terminal
             .raw_mut()
             .draw(|f| {
-                let custom_layout = layout::CustomLayout::new(f.size());
+                let mut custom_layout = layout::CustomLayout::new(
+                    f.size(),
+                    self.focus_manager.get_focused() == Panel::Log,
+                    self.selected_tab,
+                );
+
+                // The side panel gives way to the room browser on narrow terminals
+                if self.is_browsing_rooms {
+                    let room_browser = custom_layout
+                        .action_area
+                        .or_else(|| custom_layout.details.take())
+                        .or_else(|| custom_layout.navigation.take());
 
-                self.app.view(&Id::RoomDetails, f, custom_layout.details);
+                    if let Some(room_browser) = room_browser {
+                        self.app.view(&Id::RoomBrowser, f, room_browser);
+                    }
+                }
 
-                self.app.view(&Id::Help, f, custom_layout.navigation);
+                if let Some(details) = custom_layout.details {
+                    self.app.view(&Id::RoomDetails, f, details);
+                }
+
+                if let Some(navigation) = custom_layout.navigation {
+                    self.app.view(&Id::Help, f, navigation);
+                }
+
+                self.has_side_tabs = custom_layout.tabs.is_some();
+                if let Some(tabs) = custom_layout.tabs {
+                    self.app.view(&Id::Tabs, f, tabs);
+                }
 
                 self.app.view(&Id::Menu, f, custom_layout.menu);
                 self.app.view(&Id::BottomBar, f, custom_layout.bottom_bar);
@@ -84,25 +159,32 @@ impl Model {
             .unwrap();
     }
 
-    fn init_app(network_client: NetworkClient) -> Application<Id, Msg, UserEvent> {
+    fn init_app(
+        network_events: NetworkEvents,
+        keymap: Keymap,
+        theme: Theme,
+    ) -> Application<Id, Msg, UserEvent> {
         let mut app: Application<Id, Msg, UserEvent> = Application::init(
             EventListenerCfg::default()
                 .default_input_listener(Duration::from_millis(20))
-                .port(Box::new(network_client), Duration::from_millis(10))
+                .port(Box::new(network_events), Duration::from_millis(10))
                 .poll_timeout(Duration::from_millis(10))
                 .tick_interval(Duration::from_secs(1)),
         );
 
         app.mount(
             Id::Menu,
-            Box::<components::menu::Menu>::default(),
+            Box::new(components::menu::Menu::new(keymap.clone(), theme)),
             Vec::default(),
         )
         .unwrap();
 
         app.mount(
             Id::BottomBar,
-            Box::<components::bottom_bar::BottomBar>::default(),
+            Box::new(components::bottom_bar::BottomBar::new(
+                keymap.clone(),
+                theme,
+            )),
             vec![tuirealm::Sub::new(
                 tuirealm::SubEventClause::Any,
                 tuirealm::SubClause::Always,
@@ -112,7 +194,33 @@ impl Model {
 
         app.mount(
             Id::RoomDetails,
-            Box::<components::room_details::Details>::default(),
+            Box::new(components::room_details::Details::new(
+                keymap.clone(),
+                theme,
+            )),
+            vec![tuirealm::Sub::new(
+                tuirealm::SubEventClause::Any,
+                tuirealm::SubClause::Always,
+            )],
+        )
+        .unwrap();
+
+        app.mount(
+            Id::NetworkReceptor,
+            Box::<components::network_receptor::NetworkReceptor>::default(),
+            vec![tuirealm::Sub::new(
+                tuirealm::SubEventClause::Any,
+                tuirealm::SubClause::Always,
+            )],
+        )
+        .unwrap();
+
+        app.mount(
+            Id::RoomBrowser,
+            Box::new(components::room_browser::RoomBrowser::new(
+                keymap.clone(),
+                theme,
+            )),
             vec![tuirealm::Sub::new(
                 tuirealm::SubEventClause::Any,
                 tuirealm::SubClause::Always,
@@ -122,17 +230,110 @@ impl Model {
 
         app.mount(
             Id::Help,
-            Box::<components::help::Help>::default(),
+            Box::new(components::help::Help::new(&keymap, theme)),
             Vec::default(),
         )
         .unwrap();
 
+        app.mount(
+            Id::Tabs,
+            Box::new(components::tabs::Tabs::new(theme)),
+            vec![tuirealm::Sub::new(
+                tuirealm::SubEventClause::WindowResize,
+                tuirealm::SubClause::Always,
+            )],
+        )
+        .unwrap();
+
         // Activate the menu
         assert!(app.active(&Id::Menu).is_ok());
         app
     }
 }
 
+impl Model {
+    /// Make the component of the focused panel active, which also highlights its borders
+    fn change_focus(&mut self, focus_change: FocusChange) {
+        let focused_panel = self.focus_manager.change_focus(
+            focus_change,
+            self.state.is_in_room(),
+            self.is_browsing_rooms,
+        );
+
+        if let Err(error) = self.app.active(&focused_panel.get_component_id()) {
+            tracing::error!(?error, "Cannot focus the panel {focused_panel:?}");
+        }
+
+        // The users list, the chat and the details are drawn by the same component
+        let _ = self.app.attr(
+            &Id::RoomDetails,
+            components::room_details::USERS_FOCUSED,
+            tuirealm::AttrValue::Flag(focused_panel == Panel::Users),
+        );
+        let _ = self.app.attr(
+            &Id::RoomDetails,
+            components::room_details::CHAT_FOCUSED,
+            tuirealm::AttrValue::Flag(focused_panel == Panel::Chat),
+        );
+
+        // The focused panel must be visible
+        if matches!(focused_panel, Panel::Users | Panel::Chat | Panel::Details) {
+            self.select_tab(SideTab::Details);
+        }
+    }
+
+    fn select_tab(&mut self, selected_tab: SideTab) {
+        self.selected_tab = selected_tab;
+
+        let _ = self.app.attr(
+            &Id::Tabs,
+            tuirealm::Attribute::Value,
+            components::tabs::get_tab_value(selected_tab),
+        );
+    }
+
+    fn set_room_browser_open(&mut self, is_open: bool) {
+        self.is_browsing_rooms = is_open;
+
+        let _ = self.app.attr(
+            &Id::RoomBrowser,
+            tuirealm::Attribute::Display,
+            tuirealm::AttrValue::Flag(is_open),
+        );
+
+        if is_open {
+            self.change_focus(FocusChange::Panel(Panel::RoomBrowser));
+            self.send_request(network::types::NewRequestEntity::ListRooms);
+        } else if self.focus_manager.get_focused() == Panel::RoomBrowser {
+            self.change_focus(FocusChange::Panel(Panel::Menu));
+        }
+    }
+
+    fn send_request(&self, request: network::types::NewRequestEntity) {
+        // The interface does not wait for the network client, the request is dropped when its queue is full
+        let send_result = self
+            .grpc_channel
+            .try_send(network::types::Request::New(request));
+
+        match send_result {
+            Ok(()) => {}
+            Err(mpsc::error::TrySendError::Full(_)) => self.report_error(ClientError::ClientBusy),
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                self.report_error(ClientError::ClientStopped)
+            }
+        }
+    }
+
+    /// Show an error of the application along with the network events
+    fn report_error(&self, error: ClientError) {
+        tracing::error!(?error);
+        // The event is dropped if the application is not keeping up with the events
+        let _ = self
+            .network_event_sender
+            .try_send(UserEvent::NetworkError(error));
+    }
+}
+
 impl Update<Msg> for Model {
     fn update(&mut self, msg: Option<Msg>) -> Option<Msg> {
         if let Some(msg) = msg {
@@ -140,43 +341,81 @@ impl Update<Msg> for Model {
             match msg {
                 Msg::AppClose => {
                     self.quit = true;
-                    self.grpc_channel
-                        .send(network::types::Request::Quit)
-                        .unwrap();
+                    self.network_cancellation_token.cancel();
                     if let Some(network_join_handler) = self.network_join_handler.take() {
-                        network_join_handler.join().unwrap();
+                        if let Err(error) = self.runtime.block_on(network_join_handler) {
+                            tracing::error!(?error, "The network client failed");
+                        }
                     }
                     None
                 }
                 Msg::BottomBarUpdate | Msg::ReDraw => None,
+                Msg::Focus(focus_change) => {
+                    self.change_focus(focus_change);
+                    None
+                }
                 Msg::Menu(menu_message) => {
-                    let network_request = match menu_message {
-                        types::MenuMessage::MenuChange | types::MenuMessage::MenuDataChange => None,
-                        types::MenuMessage::MenuSelect(menu_selection) => {
-                            Some(network::types::NewRequestEntity::from(menu_selection))
+                    if let types::MenuMessage::MenuSelect(menu_selection) = menu_message {
+                        self.send_request(network::types::NewRequestEntity::from(menu_selection));
+                    }
+
+                    None
+                }
+                Msg::NextTab => {
+                    if self.has_side_tabs {
+                        self.select_tab(self.selected_tab.next());
+
+                        // The details are hidden behind the help, so they cannot keep the focus
+                        let focused_panel = self.focus_manager.get_focused();
+                        if self.selected_tab == SideTab::Navigation
+                            && matches!(focused_panel, Panel::Users | Panel::Chat | Panel::Details)
+                        {
+                            self.change_focus(FocusChange::Panel(Panel::Menu));
                         }
-                    };
-                    if let Some(network_request) = network_request {
-                        self.grpc_channel
-                            .send(network::types::Request::New(network_request))
-                            .unwrap();
                     }
 
                     None
                 }
-                Msg::StateUpdate(state_update) => {
-                    match &state_update {
-                        types::AppStateUpdate::UserIdUpdate { .. } => {}
-                        types::AppStateUpdate::RoomUpdate { .. } => {
-                            // This message should be received only once
-                            self.app.active(&Id::RoomDetails).unwrap();
+                Msg::RoomBrowser(room_browser_message) => {
+                    match room_browser_message {
+                        RoomBrowserMessage::Open => self.set_room_browser_open(true),
+                        RoomBrowserMessage::Close => self.set_room_browser_open(false),
+                        RoomBrowserMessage::Refresh if self.is_browsing_rooms => {
+                            self.send_request(network::types::NewRequestEntity::ListRooms);
                         }
-                        types::AppStateUpdate::UserRoomJoin { .. } => {}
-                        types::AppStateUpdate::GameStart { .. } => {}
+                        RoomBrowserMessage::Refresh => {}
+                    }
+
+                    None
+                }
+                Msg::LeaveRoom => {
+                    // Leaving is also possible while waiting for the matchmaking
+                    if self.state.is_in_room() {
+                        self.send_request(network::types::NewRequestEntity::LeaveRoom);
                     }
+
+                    None
+                }
+                Msg::SendRoomChat(message) => {
+                    if self.state.is_in_room() {
+                        self.send_request(network::types::NewRequestEntity::SendRoomChat {
+                            message,
+                        });
+                    }
+
+                    None
+                }
+                Msg::StateUpdate(state_update) => {
+                    let is_room_left = matches!(state_update, AppStateUpdate::RoomLeft);
+
                     let new_state = self.state.clone().apply_update(state_update);
                     self.state = new_state;
 
+                    // The room panels are gone, the menu takes the focus back
+                    if is_room_left {
+                        self.change_focus(FocusChange::Panel(Panel::Menu));
+                    }
+
                     None
                 }
             }
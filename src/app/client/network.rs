@@ -1,67 +1,186 @@
 use std::{
-    collections::VecDeque,
-    sync::{mpsc, Arc, Mutex},
+    sync::{Arc, Mutex},
+    time::Duration,
 };
 
 pub mod types;
 
-use crate::app::{client::model::ClientArgs, types::RoomServiceResponseType, utils};
+use crate::app::{
+    client::model::ClientArgs,
+    types::{RoomServiceResponseType, REQUEST_ID_METADATA_KEY},
+    utils,
+};
 
 use crate::app::server::grpc::server::{
-    grpc_client, PingRequest, RoomServiceRequest, RoomServiceResponse,
+    game_service_response, grpc_client, GameServiceRequest, GameServiceResponse,
+    GlobalStatsRequest, GlobalStatsResponse, LeaveRoomRequest, ListRoomsRequest, PingRequest,
+    PingResponse, ResumableGame, RoomServiceRequest, RoomServiceResponse, SendRoomChatRequest,
 };
 
+use tokio::sync::mpsc;
 use tokio_stream::StreamExt;
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
 use tuirealm::listener::Poll;
 
-use super::network::types::UserEvent;
-use super::types::{ClientConfig, LocalStorage};
+use super::network::types::{ClientError, ConnectionState, UserEvent};
+use super::types::{ClientConfig, ClientTlsConfig, LocalStorage};
+
+/// Capacity of the channels between the application and the network client
+/// Senders wait for the other side to catch up once a channel is full
+pub const REQUEST_CHANNEL_CAPACITY: usize = 8;
+pub const EVENT_CHANNEL_CAPACITY: usize = 64;
+
+pub type GrpcClient = grpc_client::GrpcClient<tonic::transport::Channel>;
+
+/// Connect to the server, over TLS when it is configured
+pub async fn connect(
+    server_url: &str,
+    tls_config: Option<&ClientTlsConfig>,
+) -> Result<GrpcClient, ClientError> {
+    let mut endpoint =
+        tonic::transport::Endpoint::from_shared(server_url.to_string()).map_err(|error| {
+            ClientError::Connection(format!("Invalid server url {server_url}: {error}"))
+        })?;
+
+    if let Some(tls_config) = tls_config {
+        let tls_config = get_tls_config(tls_config).await.map_err(|error| {
+            ClientError::Connection(format!("Could not read the TLS certificates: {error}"))
+        })?;
+
+        endpoint = endpoint
+            .tls_config(tls_config)
+            .map_err(|network_error| ClientError::Connection(format!("{network_error:?}")))?;
+    }
+
+    let channel = endpoint
+        .connect()
+        .await
+        .map_err(|network_error| ClientError::Connection(format!("{network_error:?}")))?;
 
-const NETWORK_MESSAGE_QUEUE_CAPACITY: usize = 10;
+    Ok(grpc_client::GrpcClient::new(channel))
+}
 
+async fn get_tls_config(
+    tls_config: &ClientTlsConfig,
+) -> std::io::Result<tonic::transport::ClientTlsConfig> {
+    let read_pem = |path: &str| tokio::fs::read(utils::replace_home_dir(path));
+
+    let ca_certificate = read_pem(&tls_config.ca_certificate_path).await?;
+    let mut client_tls_config = tonic::transport::ClientTlsConfig::new()
+        .ca_certificate(tonic::transport::Certificate::from_pem(ca_certificate));
+
+    if let Some(domain) = &tls_config.domain {
+        client_tls_config = client_tls_config.domain_name(domain);
+    }
+
+    match (&tls_config.certificate_path, &tls_config.key_path) {
+        (Some(certificate_path), Some(key_path)) => {
+            let certificate = read_pem(certificate_path).await?;
+            let key = read_pem(key_path).await?;
+            client_tls_config =
+                client_tls_config.identity(tonic::transport::Identity::from_pem(certificate, key));
+        }
+        (None, None) => {}
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Both the certificate and the key are needed for the client certificate",
+            ))
+        }
+    }
+
+    Ok(client_tls_config)
+}
+
+/// The delay before reconnecting is doubled after every failed attempt, up to the maximum delay
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// The room or game the user is in, so that it can be rejoined after a reconnection
+#[derive(Clone, Debug)]
+enum ActiveSession {
+    /// A private room is rejoined with the invite code that the user has joined it with
+    WaitingRoom {
+        room_id: String,
+        invite_code: Option<String>,
+    },
+    Game {
+        room_id: String,
+    },
+}
+
+/// Runs on the tokio runtime of the application, the events for the application are sent
+/// through a bounded channel
+///
+/// All the tasks of the client stop once the cancellation token is cancelled
 #[derive(Clone)]
 pub struct NetworkClient {
-    messages: Arc<Mutex<VecDeque<UserEvent>>>,
+    event_sender: mpsc::Sender<UserEvent>,
+    cancellation_token: CancellationToken,
     user_id: Option<String>,
+    active_session: Arc<Mutex<Option<ActiveSession>>>,
+    /// Stops the room stream when the user leaves the room
+    room_stream_token: Arc<Mutex<Option<CancellationToken>>>,
 }
 
+/// The receiving end of the events of the network client, polled by the application
+pub struct NetworkEvents {
+    event_receiver: mpsc::Receiver<UserEvent>,
+}
+
+#[allow(async_fn_in_trait)]
 pub trait DisplayNetworkError {
     type Item;
-    fn error_handler(self, network_client: &NetworkClient) -> Option<Self::Item>;
+    async fn error_handler(self, network_client: &NetworkClient) -> Option<Self::Item>;
 }
 
 impl<U> DisplayNetworkError for Result<tonic::Response<U>, tonic::Status> {
     type Item = U;
-    fn error_handler(self, network_client: &NetworkClient) -> Option<Self::Item> {
+    async fn error_handler(self, network_client: &NetworkClient) -> Option<Self::Item> {
         match self {
             Ok(res) => Some(res.into_inner()),
             Err(tonic_status) => {
-                let stringified_error = tonic_status.message();
                 network_client
-                    .push_user_event(UserEvent::NetworkError(stringified_error.to_string()));
+                    .push_user_event(UserEvent::NetworkError(tonic_status.into()))
+                    .await;
                 None
             }
         }
     }
 }
 
-impl Default for NetworkClient {
-    fn default() -> Self {
-        Self {
-            messages: Arc::new(Mutex::new(VecDeque::with_capacity(
-                NETWORK_MESSAGE_QUEUE_CAPACITY,
-            ))),
-            user_id: None,
-        }
-    }
+/// The delay grows exponentially with the number of attempts, jitter is added so that the clients
+/// of a restarted server do not reconnect all at once
+fn get_reconnect_delay(attempt: u32) -> Duration {
+    let max_delay = INITIAL_RECONNECT_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(MAX_RECONNECT_DELAY);
+
+    let half_delay = max_delay / 2;
+    half_delay + half_delay.mul_f64(rand::random::<f64>())
 }
 
-fn handle_room_service_message(message: RoomServiceResponse, network_client: NetworkClient) {
-    let message_type = RoomServiceResponseType::from_u8(message.message_type as u8).unwrap();
+async fn handle_room_service_message(
+    message: RoomServiceResponse,
+    network_client: &NetworkClient,
+    joined_with_invite_code: Option<&str>,
+) {
+    let Some(message_type) = RoomServiceResponseType::from_u8(message.message_type as u8) else {
+        let error = ClientError::InvalidResponse(format!(
+            "Unknown room message type {}",
+            message.message_type
+        ));
+        network_client
+            .push_user_event(UserEvent::NetworkError(error))
+            .await;
+        return;
+    };
 
     match message_type {
         RoomServiceResponseType::Init => {
             let room_id = message.room_id;
+            let invite_code = message.invite_code;
 
             let users = message
                 .user_details
@@ -69,9 +188,18 @@ fn handle_room_service_message(message: RoomServiceResponse, network_client: Net
                 .map(Into::into)
                 .collect::<Vec<_>>();
 
-            let room_created_event = UserEvent::RoomCreated { room_id, users };
+            network_client.set_active_session(ActiveSession::WaitingRoom {
+                room_id: room_id.clone(),
+                invite_code: joined_with_invite_code.map(str::to_string),
+            });
 
-            network_client.push_user_event(room_created_event)
+            let room_created_event = UserEvent::RoomCreated {
+                room_id,
+                invite_code,
+                users,
+            };
+
+            network_client.push_user_event(room_created_event).await
         }
         RoomServiceResponseType::UserJoined => {
             let users = message
@@ -82,7 +210,7 @@ fn handle_room_service_message(message: RoomServiceResponse, network_client: Net
 
             let user_joined_event = UserEvent::UserJoined { users };
 
-            network_client.push_user_event(user_joined_event);
+            network_client.push_user_event(user_joined_event).await;
         }
         RoomServiceResponseType::GameStart => {
             let room_id = message.room_id;
@@ -93,161 +221,733 @@ fn handle_room_service_message(message: RoomServiceResponse, network_client: Net
                 .map(Into::into)
                 .collect::<Vec<_>>();
 
+            network_client.set_active_session(ActiveSession::Game {
+                room_id: room_id.clone(),
+            });
+
             let user_joined_event = UserEvent::GameStart { room_id, users };
 
-            network_client.push_user_event(user_joined_event);
+            network_client.push_user_event(user_joined_event).await;
+        }
+        RoomServiceResponseType::UserLeft => {
+            let users = message
+                .user_details
+                .into_iter()
+                .map(Into::into)
+                .collect::<Vec<_>>();
+
+            network_client
+                .push_user_event(UserEvent::UserLeft { users })
+                .await;
+        }
+        RoomServiceResponseType::ServerShutdown => {
+            network_client
+                .push_user_event(UserEvent::ServerShutdown)
+                .await;
+        }
+        RoomServiceResponseType::Chat => {
+            let Some(chat_message) = message.chat_message else {
+                let error = ClientError::InvalidResponse("The chat message is missing".to_string());
+                network_client
+                    .push_user_event(UserEvent::NetworkError(error))
+                    .await;
+                return;
+            };
+
+            network_client
+                .push_user_event(UserEvent::ChatMessage {
+                    user_name: chat_message.user_name,
+                    message: chat_message.message,
+                })
+                .await;
         }
     }
 }
 
+/// Forward the messages of the room stream until the user leaves the room or the connection is lost
 async fn handle_room_service_stream(
     mut network_stream: tonic::Streaming<RoomServiceResponse>,
     network_client: NetworkClient,
-    mut quit_signal_receiver: tokio::sync::watch::Receiver<bool>,
+    stream_token: CancellationToken,
+    joined_with_invite_code: Option<String>,
 ) {
-    let handle_stream_message = |stream_message: Result<_, tonic::Status>,
-                                 network_client: NetworkClient| {
+    loop {
+        let stream_message = tokio::select! {
+            Some(stream_message) = network_stream.next() => stream_message,
+            _ = stream_token.cancelled() => return,
+            else => return,
+        };
+
         match stream_message {
-            Ok(message) => handle_room_service_message(message, network_client),
+            Ok(message) => {
+                handle_room_service_message(
+                    message,
+                    &network_client,
+                    joined_with_invite_code.as_deref(),
+                )
+                .await
+            }
             Err(error) => {
-                let stringified_error = error.message();
+                // The user is removed from the room, there is nothing to rejoin
+                if error.code() == tonic::Code::Aborted {
+                    network_client.active_session.lock().unwrap().take();
+                }
+
                 network_client
-                    .push_user_event(UserEvent::NetworkError(stringified_error.to_string()));
+                    .push_user_event(UserEvent::NetworkError(error.into()))
+                    .await;
             }
         }
-    };
+    }
+}
 
+/// Keep the game stream open until the connection is lost, the player is connected to the game while it is open
+///
+/// The request stream is closed along with the response stream, once this task ends
+async fn handle_game_stream(
+    mut network_stream: tonic::Streaming<GameServiceResponse>,
+    _request_sender: mpsc::Sender<GameServiceRequest>,
+    network_client: NetworkClient,
+    stream_token: CancellationToken,
+) {
     loop {
-        tokio::select! {
-            Some(stream_message) = network_stream.next() => handle_stream_message(stream_message, network_client.clone()),
-            _ = quit_signal_receiver.changed() => {
-                drop(network_stream);
-                return;
-            },
+        let stream_message = tokio::select! {
+            Some(stream_message) = network_stream.next() => stream_message,
+            _ = stream_token.cancelled() => return,
             else => return,
+        };
+
+        match stream_message {
+            Ok(message) if message.message_type() == game_service_response::MessageType::Resume => {
+                let progress = message
+                    .game_user_status
+                    .map(|game_user_status| game_user_status.status)
+                    .unwrap_or_default();
+
+                network_client
+                    .push_user_event(UserEvent::GameRejoined {
+                        game_id: message.game_id,
+                        progress,
+                    })
+                    .await;
+            }
+            Ok(_) => {}
+            Err(error) => {
+                network_client
+                    .push_user_event(UserEvent::NetworkError(error.into()))
+                    .await;
+                return;
+            }
+        }
+    }
+}
+
+/// Attach a new request id to the request, so that the logs of the client and the server
+/// can be correlated. The returned span carries the request id for the client side logs
+pub fn with_request_id<T>(message: T, method: &str) -> (tonic::Request<T>, tracing::Span) {
+    let request_id = utils::generate_time_ordered_id("req");
+    let mut request = tonic::Request::new(message);
+
+    if let Ok(metadata_value) = request_id.parse() {
+        request
+            .metadata_mut()
+            .insert(REQUEST_ID_METADATA_KEY, metadata_value);
+    }
+
+    let span = tracing::info_span!("request", method, request_id = request_id.as_str());
+    (request, span)
+}
+
+/// The global stats stream stays open for as long as the connection is alive, the connection
+/// is cancelled when it ends so that the supervisor reconnects
+async fn handle_global_stats_stream(
+    mut network_stream: tonic::Streaming<GlobalStatsResponse>,
+    network_client: NetworkClient,
+    connection_token: CancellationToken,
+) {
+    loop {
+        let stream_message = tokio::select! {
+            stream_message = network_stream.next() => stream_message,
+            _ = connection_token.cancelled() => return,
+        };
+
+        match stream_message {
+            Some(Ok(message)) => {
+                network_client
+                    .push_user_event(UserEvent::GlobalStats {
+                        active_players: message.active_players,
+                        active_games: message.active_games,
+                    })
+                    .await
+            }
+            Some(Err(error)) => {
+                tracing::warn!(?error, "Global stats stream failed");
+                connection_token.cancel();
+                return;
+            }
+            None => {
+                connection_token.cancel();
+                return;
+            }
         }
     }
 }
 
 impl NetworkClient {
-    #[tokio::main]
+    pub fn new(
+        event_sender: mpsc::Sender<UserEvent>,
+        cancellation_token: CancellationToken,
+    ) -> Self {
+        Self {
+            event_sender,
+            cancellation_token,
+            user_id: None,
+            active_session: Arc::new(Mutex::new(None)),
+            room_stream_token: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Run the network client until the cancellation token is cancelled or the application
+    /// drops the sending end of the requests
     pub async fn start_network_client(
-        &mut self,
-        message_receiver: mpsc::Receiver<types::Request>,
+        mut self,
+        mut request_receiver: mpsc::Receiver<types::Request>,
         config: ClientConfig,
         args: ClientArgs,
     ) {
-        let mut client = match grpc_client::GrpcClient::connect(config.server_url.clone()).await {
-            Ok(grpc_client) => {
-                let message = format!(
-                    "Successfully connected to server at address {}",
-                    config.server_url
-                );
-                self.push_user_event(UserEvent::InfoMessage(message));
-                grpc_client
-            }
+        // Use existing customer based on the args passed ( create_guest )
+        // Read the client details from ~/.local/state/blazerapp.toml for a returning user
+        if !args.create_guest {
+            let local_storage =
+                utils::read_local_storage::<LocalStorage>("~/.local/state/blazerapp.toml").await;
+
+            self.user_id = local_storage.and_then(|user_details| user_details.client_id);
+        }
 
-            Err(network_error) => {
-                let error = format!("Connection to server failed {network_error:?}");
+        self.push_user_event(UserEvent::ConnectionState(ConnectionState::Connecting))
+            .await;
+
+        // The room of the join link is joined once, on the first connection
+        let mut join_link_invite_code = args.join_link;
+
+        // Supervise the connection, every time it is lost the session is restored on a new connection
+        loop {
+            let Some((mut client, resumable_game)) = self
+                .connect_with_backoff(
+                    &config.server_url,
+                    config.tls.as_ref(),
+                    &mut request_receiver,
+                )
+                .await
+            else {
+                return;
+            };
+
+            // Cancelled when the connection is lost, or when the client stops
+            let connection_token = self.cancellation_token.child_token();
+
+            let mut join_handlers = self
+                .restore_session(&mut client, resumable_game, &connection_token)
+                .await;
+
+            if let Some(invite_code) = join_link_invite_code.take() {
+                let request_type = types::NewRequestEntity::JoinWithInvite { invite_code };
+                if let Some(join_handler) = self
+                    .start_room_stream(&mut client, request_type, &connection_token)
+                    .await
+                {
+                    join_handlers.push(join_handler);
+                }
+            }
 
-                self.push_user_event(UserEvent::NetworkError(error));
+            let is_connection_lost = loop {
+                tokio::select! {
+                    _ = connection_token.cancelled() => {
+                        break !self.cancellation_token.is_cancelled();
+                    }
+                    request = request_receiver.recv() => match request {
+                        Some(types::Request::New(types::NewRequestEntity::LeaveRoom)) => {
+                            self.leave_room(&mut client).await;
+                        }
+                        Some(types::Request::New(types::NewRequestEntity::SendRoomChat { message })) => {
+                            self.send_room_chat(&mut client, message).await;
+                        }
+                        Some(types::Request::New(types::NewRequestEntity::ListRooms)) => {
+                            self.list_rooms(&mut client).await;
+                        }
+                        Some(types::Request::New(request_type)) => {
+                            if let Some(join_handler) = self
+                                .start_room_stream(&mut client, request_type, &connection_token)
+                                .await
+                            {
+                                join_handlers.push(join_handler);
+                            }
+                        }
+                        // The application has closed
+                        None => break false,
+                    },
+                }
+            };
+
+            // Inform all the join handles to finish their task
+            connection_token.cancel();
+            for handle in join_handlers {
+                // wait for all tasks to finish
+                if let Err(error) = handle.await {
+                    tracing::error!(?error, "Network task failed");
+                }
+            }
 
-                // Add the retry logic for exponential retry
+            if !is_connection_lost {
                 return;
             }
-        };
+        }
+    }
 
-        // Use existing customer based on the args passed ( create_guest )
-        let ping_request = if args.create_guest {
-            PingRequest { user_id: None }
-        } else {
-            let local_storage =
-                utils::read_local_storage::<LocalStorage>("~/.local/state/blazerapp.toml").await;
+    /// Connect to the server and establish the session of the user
+    ///
+    /// Failed attempts are retried with an exponential backoff until the session is established,
+    /// returns `None` if the application quits in the meantime
+    async fn connect_with_backoff(
+        &mut self,
+        server_url: &str,
+        tls_config: Option<&ClientTlsConfig>,
+        request_receiver: &mut mpsc::Receiver<types::Request>,
+    ) -> Option<(GrpcClient, Option<ResumableGame>)> {
+        let mut attempt = 0;
+        let cancellation_token = self.cancellation_token.clone();
+
+        loop {
+            let session = tokio::select! {
+                session = self.establish_session(server_url, tls_config) => session,
+                _ = cancellation_token.cancelled() => return None,
+            };
+
+            match session {
+                Ok(session) => {
+                    self.push_user_event(UserEvent::ConnectionState(ConnectionState::Connected))
+                        .await;
+                    return Some(session);
+                }
+                Err(error) => {
+                    attempt += 1;
+                    let retry_in = get_reconnect_delay(attempt);
+
+                    tracing::warn!(attempt, ?retry_in, "{error}");
+                    self.push_user_event(UserEvent::ConnectionState(
+                        ConnectionState::Reconnecting { attempt, retry_in },
+                    ))
+                    .await;
+
+                    let retry_at = tokio::time::Instant::now() + retry_in;
+                    loop {
+                        tokio::select! {
+                            _ = tokio::time::sleep_until(retry_at) => break,
+                            _ = cancellation_token.cancelled() => return None,
+                            request = request_receiver.recv() => match request {
+                                Some(types::Request::New(_)) => {
+                                    self.push_user_event(UserEvent::NetworkError(
+                                        ClientError::Unavailable(
+                                            "Not connected to the server, please wait".to_string(),
+                                        ),
+                                    ))
+                                    .await;
+                                }
+                                None => return None,
+                            },
+                        }
+                    }
+                }
+            }
+        }
+    }
 
-            PingRequest {
-                user_id: local_storage.and_then(|user_details| user_details.client_id),
+    /// Connect to the server and ping it, which restores the session of a known user
+    /// The server offers to resume the game that the user was disconnected from
+    async fn establish_session(
+        &mut self,
+        server_url: &str,
+        tls_config: Option<&ClientTlsConfig>,
+    ) -> Result<(GrpcClient, Option<ResumableGame>), ClientError> {
+        let mut client = connect(server_url, tls_config).await?;
+
+        let mut replaced_user_id = None;
+        let ping_response = match self.ping(&mut client).await {
+            // The stored user is no longer known to the server, continue as a new guest
+            Err(ClientError::NotFound(message)) if self.user_id.is_some() => {
+                tracing::warn!(message, "The stored user is not known to the server");
+                replaced_user_id = self.user_id.take();
+
+                match self.ping(&mut client).await {
+                    Ok(ping_response) => ping_response,
+                    Err(error) => {
+                        // The stored user is kept until the new guest is created
+                        self.user_id = replaced_user_id;
+                        return Err(error);
+                    }
+                }
             }
+            ping_response => ping_response?,
         };
 
-        // Read the client details from ~/.local/state/blazerapp.toml for a returning user
+        let message = format!("Successfully connected to server at address {server_url}");
+        self.push_user_event(UserEvent::InfoMessage(message)).await;
 
-        let ping_result = client.ping(ping_request).await;
+        // The user is told last since the games played by the previous user are no longer counted
+        if let Some(user_id) = replaced_user_id {
+            self.push_user_event(UserEvent::NetworkError(ClientError::UserReplaced {
+                user_id,
+                guest_id: ping_response.user_id.clone(),
+            }))
+            .await;
+        }
 
-        if let Some(ping_response) = ping_result.error_handler(self) {
-            let client_id = ping_response.user_id;
+        let client_id = ping_response.user_id;
+
+        // Write the client_id / user_id to localstorage data to persist session
+        let local_storage_data = LocalStorage::new(client_id.clone());
+        if let Err(error) =
+            utils::write_local_storage("~/.local/state/blazerapp.toml", local_storage_data).await
+        {
+            self.push_user_event(UserEvent::NetworkError(ClientError::LocalStorage(
+                error.to_string(),
+            )))
+            .await;
+        }
 
-            // Write the client_id / user_id to localstorage data to persist session
-            let local_storage_data = LocalStorage::new(client_id.clone());
-            utils::write_local_storage("~/.local/state/blazerapp.toml", local_storage_data).await;
+        self.user_id = Some(client_id);
 
-            self.user_id = Some(client_id);
-        }
+        Ok((client, ping_response.resumable_game))
+    }
+
+    async fn ping(&self, client: &mut GrpcClient) -> Result<PingResponse, ClientError> {
+        let ping_request = PingRequest {
+            user_id: self.user_id.clone(),
+        };
 
-        let (quit_signal_sender, quit_signal_receiver) = tokio::sync::watch::channel(false);
+        let (ping_request, request_span) = with_request_id(ping_request, "ping");
+        let ping_response = client.ping(ping_request).instrument(request_span).await?;
 
+        Ok(ping_response.into_inner())
+    }
+
+    /// Open the streams of the session on a new connection and rejoin the room the user was in
+    async fn restore_session(
+        &self,
+        client: &mut GrpcClient,
+        resumable_game: Option<ResumableGame>,
+        connection_token: &CancellationToken,
+    ) -> Vec<tokio::task::JoinHandle<()>> {
         let mut join_handlers = Vec::<tokio::task::JoinHandle<()>>::new();
 
-        while let Ok(message) = message_receiver.recv() {
-            match message {
-                types::Request::Quit => {
-                    // Inform all the join handles to finish their task
-                    quit_signal_sender.send(true).unwrap();
-                    for handle in join_handlers {
-                        // wait for all tasks to finish
-                        handle.await.unwrap();
-                    }
+        // Keep receiving the global stats for as long as the connection is alive
+        // The end of this stream is used to detect that the connection is lost
+        if let Some(user_id) = self.user_id.clone() {
+            let global_stats_request = GlobalStatsRequest { client_id: user_id };
+            let (global_stats_request, request_span) =
+                with_request_id(global_stats_request, "global_stats");
+
+            let global_stats_stream = client
+                .global_stats(global_stats_request)
+                .instrument(request_span.clone())
+                .await
+                .error_handler(self)
+                .await;
+
+            match global_stats_stream {
+                Some(stream) => {
+                    let join_handler = tokio::spawn(
+                        handle_global_stats_stream(stream, self.clone(), connection_token.clone())
+                            .instrument(request_span),
+                    );
+
+                    join_handlers.push(join_handler);
+                }
+                None => connection_token.cancel(),
+            }
+        }
 
-                    return;
+        // The session is set again once the server sends the room details
+        let active_session = self.active_session.lock().unwrap().take();
+
+        match (active_session, resumable_game) {
+            // The server keeps track of the games that can be resumed, it is offered on every ping
+            (_, Some(resumable_game)) => {
+                if let Some(join_handler) = self
+                    .start_game_stream(client, resumable_game, connection_token)
+                    .await
+                {
+                    join_handlers.push(join_handler);
                 }
-                types::Request::New(request_type) => {
-                    let (request_type, room_id) = match request_type {
-                        types::NewRequestEntity::JoinRoom { room_id } => (2, Some(room_id)),
-                        types::NewRequestEntity::NewGame => (2, None),
-                        types::NewRequestEntity::CreateRoom => (1, None),
-                    };
-
-                    let room_request = RoomServiceRequest {
-                        client_id: self.user_id.clone().unwrap(),
-                        room_id,
-                        request_type,
-                    };
-
-                    let room_stream = client.room_service(room_request).await.error_handler(self);
-
-                    let cloned_self = self.clone();
-
-                    match room_stream {
-                        Some(stream) => {
-                            let join_handler = tokio::spawn(handle_room_service_stream(
-                                stream,
-                                cloned_self,
-                                quit_signal_receiver.clone(),
-                            ));
-
-                            join_handlers.push(join_handler);
-                        }
-                        None => {
-                            // This can happen in case the server panics
-                        }
-                    }
+            }
+            (
+                Some(ActiveSession::WaitingRoom {
+                    room_id,
+                    invite_code,
+                }),
+                None,
+            ) => {
+                self.push_user_event(UserEvent::InfoMessage("Rejoining the room".to_string()))
+                    .await;
+
+                let request_type = match invite_code {
+                    Some(invite_code) => types::NewRequestEntity::JoinWithInvite { invite_code },
+                    None => types::NewRequestEntity::JoinRoom { room_id },
+                };
+                if let Some(join_handler) = self
+                    .start_room_stream(client, request_type, connection_token)
+                    .await
+                {
+                    join_handlers.push(join_handler);
                 }
             }
+            (Some(ActiveSession::Game { room_id }), None) => {
+                self.push_user_event(UserEvent::NetworkError(ClientError::ConnectionLost(
+                    format!("The game in room {room_id} can no longer be resumed"),
+                )))
+                .await;
+            }
+            (None, None) => {}
         }
+
+        join_handlers
     }
 
-    fn push_user_event(&self, event: UserEvent) {
+    async fn start_room_stream(
+        &self,
+        client: &mut GrpcClient,
+        request_type: types::NewRequestEntity,
+        connection_token: &CancellationToken,
+    ) -> Option<tokio::task::JoinHandle<()>> {
+        let (request_type, room_id, invite_code, single_use_invite, is_public) = match request_type
+        {
+            types::NewRequestEntity::JoinRoom { room_id } => (2, Some(room_id), None, false, false),
+            types::NewRequestEntity::JoinWithInvite { invite_code } => {
+                (2, None, Some(invite_code), false, false)
+            }
+            types::NewRequestEntity::NewGame => (2, None, None, false, false),
+            types::NewRequestEntity::CreateRoom => (1, None, None, false, false),
+            types::NewRequestEntity::CreateSingleUseRoom => (1, None, None, true, false),
+            types::NewRequestEntity::CreatePublicRoom => (1, None, None, false, true),
+            types::NewRequestEntity::LeaveRoom
+            | types::NewRequestEntity::SendRoomChat { .. }
+            | types::NewRequestEntity::ListRooms => return None,
+        };
+
+        let joined_with_invite_code = invite_code.clone();
+        let room_request = RoomServiceRequest {
+            client_id: self.user_id.clone()?,
+            room_id,
+            request_type,
+            invite_code,
+            single_use_invite,
+            is_public,
+        };
+
+        let (room_request, request_span) = with_request_id(room_request, "room_service");
+
+        // The stream is not available in case the server rejects the request
+        let stream = client
+            .room_service(room_request)
+            .instrument(request_span.clone())
+            .await
+            .error_handler(self)
+            .await?;
+
+        // The user is only in one room at a time, the stream of the previous room is no longer needed
+        let room_stream_token = connection_token.child_token();
+        if let Some(previous_token) = self
+            .room_stream_token
+            .lock()
+            .unwrap()
+            .replace(room_stream_token.clone())
+        {
+            previous_token.cancel();
+        }
+
+        let join_handler = tokio::spawn(
+            handle_room_service_stream(
+                stream,
+                self.clone(),
+                room_stream_token,
+                joined_with_invite_code,
+            )
+            .instrument(request_span),
+        );
+
+        Some(join_handler)
+    }
+
+    /// Rejoin the game from the progress known to the server
+    async fn start_game_stream(
+        &self,
+        client: &mut GrpcClient,
+        resumable_game: ResumableGame,
+        connection_token: &CancellationToken,
+    ) -> Option<tokio::task::JoinHandle<()>> {
+        let (request_sender, request_receiver) = mpsc::channel(REQUEST_CHANNEL_CAPACITY);
+
+        // The first message of the stream identifies the player and the game
+        let first_request = GameServiceRequest {
+            client_id: self.user_id.clone()?,
+            game_id: resumable_game.game_id,
+            progress: resumable_game.progress,
+            keystroke_intervals_ms: vec![],
+        };
+        request_sender.send(first_request).await.ok()?;
+
+        let (game_request, request_span) = with_request_id(
+            tokio_stream::wrappers::ReceiverStream::new(request_receiver),
+            "game_service",
+        );
+
+        let stream = client
+            .game_service(game_request)
+            .instrument(request_span.clone())
+            .await
+            .error_handler(self)
+            .await?;
+
+        let join_handler = tokio::spawn(
+            handle_game_stream(
+                stream,
+                request_sender,
+                self.clone(),
+                connection_token.clone(),
+            )
+            .instrument(request_span),
+        );
+
+        Some(join_handler)
+    }
+
+    /// Close the room stream and leave the room on the server, so that the other users are informed
+    /// right away
+    async fn leave_room(&self, client: &mut GrpcClient) {
+        if let Some(room_stream_token) = self.room_stream_token.lock().unwrap().take() {
+            room_stream_token.cancel();
+        }
+        self.active_session.lock().unwrap().take();
+
+        if let Some(client_id) = self.user_id.clone() {
+            let (leave_room_request, request_span) =
+                with_request_id(LeaveRoomRequest { client_id }, "leave_room");
+
+            // The server also removes the user from the room once the stream is closed
+            client
+                .leave_room(leave_room_request)
+                .instrument(request_span)
+                .await
+                .error_handler(self)
+                .await;
+        }
+
+        self.push_user_event(UserEvent::RoomLeft).await;
+    }
+
+    /// The message is received back over the room stream, along with the other users of the room
+    async fn send_room_chat(&self, client: &mut GrpcClient, message: String) {
+        let Some(client_id) = self.user_id.clone() else {
+            return;
+        };
+
+        let (send_room_chat_request, request_span) =
+            with_request_id(SendRoomChatRequest { client_id, message }, "send_room_chat");
+
+        client
+            .send_room_chat(send_room_chat_request)
+            .instrument(request_span)
+            .await
+            .error_handler(self)
+            .await;
+    }
+
+    /// The room browser asks for the rooms again while it is open, a failed request is not retried
+    async fn list_rooms(&self, client: &mut GrpcClient) {
+        let Some(client_id) = self.user_id.clone() else {
+            return;
+        };
+
+        let (list_rooms_request, request_span) =
+            with_request_id(ListRoomsRequest { client_id }, "list_rooms");
+
+        let Some(response) = client
+            .list_rooms(list_rooms_request)
+            .instrument(request_span)
+            .await
+            .error_handler(self)
+            .await
+        else {
+            return;
+        };
+
+        let rooms = response
+            .rooms
+            .into_iter()
+            .map(Into::into)
+            .collect::<Vec<_>>();
+
+        self.push_user_event(UserEvent::RoomList { rooms }).await;
+    }
+
+    fn set_active_session(&self, active_session: ActiveSession) {
+        *self.active_session.lock().unwrap() = Some(active_session);
+    }
+
+    /// Waits for the application to catch up when the event channel is full
+    async fn push_user_event(&self, event: UserEvent) {
         tracing::info!(push_user_event=?event);
-        self.messages.lock().unwrap().push_back(event)
+
+        tokio::select! {
+            result = self.event_sender.send(event) => {
+                if result.is_err() {
+                    tracing::warn!("The application is no longer receiving network events");
+                }
+            }
+            _ = self.cancellation_token.cancelled() => {}
+        }
+    }
+}
+
+impl NetworkEvents {
+    pub fn new(event_receiver: mpsc::Receiver<UserEvent>) -> Self {
+        Self { event_receiver }
     }
 }
 
-impl Poll<UserEvent> for NetworkClient {
+impl Poll<UserEvent> for NetworkEvents {
     fn poll(&mut self) -> tuirealm::listener::ListenerResult<Option<tuirealm::Event<UserEvent>>> {
         Ok(self
-            .messages
-            .lock()
-            .unwrap()
-            .pop_front()
+            .event_receiver
+            .try_recv()
+            .ok()
             .map(tuirealm::Event::User))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconnect_delays_grow_with_the_attempts() {
+        for attempt in 1..=4 {
+            let max_delay = INITIAL_RECONNECT_DELAY * 2u32.pow(attempt - 1);
+            let delay = get_reconnect_delay(attempt);
+
+            // The jitter takes at most half of the delay away
+            assert!(delay >= max_delay / 2, "{delay:?} at attempt {attempt}");
+            assert!(delay <= max_delay, "{delay:?} at attempt {attempt}");
+        }
+    }
+
+    #[test]
+    fn reconnect_delays_are_capped() {
+        for attempt in [10, 100, u32::MAX] {
+            let delay = get_reconnect_delay(attempt);
+
+            assert!(delay >= MAX_RECONNECT_DELAY / 2);
+            assert!(delay <= MAX_RECONNECT_DELAY);
+        }
+    }
+}
@@ -1,18 +1,113 @@
 #[derive(Debug, PartialEq, Eq, Clone, PartialOrd)]
 pub enum UserEvent {
     InfoMessage(String),
-    NetworkError(String),
+    NetworkError(ClientError),
     RoomCreated {
         room_id: String,
+        /// The other users can join the room with this code, while it is valid
+        invite_code: Option<String>,
         users: Vec<UserDetails>,
     },
     UserJoined {
         users: Vec<UserDetails>,
     },
+    /// Another user has left the room, the remaining users are sent
+    UserLeft {
+        users: Vec<UserDetails>,
+    },
+    /// The user has left the room
+    RoomLeft,
     GameStart {
         room_id: String,
         users: Vec<UserDetails>,
     },
+    /// A chat message of a user of the room, the own messages of the user included
+    ChatMessage {
+        user_name: String,
+        message: String,
+    },
+    GlobalStats {
+        active_players: u32,
+        active_games: u32,
+    },
+    ServerShutdown,
+    ConnectionState(ConnectionState),
+    /// The public rooms that are waiting for players, the newest rooms first
+    RoomList {
+        rooms: Vec<PublicRoom>,
+    },
+    /// The user has rejoined the game that the user was disconnected from
+    GameRejoined {
+        game_id: String,
+        progress: u32,
+    },
+}
+
+/// Errors shown to the user, the errors returned by the server are classified by their status code
+#[derive(Debug, PartialEq, Eq, Clone, PartialOrd, thiserror::Error)]
+pub enum ClientError {
+    #[error("Not found: {0}")]
+    NotFound(String),
+    #[error("Already exists: {0}")]
+    AlreadyExists(String),
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("Server unavailable: {0}")]
+    Unavailable(String),
+    #[error("Too many requests: {0}")]
+    RateLimited(String),
+    /// The private rooms can only be joined with their invite code
+    #[error("Invite code required: {0}, ask the host of the room for the invite code")]
+    InviteRequired(String),
+    /// The session was closed by an administrator
+    #[error("Removed from the room: {0}, start a new game from the menu")]
+    RemovedFromRoom(String),
+    #[error("Server error: {0}")]
+    Server(String),
+    #[error("Connection failed: {0}")]
+    Connection(String),
+    #[error("Connection lost: {0}")]
+    ConnectionLost(String),
+    #[error("The user {user_id} is no longer known to the server, continuing as the new guest {guest_id}")]
+    UserReplaced { user_id: String, guest_id: String },
+    #[error("Invalid response from the server: {0}")]
+    InvalidResponse(String),
+    #[error("Local storage error: {0}")]
+    LocalStorage(String),
+    #[error("The network client has stopped")]
+    ClientStopped,
+    #[error("The network client is busy, the request was not sent")]
+    ClientBusy,
+}
+
+impl From<tonic::Status> for ClientError {
+    fn from(status: tonic::Status) -> Self {
+        let message = status.message().to_string();
+
+        match status.code() {
+            tonic::Code::NotFound => Self::NotFound(message),
+            tonic::Code::AlreadyExists => Self::AlreadyExists(message),
+            tonic::Code::InvalidArgument | tonic::Code::FailedPrecondition => {
+                Self::InvalidRequest(message)
+            }
+            tonic::Code::Unavailable => Self::Unavailable(message),
+            tonic::Code::ResourceExhausted => Self::RateLimited(message),
+            tonic::Code::PermissionDenied => Self::InviteRequired(message),
+            tonic::Code::Aborted => Self::RemovedFromRoom(message),
+            _ => Self::Server(message),
+        }
+    }
+}
+
+/// State of the connection with the server, the network client keeps reconnecting until it is closed
+#[derive(Debug, PartialEq, Eq, Clone, PartialOrd)]
+pub enum ConnectionState {
+    Connecting,
+    Connected,
+    Reconnecting {
+        attempt: u32,
+        retry_in: std::time::Duration,
+    },
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Clone)]
@@ -23,13 +118,66 @@ pub struct UserDetails {
     pub rank: u32,
 }
 
+#[derive(Debug, PartialEq, Eq, PartialOrd, Clone)]
+pub struct PublicRoom {
+    pub room_id: String,
+    pub room_size: u32,
+    pub player_count: u32,
+    /// Not set if the host has left the room
+    pub host_name: Option<String>,
+    pub age_seconds: u64,
+}
+
 pub enum NewRequestEntity {
-    JoinRoom { room_id: String },
+    JoinRoom {
+        room_id: String,
+    },
+    JoinWithInvite {
+        invite_code: String,
+    },
     CreateRoom,
+    /// Create a private room whose invite code can only be used once
+    CreateSingleUseRoom,
+    /// Create a room that is listed in the room browser
+    CreatePublicRoom,
+    ListRooms,
     NewGame,
+    LeaveRoom,
+    SendRoomChat {
+        message: String,
+    },
 }
 
+/// The network client stops once the application cancels it or drops the request sender
 pub enum Request {
     New(NewRequestEntity),
-    Quit,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn denied_and_aborted_requests_are_explained() {
+        let error = ClientError::from(tonic::Status::permission_denied(
+            "The room with id room_1 can only be joined with its invite code",
+        ));
+        assert_eq!(
+            error,
+            ClientError::InviteRequired(
+                "The room with id room_1 can only be joined with its invite code".to_string()
+            )
+        );
+        assert!(error.to_string().contains("ask the host"));
+
+        let error = ClientError::from(tonic::Status::aborted(
+            "The room was deleted by an administrator",
+        ));
+        assert!(matches!(error, ClientError::RemovedFromRoom(_)));
+
+        let error = ClientError::from(tonic::Status::resource_exhausted(
+            "Too many calls to Ping, retry later",
+        ));
+        assert!(matches!(error, ClientError::RateLimited(_)));
+    }
 }
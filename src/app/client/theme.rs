@@ -0,0 +1,225 @@
+/// The theme holds the colors of the application, every component reads its styles from the theme
+///
+/// A built-in theme is picked in the `[theme]` section of client.toml, a custom theme starts from a built-in
+/// theme and changes any of its colors in `[theme.colors]`
+///
+/// ```toml
+/// [theme]
+/// preset = "dark"
+///
+/// [theme.colors]
+/// border_focused = "#56b4e9"
+/// error = "rgb(230, 159, 0)"
+/// ```
+use std::collections::HashMap;
+
+use tuirealm::{
+    props::{Borders, Color, Style},
+    utils::parser::parse_color,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemePreset {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+    /// Avoids telling the states apart by red and green only, based on the Okabe-Ito palette
+    ColorBlind,
+}
+
+/// The colors of a theme that can be changed in client.toml
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeColor {
+    Text,
+    Muted,
+    Border,
+    BorderFocused,
+    Highlight,
+    Info,
+    Success,
+    Error,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct ThemeConfig {
+    #[serde(default)]
+    pub preset: ThemePreset,
+    /// The colors listed here replace the colors of the preset, either as a name, `#rrggbb` or `rgb(r, g, b)`
+    #[serde(default)]
+    pub colors: HashMap<ThemeColor, String>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Theme {
+    pub text: Color,
+    /// Secondary information, such as the time of the network logs
+    pub muted: Color,
+    pub border: Color,
+    /// The borders of the panel that receives the keyboard input
+    pub border_focused: Color,
+    /// The selected item of a list
+    pub highlight: Color,
+    pub info: Color,
+    pub success: Color,
+    pub error: Color,
+}
+
+impl Theme {
+    pub fn new(config: &ThemeConfig) -> Self {
+        let mut theme = Self::from_preset(config.preset);
+
+        for (theme_color, color) in &config.colors {
+            match parse_color(color.trim()) {
+                Some(color) => theme.set_color(*theme_color, color),
+                None => {
+                    tracing::warn!("Invalid color {color} for {theme_color:?}, keeping the color of the preset")
+                }
+            }
+        }
+
+        theme
+    }
+
+    fn from_preset(preset: ThemePreset) -> Self {
+        match preset {
+            ThemePreset::Dark => Self {
+                text: Color::Reset,
+                muted: Color::DarkGray,
+                border: Color::Reset,
+                border_focused: Color::Green,
+                highlight: Color::Gray,
+                info: Color::Gray,
+                success: Color::Green,
+                error: Color::Red,
+            },
+            ThemePreset::Light => Self {
+                text: Color::Black,
+                muted: Color::Gray,
+                border: Color::DarkGray,
+                border_focused: Color::Blue,
+                highlight: Color::LightBlue,
+                info: Color::DarkGray,
+                success: Color::Green,
+                error: Color::Red,
+            },
+            ThemePreset::HighContrast => Self {
+                text: Color::White,
+                muted: Color::White,
+                border: Color::White,
+                border_focused: Color::Yellow,
+                highlight: Color::Yellow,
+                info: Color::White,
+                success: Color::LightGreen,
+                error: Color::LightRed,
+            },
+            ThemePreset::ColorBlind => Self {
+                text: Color::Reset,
+                muted: Color::DarkGray,
+                border: Color::Reset,
+                border_focused: Color::Rgb(86, 180, 233),
+                highlight: Color::Gray,
+                info: Color::Gray,
+                success: Color::Rgb(86, 180, 233),
+                error: Color::Rgb(230, 159, 0),
+            },
+        }
+    }
+
+    fn set_color(&mut self, theme_color: ThemeColor, color: Color) {
+        let field = match theme_color {
+            ThemeColor::Text => &mut self.text,
+            ThemeColor::Muted => &mut self.muted,
+            ThemeColor::Border => &mut self.border,
+            ThemeColor::BorderFocused => &mut self.border_focused,
+            ThemeColor::Highlight => &mut self.highlight,
+            ThemeColor::Info => &mut self.info,
+            ThemeColor::Success => &mut self.success,
+            ThemeColor::Error => &mut self.error,
+        };
+
+        *field = color;
+    }
+
+    /// The borders of the focused panel are highlighted
+    pub fn get_borders(&self, is_focused: bool) -> Borders {
+        if is_focused {
+            Borders::default().color(self.border_focused)
+        } else {
+            Borders::default().color(self.border)
+        }
+    }
+
+    /// The style of the borders used by the components while they are not focused
+    pub fn get_inactive_style(&self) -> Style {
+        Style::default().fg(self.border)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_config(preset: ThemePreset, colors: &[(ThemeColor, &str)]) -> ThemeConfig {
+        ThemeConfig {
+            preset,
+            colors: colors
+                .iter()
+                .map(|(theme_color, color)| (*theme_color, color.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn the_colors_of_the_preset_are_used_by_default() {
+        let theme = Theme::new(&get_config(ThemePreset::HighContrast, &[]));
+
+        assert_eq!(theme.border_focused, Color::Yellow);
+        assert_eq!(theme.error, Color::LightRed);
+    }
+
+    #[test]
+    fn the_colors_replace_the_colors_of_the_preset() {
+        let theme = Theme::new(&get_config(
+            ThemePreset::Dark,
+            &[
+                (ThemeColor::BorderFocused, "#56b4e9"),
+                (ThemeColor::Error, " rgb(230, 159, 0) "),
+                (ThemeColor::Muted, "blue"),
+            ],
+        ));
+
+        assert_eq!(theme.border_focused, Color::Rgb(86, 180, 233));
+        assert_eq!(theme.error, Color::Rgb(230, 159, 0));
+        assert_eq!(theme.muted, Color::Blue);
+        assert_eq!(theme.success, Color::Green);
+    }
+
+    #[test]
+    fn invalid_colors_keep_the_colors_of_the_preset() {
+        let theme = Theme::new(&get_config(
+            ThemePreset::Light,
+            &[
+                (ThemeColor::Highlight, "not a color"),
+                (ThemeColor::Text, "#12345"),
+            ],
+        ));
+
+        assert_eq!(theme.highlight, Color::LightBlue);
+        assert_eq!(theme.text, Color::Black);
+    }
+
+    #[test]
+    fn the_color_blind_preset_does_not_rely_on_red_and_green() {
+        let theme = Theme::new(&get_config(ThemePreset::ColorBlind, &[]));
+
+        for color in [theme.success, theme.error, theme.border_focused] {
+            assert!(
+                ![Color::Red, Color::Green, Color::LightRed, Color::LightGreen].contains(&color),
+                "{color:?} is red or green"
+            );
+        }
+    }
+}
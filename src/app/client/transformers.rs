@@ -6,6 +6,15 @@ impl From<types::MenuSelection> for network_types::NewRequestEntity {
         match item_selection {
             types::MenuSelection::NewGame => network_types::NewRequestEntity::NewGame,
             types::MenuSelection::CreateRoom => network_types::NewRequestEntity::CreateRoom,
+            types::MenuSelection::CreateSingleUseRoom => {
+                network_types::NewRequestEntity::CreateSingleUseRoom
+            }
+            types::MenuSelection::CreatePublicRoom => {
+                network_types::NewRequestEntity::CreatePublicRoom
+            }
+            types::MenuSelection::JoinWithInvite { invite_code } => {
+                network_types::NewRequestEntity::JoinWithInvite { invite_code }
+            }
             types::MenuSelection::JoinRoom { room_id } => {
                 network_types::NewRequestEntity::JoinRoom { room_id }
             }
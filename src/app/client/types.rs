@@ -1,6 +1,33 @@
+use super::{keymap::KeymapConfig, theme::ThemeConfig};
+
 #[derive(serde::Deserialize)]
 pub struct ClientConfig {
     pub server_url: String,
+    /// Filter of the logs written to file when the client is built with the `client_logs` feature
+    #[serde(default = "default_log_level")]
+    pub log_level: String,
+    #[serde(default)]
+    pub keymap: KeymapConfig,
+    #[serde(default)]
+    pub theme: ThemeConfig,
+    /// The client connects over TLS when this is configured, `server_url` must then use `https`
+    pub tls: Option<ClientTlsConfig>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct ClientTlsConfig {
+    /// PEM encoded CA certificates that the certificate of the server is verified with
+    pub ca_certificate_path: String,
+    /// Name expected in the certificate of the server, the host of `server_url` is expected if not set
+    pub domain: Option<String>,
+    /// PEM encoded certificate presented to the servers that require client certificates ( mTLS )
+    pub certificate_path: Option<String>,
+    /// PEM encoded private key of the client certificate
+    pub key_path: Option<String>,
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
 }
 
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -28,6 +55,10 @@ pub enum AppStateUpdate {
     UserRoomJoin {
         users: Vec<UserDetails>,
     },
+    UserRoomLeave {
+        users: Vec<UserDetails>,
+    },
+    RoomLeft,
     GameStart {
         room_id: String,
         users: Vec<UserDetails>,
@@ -48,7 +79,13 @@ pub struct UserDetails {
 pub enum Msg {
     AppClose,
     BottomBarUpdate,
+    Focus(FocusChange),
+    LeaveRoom,
     Menu(MenuMessage),
+    NextTab,
+    RoomBrowser(RoomBrowserMessage),
+    /// Send a chat message to the users of the room
+    SendRoomChat(String),
     StateUpdate(AppStateUpdate),
     ReDraw,
 }
@@ -61,6 +98,46 @@ pub enum Id {
     RoomDetails,
     NetworkReceptor,
     Help,
+    Tabs,
+    RoomBrowser,
+}
+
+/// The panels that can take the keyboard input
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Panel {
+    Menu,
+    /// Only available while the room browser is open
+    RoomBrowser,
+    Users,
+    Chat,
+    Details,
+    Log,
+}
+
+/// The side panels are shown one at a time as tabs on narrow terminals
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum SideTab {
+    #[default]
+    Details,
+    Navigation,
+}
+
+impl SideTab {
+    pub fn next(&self) -> Self {
+        match self {
+            SideTab::Details => SideTab::Navigation,
+            SideTab::Navigation => SideTab::Details,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum FocusChange {
+    Next,
+    Previous,
+    Panel(Panel),
+    /// Go back to the panel that was focused before the current one
+    Return,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -74,7 +151,24 @@ pub enum MenuMessage {
 pub enum MenuSelection {
     NewGame,
     CreateRoom,
-    JoinRoom { room_id: String },
+    /// A private room whose invite code can only be used once
+    CreateSingleUseRoom,
+    CreatePublicRoom,
+    JoinWithInvite {
+        invite_code: String,
+    },
+    /// A public room picked in the room browser
+    JoinRoom {
+        room_id: String,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RoomBrowserMessage {
+    Open,
+    Close,
+    /// Ask the server for the public rooms again
+    Refresh,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -99,6 +193,10 @@ pub struct AppState {
 }
 
 impl AppState {
+    pub fn is_in_room(&self) -> bool {
+        self.room_details.is_some()
+    }
+
     pub fn apply_update(self, update: AppStateUpdate) -> Self {
         match update {
             AppStateUpdate::UserIdUpdate { user_id } => Self {
@@ -116,10 +214,13 @@ impl AppState {
                     ..self
                 }
             }
-            AppStateUpdate::UserRoomJoin { users } => {
-                let previous_room_state = self.room_details.expect(
-                    "Message ordering is invalid. Expected room details before user room join",
-                );
+            AppStateUpdate::UserRoomJoin { users } | AppStateUpdate::UserRoomLeave { users } => {
+                let Some(previous_room_state) = self.room_details else {
+                    tracing::warn!(
+                        "Message ordering is invalid. Expected room details before the users of the room"
+                    );
+                    return self;
+                };
 
                 let new_room_state = RoomState {
                     room_users: users,
@@ -131,6 +232,10 @@ impl AppState {
                     ..self
                 }
             }
+            AppStateUpdate::RoomLeft => Self {
+                room_details: None,
+                ..self
+            },
             AppStateUpdate::GameStart { room_id, users } => {
                 let room_state = RoomState {
                     room_id,
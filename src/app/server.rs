@@ -1,70 +1,211 @@
 pub mod errors;
 pub mod grpc;
+pub mod logging;
+pub mod metrics;
+pub mod rate_limit;
+pub mod shutdown;
 
+use tokio_util::sync::CancellationToken;
+
+use crate::app::{self, types};
+use app::server::errors::ServerError;
 use app::server::grpc::{
-    server::{grpc_server, MyGrpc, FILE_DESCRIPTOR_SET},
-    utils::create_redis_client,
+    admin::{AdminAuthenticator, AdminService},
+    functions::room_chat,
+    health,
+    server::{admin_server, grpc_server, MyGrpc, FILE_DESCRIPTOR_SET},
+    storage::Store,
+    utils::{create_redis_client, get_server_tls_config},
 };
-use tokio_stream::StreamExt;
 
-fn get_signals() -> signal_hook_tokio::Signals {
-    signal_hook_tokio::Signals::new(&[signal_hook::consts::SIGINT])
-        .expect("Unable to register the signals for graceful shutdown")
+/// A server running in the background, dropping the handle does not stop the server
+pub struct ServerHandle {
+    local_address: std::net::SocketAddr,
+    shutdown_token: CancellationToken,
+    join_handle: tokio::task::JoinHandle<()>,
+    /// The health reporting, the room chat delivery and the metrics server, which stop along with the server
+    background_handles: Vec<tokio::task::JoinHandle<()>>,
 }
 
-async fn handle_signal(
-    mut signals: signal_hook_tokio::Signals,
-    shutdown_informer: tokio::sync::oneshot::Sender<()>,
-) {
-    while let Some(signal) = signals.next().await {
-        match signal {
-            signal_hook::consts::SIGINT => {
-                tracing::warn!("Received signal to shutdown");
-                shutdown_informer
-                    .send(())
-                    .expect("Unable to inform shutdown signal");
-                break;
+impl ServerHandle {
+    /// The address that the server is bound to, useful when binding to port 0
+    pub fn local_address(&self) -> std::net::SocketAddr {
+        self.local_address
+    }
+
+    /// Drain the sessions and wait until the server has stopped
+    pub async fn shutdown(self) {
+        self.shutdown_token.cancel();
+        self.wait().await;
+    }
+
+    /// Wait until the server and its background tasks have stopped, once the shutdown signal is received
+    pub async fn wait(self) {
+        if let Err(error) = self.join_handle.await {
+            tracing::error!(?error, "The server task failed");
+        }
+
+        for background_handle in self.background_handles {
+            if let Err(error) = background_handle.await {
+                tracing::error!(?error, "A background task of the server failed");
             }
-            _ => unreachable!(),
         }
     }
 }
 
-use crate::app::{self, types};
-
+/// Start serving on the given listener, the server drains the sessions and stops once `shutdown_signal` completes
+///
+/// Pass `shutdown::wait_for_signal()` to stop on SIGINT and SIGTERM, `token.cancelled_owned()` to stop with a
+/// `CancellationToken`, or `std::future::pending()` to stop only through the returned handle
 pub async fn start_server(
+    mut server_config: types::ServerConfig,
+    tcp_listener: tokio::net::TcpListener,
+    shutdown_signal: impl std::future::Future<Output = ()> + Send + 'static,
+) -> Result<ServerHandle, ServerError> {
+    let redis_client = create_redis_client(server_config.redis.take().unwrap_or_default()).await?;
+
+    start_server_with_store(
+        server_config,
+        tcp_listener,
+        Store::new(redis_client),
+        shutdown_signal,
+    )
+    .await
+}
+
+/// Serve on the given listener with the given store, the redis configuration is not used
+///
+/// The store can be cloned before it is passed, to inspect the state of the server in the tests
+pub async fn start_server_with_store(
     server_config: types::ServerConfig,
     tcp_listener: tokio::net::TcpListener,
-) {
-    let redis_client = create_redis_client(server_config.redis.unwrap_or_default())
+    store: Store,
+    shutdown_signal: impl std::future::Future<Output = ()> + Send + 'static,
+) -> Result<ServerHandle, ServerError> {
+    let local_address = tcp_listener
+        .local_addr()
+        .map_err(ServerError::ListenerAddress)?;
+    let rate_limiter = server_config.rate_limit.map(|rate_limit_config| {
+        std::sync::Arc::new(rate_limit::RateLimiter::new(rate_limit_config))
+    });
+    let service = MyGrpc::new(store, rate_limiter.clone()).await;
+
+    // Readiness of the server is reported through the standard grpc health service
+    // Both stop once the sessions are drained, which cancels the shutdown token of the store
+    let mut background_handles = Vec::new();
+    let (health_reporter, health_service) = tonic_health::server::health_reporter();
+    background_handles.push(tokio::spawn(health::report_readiness(
+        health_reporter.clone(),
+        service.store.clone(),
+        service.store.shutdown_token.clone(),
+    )));
+
+    // The chat messages are published to every server, the subscription is made before any message is sent
+    let chat_subscription = service
+        .store
+        .redis_client
+        .subscribe(grpc::types::ROOM_CHAT_CHANNEL)
         .await
-        .unwrap();
+        .map_err(ServerError::ChatSubscription)?;
+    background_handles.push(tokio::spawn(room_chat::deliver_room_chat(
+        service.store.clone(),
+        chat_subscription,
+    )));
 
-    let (sender, receiver) = tokio::sync::oneshot::channel();
-    let shutdown_signals = get_signals();
-    tokio::spawn(handle_signal(shutdown_signals, sender));
+    let admin_service = server_config.admin.map(|admin_config| {
+        admin_server::AdminServer::with_interceptor(
+            AdminService::new(service.store.clone(), health_reporter),
+            AdminAuthenticator::new(&admin_config.token),
+        )
+    });
+
+    if let Some(metrics_config) = server_config.metrics {
+        let host = server_config.server.unwrap_or_default().host;
+        let metrics_address = format!("{host}:{}", metrics_config.port);
+        let metrics_listener = tokio::net::TcpListener::bind(&metrics_address)
+            .await
+            .map_err(|source| ServerError::MetricsAddress {
+                address: metrics_address.clone(),
+                source,
+            })?;
 
-    let service = MyGrpc::new(redis_client).await;
+        tracing::info!("Serving metrics on {metrics_address}/metrics");
+        background_handles.push(tokio::spawn(metrics::serve_metrics(
+            metrics_listener,
+            service.store.clone(),
+            service.store.shutdown_token.clone().cancelled_owned(),
+        )));
+    }
 
     let reflection_service = tonic_reflection::server::Builder::configure()
         .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
-        .build()
-        .unwrap();
+        .build()?;
+
+    let store = service.store.clone();
+    let service_shutdown_token = service.store.shutdown_token.clone();
+    let grace_period = std::time::Duration::from_secs(server_config.shutdown.grace_period_seconds);
+
+    let shutdown_token = CancellationToken::new();
+    let server_shutdown_token = shutdown_token.clone();
+    let stop_accepting = CancellationToken::new();
+    let incoming = shutdown::Incoming::new(tcp_listener, stop_accepting.clone());
+
+    let mut server_builder = tonic::transport::Server::builder();
+    if let Some(tls_config) = &server_config.tls {
+        let server_tls_config = get_server_tls_config(tls_config)
+            .await
+            .map_err(ServerError::TlsCertificates)?;
+
+        server_builder = server_builder
+            .tls_config(server_tls_config)
+            .map_err(ServerError::TlsConfiguration)?;
+        tracing::info!(
+            "TLS is enabled, client certificates are {}",
+            if tls_config.client_ca_path.is_some() {
+                "required"
+            } else {
+                "not required"
+            }
+        );
+    }
 
-    tracing::info!("Server successfully running");
+    tracing::info!("Server successfully running on {local_address}");
 
-    tonic::transport::Server::builder()
+    let server = server_builder
+        .layer(logging::RequestTracingLayer)
+        .layer(metrics::RpcMetricsLayer)
+        // The rejected calls are still logged and counted by the outer layers
+        .layer(rate_limit::RateLimitLayer::new(rate_limiter))
         .add_service(reflection_service)
+        .add_service(health_service)
+        .add_optional_service(admin_service)
         .add_service(grpc_server::GrpcServer::new(service))
-        .serve_with_incoming_shutdown(
-            tokio_stream::wrappers::TcpListenerStream::new(tcp_listener),
-            async {
-                receiver.await.unwrap();
-                tracing::info!("Initiating graceful shutdown, waiting for tasks to finish");
-            },
-        )
-        .await
-        .expect("Could not start the server");
+        .serve_with_incoming_shutdown(incoming, async move {
+            tokio::select! {
+                _ = shutdown_signal => {},
+                _ = server_shutdown_token.cancelled() => {},
+            }
+
+            // The new connections are refused before the connected sessions are drained
+            stop_accepting.cancel();
+            tracing::info!("Initiating graceful shutdown, waiting for games to finish");
+            shutdown::drain_sessions(&store, grace_period).await;
+        });
+
+    let join_handle = tokio::spawn(async move {
+        if let Err(error) = server.await {
+            tracing::error!(?error, "The server has failed");
+        }
+
+        // The background tasks are stopped as well when the server fails before the sessions are drained
+        service_shutdown_token.cancel();
+        tracing::info!("Server shutdown");
+    });
 
-    tracing::info!("Server shutdown");
+    Ok(ServerHandle {
+        local_address,
+        shutdown_token,
+        join_handle,
+        background_handles,
+    })
 }
@@ -8,10 +8,35 @@ pub enum DbError {
     DuplicateValue,
     #[error("Failed to parse value")]
     ParsingFailure,
+    #[error("The value kept changing while it was updated")]
+    UpdateConflict,
     #[error("Unknown Database error")]
     Others(#[from] fred::error::RedisError),
 }
 
+/// The errors that prevent the server from starting
+#[derive(Error, Debug)]
+pub enum ServerError {
+    #[error("Could not connect to redis")]
+    Redis(#[from] fred::error::RedisError),
+    #[error("Could not get the address of the listener")]
+    ListenerAddress(#[source] std::io::Error),
+    #[error("Could not bind to the metrics address {address}")]
+    MetricsAddress {
+        address: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Could not read the TLS certificates")]
+    TlsCertificates(#[source] std::io::Error),
+    #[error("Invalid TLS configuration")]
+    TlsConfiguration(#[source] tonic::transport::Error),
+    #[error("Could not build the reflection service")]
+    Reflection(#[from] tonic_reflection::server::Error),
+    #[error("Could not subscribe to the room chat")]
+    ChatSubscription(#[source] DbError),
+}
+
 #[derive(Error, Debug)]
 pub enum ApiError {
     #[error("The user with id {user_id} does not exist")]
@@ -20,12 +45,26 @@ pub enum ApiError {
     UserAlreadyExists { user_id: String },
     #[error("The room with id {room_id} does not exist")]
     RoomNotFound { room_id: String },
-    #[error("The room with id {room_id} already exists")]
-    RoomAlreadyExists { room_id: String },
+    #[error("The invite code {invite_code} does not exist or has expired")]
+    InviteNotFound { invite_code: String },
+    #[error("The room with id {room_id} can only be joined with its invite code")]
+    InviteRequired { room_id: String },
+    #[error("The game with id {game_id} does not exist")]
+    GameNotFound { game_id: String },
+    #[error("The game with id {game_id} can no longer be resumed")]
+    GameNotResumable { game_id: String },
+    #[error("The user with id {user_id} is not connected to this server")]
+    SessionNotFound { user_id: String },
+    #[error("The server is draining and does not accept new rooms")]
+    ServerDraining,
     #[error("Internal Server error")]
     InternalServerError,
     #[error("Bad Request {message}")]
     BadRequest { message: String },
+    #[error("Too many calls to {method}, retry later")]
+    RateLimited { method: String },
+    #[error("A user can have at most {limit} rooms open at once")]
+    TooManyRooms { limit: usize },
 }
 
 impl DbError {
@@ -77,9 +116,16 @@ impl From<ApiError> for tonic::Status {
             ApiError::UserNotFound { .. } => tonic::Code::NotFound,
             ApiError::RoomNotFound { .. } => tonic::Code::NotFound,
             ApiError::UserAlreadyExists { .. } => tonic::Code::AlreadyExists,
-            ApiError::RoomAlreadyExists { .. } => tonic::Code::AlreadyExists,
+            ApiError::InviteNotFound { .. } => tonic::Code::NotFound,
+            ApiError::InviteRequired { .. } => tonic::Code::PermissionDenied,
+            ApiError::GameNotFound { .. } => tonic::Code::NotFound,
+            ApiError::GameNotResumable { .. } => tonic::Code::FailedPrecondition,
+            ApiError::SessionNotFound { .. } => tonic::Code::NotFound,
+            ApiError::ServerDraining => tonic::Code::Unavailable,
             ApiError::InternalServerError => tonic::Code::Internal,
             ApiError::BadRequest { .. } => tonic::Code::InvalidArgument,
+            ApiError::RateLimited { .. } => tonic::Code::ResourceExhausted,
+            ApiError::TooManyRooms { .. } => tonic::Code::ResourceExhausted,
         };
 
         Self::new(code, api_error.to_string())
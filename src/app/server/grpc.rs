@@ -1,4 +1,9 @@
+pub mod admin;
+pub mod anti_cheat;
+pub mod chat_filter;
 pub mod functions;
+pub mod health;
+pub mod invite_code;
 pub mod redis_client;
 pub mod server;
 pub mod storage;
@@ -0,0 +1,382 @@
+use subtle::ConstantTimeEq;
+use tonic_health::{server::HealthReporter, ServingStatus};
+
+use crate::app::server::errors::{self, ResultExtApp};
+
+use super::{
+    functions::room_service,
+    health,
+    server::{
+        admin_server, AdminCheatFlag, AdminDrainRequest, AdminDrainResponse, AdminEmptyResponse,
+        AdminEntityRequest, AdminGameDetails, AdminListRequest, AdminListResponse,
+        AdminRoomDetails, AdminUserDetails,
+    },
+    storage::{
+        interface::{
+            game::GameInterface, room::RoomInterface, session::SessionInterface,
+            user::UserInterface,
+        },
+        models, Store,
+    },
+    types,
+};
+
+impl From<models::Room> for AdminRoomDetails {
+    fn from(room: models::Room) -> Self {
+        Self {
+            room_id: room.room_id,
+            room_size: room.room_size.into(),
+            users: room.users,
+        }
+    }
+}
+
+impl From<models::Game> for AdminGameDetails {
+    fn from(game: models::Game) -> Self {
+        Self {
+            game_id: game.game_id,
+            users_in_game: game.users_in_game,
+            game_status: format!("{:?}", game.game_status),
+            prompt: game.prompt,
+            cheat_flags: game.cheat_flags.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<models::CheatFlag> for AdminCheatFlag {
+    fn from(flag: models::CheatFlag) -> Self {
+        Self {
+            user_id: flag.user_id,
+            kind: format!("{:?}", flag.kind),
+            progress: flag.progress as u32,
+            flagged_at: flag.flagged_at,
+        }
+    }
+}
+
+impl From<models::User> for AdminUserDetails {
+    fn from(user: models::User) -> Self {
+        Self {
+            user_id: user.user_id,
+            user_name: user.user_name,
+            games_played: user.games_played as u32,
+            rank: user.player_rank as u32,
+            room_id: user.room_id,
+            game_id: user.game_id,
+        }
+    }
+}
+
+/// Rejects the requests that do not carry the configured admin token
+#[derive(Clone)]
+pub struct AdminAuthenticator {
+    authorization: String,
+}
+
+impl AdminAuthenticator {
+    pub fn new(token: &str) -> Self {
+        Self {
+            authorization: format!("Bearer {token}"),
+        }
+    }
+}
+
+impl tonic::service::Interceptor for AdminAuthenticator {
+    fn call(&mut self, request: tonic::Request<()>) -> Result<tonic::Request<()>, tonic::Status> {
+        let authorization = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok());
+
+        // The token is compared in constant time, so the time of the comparison tells nothing about the token
+        let is_authorized = authorization.is_some_and(|authorization| {
+            authorization
+                .as_bytes()
+                .ct_eq(self.authorization.as_bytes())
+                .into()
+        });
+
+        if is_authorized {
+            Ok(request)
+        } else {
+            Err(tonic::Status::unauthenticated("Invalid admin token"))
+        }
+    }
+}
+
+pub struct AdminService {
+    store: Store,
+    health_reporter: HealthReporter,
+}
+
+impl AdminService {
+    pub fn new(store: Store, health_reporter: HealthReporter) -> Self {
+        Self {
+            store,
+            health_reporter,
+        }
+    }
+}
+
+/// Close the room session of the user, if the user is connected to this instance
+async fn close_session(store: &Store, user_id: &str, message: &str) {
+    if store
+        .close_channel(user_id, tonic::Status::aborted(message))
+        .await
+        .is_err()
+    {
+        tracing::debug!("User {user_id} has no session to close");
+    }
+}
+
+/// Clear the room or the game that the users refer to, once the room or the game is deleted
+async fn clear_user_references(
+    store: &Store,
+    user_ids: Vec<String>,
+    clear_reference: impl Fn(&mut models::User) -> bool,
+) -> Result<(), tonic::Status> {
+    let users = store
+        .get_multiple_users(user_ids)
+        .await
+        .to_internal_api_error()?;
+
+    for mut user in users {
+        // The user might have moved to another room or game already
+        if clear_reference(&mut user) {
+            store.insert_user(user).await.to_internal_api_error()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn sorted(mut ids: Vec<String>) -> AdminListResponse {
+    ids.sort();
+    AdminListResponse { ids }
+}
+
+#[tonic::async_trait]
+impl admin_server::Admin for AdminService {
+    async fn list_rooms(
+        &self,
+        _request: tonic::Request<AdminListRequest>,
+    ) -> Result<tonic::Response<AdminListResponse>, tonic::Status> {
+        let room_ids = self
+            .store
+            .get_all_room_ids()
+            .await
+            .to_internal_api_error()?;
+
+        Ok(tonic::Response::new(sorted(room_ids)))
+    }
+
+    async fn get_room(
+        &self,
+        request: tonic::Request<AdminEntityRequest>,
+    ) -> Result<tonic::Response<AdminRoomDetails>, tonic::Status> {
+        let room_id = request.into_inner().id;
+        let room = self
+            .store
+            .find_room(&room_id)
+            .await
+            .to_not_found(errors::ApiError::RoomNotFound { room_id })?;
+
+        Ok(tonic::Response::new(room.into()))
+    }
+
+    async fn delete_room(
+        &self,
+        request: tonic::Request<AdminEntityRequest>,
+    ) -> Result<tonic::Response<AdminEmptyResponse>, tonic::Status> {
+        let room_id = request.into_inner().id;
+
+        if room_id == types::COMMON_ROOM_KEY {
+            Err(errors::ApiError::BadRequest {
+                message: "The common room cannot be deleted".to_string(),
+            })?
+        }
+
+        let room =
+            self.store
+                .find_room(&room_id)
+                .await
+                .to_not_found(errors::ApiError::RoomNotFound {
+                    room_id: room_id.clone(),
+                })?;
+
+        clear_user_references(&self.store, room.users.clone(), |user| {
+            let is_in_room = user.room_id.as_ref() == Some(&room_id);
+            if is_in_room {
+                user.clear_room_id();
+            }
+            is_in_room
+        })
+        .await?;
+
+        self.store
+            .delete_room(&room_id)
+            .await
+            .to_internal_api_error()?;
+
+        for user_id in &room.users {
+            close_session(
+                &self.store,
+                user_id,
+                "The room was deleted by an administrator",
+            )
+            .await;
+        }
+
+        tracing::warn!("Room {room_id} was deleted by an administrator");
+        Ok(tonic::Response::new(AdminEmptyResponse {}))
+    }
+
+    async fn list_games(
+        &self,
+        _request: tonic::Request<AdminListRequest>,
+    ) -> Result<tonic::Response<AdminListResponse>, tonic::Status> {
+        let game_ids = self
+            .store
+            .get_all_game_ids()
+            .await
+            .to_internal_api_error()?;
+
+        Ok(tonic::Response::new(sorted(game_ids)))
+    }
+
+    async fn get_game(
+        &self,
+        request: tonic::Request<AdminEntityRequest>,
+    ) -> Result<tonic::Response<AdminGameDetails>, tonic::Status> {
+        let game_id = request.into_inner().id;
+        let game = self
+            .store
+            .find_game(&game_id)
+            .await
+            .to_not_found(errors::ApiError::GameNotFound { game_id })?;
+
+        Ok(tonic::Response::new(game.into()))
+    }
+
+    async fn delete_game(
+        &self,
+        request: tonic::Request<AdminEntityRequest>,
+    ) -> Result<tonic::Response<AdminEmptyResponse>, tonic::Status> {
+        let game_id = request.into_inner().id;
+
+        let game =
+            self.store
+                .find_game(&game_id)
+                .await
+                .to_not_found(errors::ApiError::GameNotFound {
+                    game_id: game_id.clone(),
+                })?;
+
+        clear_user_references(&self.store, game.users_in_game, |user| {
+            let is_in_game = user.game_id.as_ref() == Some(&game_id);
+            if is_in_game {
+                user.clear_game_id();
+            }
+            is_in_game
+        })
+        .await?;
+
+        self.store
+            .delete_game(&game_id)
+            .await
+            .to_internal_api_error()?;
+
+        tracing::warn!("Game {game_id} was deleted by an administrator");
+        Ok(tonic::Response::new(AdminEmptyResponse {}))
+    }
+
+    async fn list_users(
+        &self,
+        _request: tonic::Request<AdminListRequest>,
+    ) -> Result<tonic::Response<AdminListResponse>, tonic::Status> {
+        let user_ids = self
+            .store
+            .get_all_user_ids()
+            .await
+            .to_internal_api_error()?;
+
+        Ok(tonic::Response::new(sorted(user_ids)))
+    }
+
+    async fn get_user(
+        &self,
+        request: tonic::Request<AdminEntityRequest>,
+    ) -> Result<tonic::Response<AdminUserDetails>, tonic::Status> {
+        let user_id = request.into_inner().id;
+        let user = self
+            .store
+            .find_user(&user_id)
+            .await
+            .to_not_found(errors::ApiError::UserNotFound { user_id })?;
+
+        Ok(tonic::Response::new(user.into()))
+    }
+
+    async fn delete_user(
+        &self,
+        request: tonic::Request<AdminEntityRequest>,
+    ) -> Result<tonic::Response<AdminEmptyResponse>, tonic::Status> {
+        let user_id = request.into_inner().id;
+
+        // Removes the user from the room and informs the other users of the room
+        room_service::leave_current_room(&self.store, &user_id).await?;
+
+        self.store
+            .delete_user(&user_id)
+            .await
+            .to_internal_api_error()?;
+
+        close_session(
+            &self.store,
+            &user_id,
+            "The user was deleted by an administrator",
+        )
+        .await;
+
+        tracing::warn!("User {user_id} was deleted by an administrator");
+        Ok(tonic::Response::new(AdminEmptyResponse {}))
+    }
+
+    async fn kick_session(
+        &self,
+        request: tonic::Request<AdminEntityRequest>,
+    ) -> Result<tonic::Response<AdminEmptyResponse>, tonic::Status> {
+        let user_id = request.into_inner().id;
+
+        self.store
+            .close_channel(
+                &user_id,
+                tonic::Status::aborted("The session was closed by an administrator"),
+            )
+            .await
+            .to_not_found(errors::ApiError::SessionNotFound {
+                user_id: user_id.clone(),
+            })?;
+
+        tracing::warn!("Session of user {user_id} was closed by an administrator");
+        Ok(tonic::Response::new(AdminEmptyResponse {}))
+    }
+
+    async fn drain(
+        &self,
+        _request: tonic::Request<AdminDrainRequest>,
+    ) -> Result<tonic::Response<AdminDrainResponse>, tonic::Status> {
+        if !self.store.is_draining() {
+            tracing::warn!("Draining the server, new rooms will not be accepted");
+            self.store.start_draining();
+        }
+
+        let mut health_reporter = self.health_reporter.clone();
+        health::set_serving_status(&mut health_reporter, ServingStatus::NotServing).await;
+
+        let connected_sessions = self.store.get_session_count() as u32;
+        Ok(tonic::Response::new(AdminDrainResponse {
+            connected_sessions,
+        }))
+    }
+}
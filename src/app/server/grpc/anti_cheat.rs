@@ -0,0 +1,282 @@
+use crate::app::server::grpc::storage::models::{CheatFlagKind, PlayerProgress, TypingTiming};
+
+/// About 300 words per minute, faster than the fastest typists
+pub const MAX_CHARACTERS_PER_SECOND: f64 = 25.0;
+/// Characters allowed on top of the rate, for the updates that are batched by the client or the network
+pub const MAX_BURST_CHARACTERS: usize = 10;
+/// Longer intervals are pauses, which are not part of the distribution of the keystroke intervals
+pub const MAX_KEYSTROKE_INTERVAL_MS: u64 = 2_000;
+/// The distribution of the keystroke intervals is only checked once there are enough keystrokes
+pub const MIN_KEYSTROKES_FOR_TIMING_CHECK: u32 = 30;
+/// People do not type with a coefficient of variation of their keystroke intervals below this
+pub const MIN_KEYSTROKE_INTERVAL_VARIATION: f64 = 0.1;
+
+/// Result of the checks of a progress reported by a player
+#[derive(Debug)]
+pub struct ProgressCheck {
+    /// A rejected progress is not applied to the game
+    pub is_accepted: bool,
+    /// Timing of the player once the progress is applied, unchanged for a rejected progress
+    pub timing: TypingTiming,
+    pub flags: Vec<CheatFlagKind>,
+}
+
+impl ProgressCheck {
+    fn accepted(timing: TypingTiming, flags: Vec<CheatFlagKind>) -> Self {
+        Self {
+            is_accepted: true,
+            timing,
+            flags,
+        }
+    }
+
+    fn rejected(timing: TypingTiming, flag: CheatFlagKind) -> Self {
+        Self {
+            is_accepted: false,
+            timing,
+            flags: vec![flag],
+        }
+    }
+}
+
+/// Check the progress reported by a player at `now`, in milliseconds since unix epoch
+///
+/// The progress is rejected when it is physically impossible to type,
+/// and only flagged when the timing of the keystrokes looks automated
+///
+/// The rates are checked against the time at which the server receives the progress, the keystroke intervals
+/// measured by the client are only used to tell the timing of the keystrokes within a batch
+pub fn check_progress(
+    prompt: &str,
+    player: &PlayerProgress,
+    progress: usize,
+    keystroke_intervals: &[u32],
+    now: u64,
+) -> ProgressCheck {
+    let mut timing = player.timing;
+
+    if progress > prompt.chars().count() {
+        return ProgressCheck::rejected(timing, CheatFlagKind::ProgressBeyondPrompt);
+    }
+
+    let started_at = *timing.started_at.get_or_insert_with(|| {
+        timing.start_progress = player.progress;
+        now
+    });
+    let last_update_at = timing.last_update_at.unwrap_or(started_at);
+
+    // The progress can only move forward, going back is ignored by the game
+    if progress <= player.progress {
+        timing.last_update_at = Some(last_update_at);
+        return ProgressCheck::accepted(timing, vec![]);
+    }
+
+    let typed = progress - player.progress;
+    let since_last_update = now.saturating_sub(last_update_at);
+
+    if !is_typeable(typed, since_last_update) {
+        return ProgressCheck::rejected(timing, CheatFlagKind::ImpossibleBurst);
+    }
+
+    if !is_typeable(
+        progress.saturating_sub(timing.start_progress),
+        now.saturating_sub(started_at),
+    ) {
+        return ProgressCheck::rejected(timing, CheatFlagKind::ImpossibleKeystrokeRate);
+    }
+
+    if keystroke_intervals.is_empty() {
+        // Without the intervals of the client, only the updates of a single character tell the interval between
+        // two keystrokes
+        if typed == 1 {
+            add_keystroke_interval(&mut timing, since_last_update);
+        }
+    } else {
+        // There is one interval for every character typed, the extra intervals are ignored
+        for interval in keystroke_intervals.iter().take(typed) {
+            add_keystroke_interval(&mut timing, u64::from(*interval));
+        }
+    }
+
+    timing.last_update_at = Some(now);
+
+    let flags = if is_timing_suspicious(&timing) {
+        vec![CheatFlagKind::SuspiciousKeystrokeTiming]
+    } else {
+        vec![]
+    };
+
+    ProgressCheck::accepted(timing, flags)
+}
+
+fn add_keystroke_interval(timing: &mut TypingTiming, interval_ms: u64) {
+    if interval_ms > MAX_KEYSTROKE_INTERVAL_MS {
+        return;
+    }
+
+    let interval = interval_ms as f64;
+    timing.keystroke_count += 1;
+    timing.keystroke_interval_sum += interval;
+    timing.keystroke_interval_sum_of_squares += interval * interval;
+}
+
+fn is_typeable(characters: usize, duration_ms: u64) -> bool {
+    let max_characters =
+        MAX_BURST_CHARACTERS as f64 + MAX_CHARACTERS_PER_SECOND * duration_ms as f64 / 1000.0;
+
+    characters as f64 <= max_characters
+}
+
+/// Keystrokes sent at a constant interval are typed by a program rather than a person
+fn is_timing_suspicious(timing: &TypingTiming) -> bool {
+    if timing.keystroke_count < MIN_KEYSTROKES_FOR_TIMING_CHECK {
+        return false;
+    }
+
+    let count = f64::from(timing.keystroke_count);
+    let mean = timing.keystroke_interval_sum / count;
+    let variance = (timing.keystroke_interval_sum_of_squares / count - mean * mean).max(0.0);
+
+    mean <= 0.0 || variance.sqrt() / mean < MIN_KEYSTROKE_INTERVAL_VARIATION
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROMPT: &str = "the quick brown fox jumps over the lazy dog and keeps on running";
+
+    /// Report the progress of a player through the checks, as the game service does, returns the flags
+    fn report(
+        player: &mut PlayerProgress,
+        progress: usize,
+        keystroke_intervals: &[u32],
+        now: u64,
+    ) -> Vec<CheatFlagKind> {
+        let check = check_progress(PROMPT, player, progress, keystroke_intervals, now);
+
+        player.timing = check.timing;
+        if check.is_accepted {
+            player.progress = player.progress.max(progress);
+        }
+
+        check.flags
+    }
+
+    #[test]
+    fn progress_beyond_the_prompt_is_rejected() {
+        let player = PlayerProgress::default();
+
+        let check = check_progress(PROMPT, &player, PROMPT.len() + 1, &[], 0);
+        assert!(!check.is_accepted);
+        assert_eq!(check.flags, vec![CheatFlagKind::ProgressBeyondPrompt]);
+    }
+
+    #[test]
+    fn human_typing_is_accepted() {
+        let mut player = PlayerProgress::default();
+        assert!(report(&mut player, 0, &[], 0).is_empty());
+
+        // About 80 words per minute with irregular keystrokes
+        let mut now = 0;
+        for progress in 1..=PROMPT.len() {
+            now += 100 + (progress as u64 * 37) % 120;
+            assert!(report(&mut player, progress, &[], now).is_empty());
+        }
+
+        assert_eq!(player.progress, PROMPT.len());
+    }
+
+    #[test]
+    fn batched_updates_are_accepted() {
+        let mut player = PlayerProgress::default();
+        report(&mut player, 0, &[], 0);
+
+        // A few characters every half second, as the bots send them
+        let mut now = 0;
+        for progress in (3..=PROMPT.len()).step_by(3) {
+            now += 500;
+            let intervals = [110, 190, 200 + (progress as u32 * 37) % 60];
+            assert!(report(&mut player, progress, &intervals, now).is_empty());
+        }
+
+        assert!(player.timing.keystroke_count >= MIN_KEYSTROKES_FOR_TIMING_CHECK);
+    }
+
+    #[test]
+    fn whole_prompt_at_once_is_rejected() {
+        let mut player = PlayerProgress::default();
+        report(&mut player, 0, &[], 0);
+
+        let flags = report(&mut player, PROMPT.len(), &[], 50);
+        assert_eq!(flags, vec![CheatFlagKind::ImpossibleBurst]);
+        assert_eq!(player.progress, 0);
+    }
+
+    #[test]
+    fn sustained_impossible_rate_is_rejected() {
+        let mut player = PlayerProgress::default();
+        report(&mut player, 0, &[], 0);
+
+        // Every update is within the burst, but the rate over the game is far above anyone's typing
+        let mut now = 0;
+        let mut flags = vec![];
+        for progress in (8..=PROMPT.len()).step_by(8) {
+            now += 100;
+            flags.extend(report(&mut player, progress, &[], now));
+        }
+
+        assert!(flags.contains(&CheatFlagKind::ImpossibleKeystrokeRate));
+        assert!(player.progress < PROMPT.len());
+    }
+
+    #[test]
+    fn constant_keystroke_intervals_are_flagged() {
+        let mut player = PlayerProgress::default();
+        report(&mut player, 0, &[], 0);
+
+        let mut flags = vec![];
+        for progress in 1..=PROMPT.len() {
+            flags.extend(report(&mut player, progress, &[], progress as u64 * 120));
+        }
+
+        // The progress is still applied, only the result is flagged
+        assert!(flags.contains(&CheatFlagKind::SuspiciousKeystrokeTiming));
+        assert!(PROMPT.len() as u32 > MIN_KEYSTROKES_FOR_TIMING_CHECK);
+        assert_eq!(player.progress, PROMPT.len());
+    }
+
+    #[test]
+    fn constant_keystroke_intervals_within_batches_are_flagged() {
+        let mut player = PlayerProgress::default();
+        report(&mut player, 0, &[], 0);
+
+        // The batches are received at irregular times, but the characters were typed at a constant interval
+        let mut now = 0;
+        let mut flags = vec![];
+        for progress in (4..=PROMPT.len()).step_by(4) {
+            now += 480 + (progress as u64 * 37) % 60;
+            flags.extend(report(&mut player, progress, &[120; 4], now));
+        }
+
+        assert!(flags.contains(&CheatFlagKind::SuspiciousKeystrokeTiming));
+    }
+
+    #[test]
+    fn only_one_interval_is_counted_for_every_character() {
+        let mut player = PlayerProgress::default();
+        report(&mut player, 0, &[], 0);
+
+        report(&mut player, 2, &[100, 150, 200, 250], 500);
+        assert_eq!(player.timing.keystroke_count, 2);
+
+        // Pauses are not part of the distribution of the keystroke intervals
+        report(
+            &mut player,
+            4,
+            &[100, MAX_KEYSTROKE_INTERVAL_MS as u32 + 1],
+            3_000,
+        );
+        assert_eq!(player.timing.keystroke_count, 3);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::app::server::errors::ApiError;
+
+/// Longest chat message, in characters, once the surrounding whitespace is removed
+pub const MAX_CHAT_MESSAGE_LENGTH: usize = 200;
+
+/// Words that are masked in the chat messages, a word is also masked with the usual endings added
+const BLOCKED_WORDS: &[&str] = &[
+    "arse",
+    "arsehole",
+    "ass",
+    "asshole",
+    "bastard",
+    "bitch",
+    "bollocks",
+    "bullshit",
+    "crap",
+    "cunt",
+    "dick",
+    "dickhead",
+    "fuck",
+    "motherfucker",
+    "piss",
+    "prick",
+    "shit",
+    "slut",
+    "twat",
+    "wanker",
+    "whore",
+];
+
+const WORD_ENDINGS: &[&str] = &["s", "es", "ed", "er", "ers", "ing", "in", "y"];
+
+/// Check the length of a chat message and mask the blocked words
+///
+/// The control characters are replaced with spaces, so that a message cannot break the layout of the clients
+pub fn filter_message(message: &str) -> Result<String, ApiError> {
+    let message = message
+        .chars()
+        .map(|character| {
+            if character.is_control() {
+                ' '
+            } else {
+                character
+            }
+        })
+        .collect::<String>();
+    let message = message.trim();
+
+    if message.is_empty() {
+        Err(ApiError::BadRequest {
+            message: "The chat message is empty".to_string(),
+        })?
+    }
+
+    if message.chars().count() > MAX_CHAT_MESSAGE_LENGTH {
+        Err(ApiError::BadRequest {
+            message: format!(
+                "The chat message is longer than {MAX_CHAT_MESSAGE_LENGTH} characters"
+            ),
+        })?
+    }
+
+    let mut filtered_message = String::with_capacity(message.len());
+    let mut word = String::new();
+
+    for character in message.chars() {
+        if character.is_alphanumeric() {
+            word.push(character);
+        } else {
+            push_word(&mut filtered_message, &word);
+            word.clear();
+            filtered_message.push(character);
+        }
+    }
+    push_word(&mut filtered_message, &word);
+
+    Ok(filtered_message)
+}
+
+fn push_word(filtered_message: &mut String, word: &str) {
+    if is_blocked(word) {
+        filtered_message.extend(std::iter::repeat_n('*', word.chars().count()));
+    } else {
+        filtered_message.push_str(word);
+    }
+}
+
+/// Whole words are matched, so that the words that only contain a blocked word are kept
+fn is_blocked(word: &str) -> bool {
+    let word = word.to_lowercase();
+
+    BLOCKED_WORDS.iter().any(|blocked_word| {
+        word.strip_prefix(blocked_word)
+            .is_some_and(|ending| ending.is_empty() || WORD_ENDINGS.contains(&ending))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocked_words_are_masked() {
+        let message = filter_message("Oh shit, FUCKING lag!").unwrap();
+        assert_eq!(message, "Oh ****, ******* lag!");
+    }
+
+    #[test]
+    fn words_containing_blocked_words_are_kept() {
+        let message = "Reading Dickens in Scunthorpe on a class trip";
+        assert_eq!(filter_message(message).unwrap(), message);
+    }
+
+    #[test]
+    fn empty_and_long_messages_are_rejected() {
+        assert!(filter_message(" \n\t ").is_err());
+        assert!(filter_message(&"a".repeat(MAX_CHAT_MESSAGE_LENGTH + 1)).is_err());
+        assert!(filter_message(&"a".repeat(MAX_CHAT_MESSAGE_LENGTH)).is_ok());
+    }
+
+    #[test]
+    fn control_characters_are_replaced() {
+        let message = filter_message("  first\nsecond\u{1b}[2J  ").unwrap();
+        assert_eq!(message, "first second [2J");
+    }
+}
@@ -1,2 +1,6 @@
+pub mod game_service;
+pub mod global_stats;
 pub mod ping;
+pub mod room_browser;
+pub mod room_chat;
 pub mod room_service;
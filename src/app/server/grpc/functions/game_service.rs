@@ -0,0 +1,581 @@
+use tokio::sync::mpsc;
+
+use crate::app::{
+    server::{
+        errors::{self, ResultExtApp},
+        grpc::storage::interface::{
+            game::GameInterface, stats::StatsInterface, user::UserInterface,
+        },
+        logging, metrics,
+    },
+    utils,
+};
+
+use crate::app::server::grpc::{
+    anti_cheat,
+    server::{
+        game_service_response, game_user_status, grpc_server, GameServiceRequest,
+        GameServiceResponse, GameUserStatus, MyGrpc, ResumableGame,
+    },
+    storage::{models, Store},
+    types,
+};
+
+impl From<models::PlayerStatus> for game_user_status::PlayerStatus {
+    fn from(player_status: models::PlayerStatus) -> Self {
+        match player_status {
+            models::PlayerStatus::Playing => Self::Playing,
+            models::PlayerStatus::Disconnected { .. } => Self::Disconnected,
+            models::PlayerStatus::Finished => Self::Finished,
+            models::PlayerStatus::DidNotFinish => Self::DidNotFinish,
+        }
+    }
+}
+
+fn get_game_user_status(game: &models::Game, user_id: &str) -> GameUserStatus {
+    let player = game.get_player(user_id);
+
+    GameUserStatus {
+        client_id: user_id.to_string(),
+        status: player.progress as u32,
+        player_status: game_user_status::PlayerStatus::from(player.status).into(),
+        unranked: player.unranked,
+    }
+}
+
+/// The prompt is only sent when the player starts or resumes the game
+fn get_game_response(
+    game: &models::Game,
+    user_id: &str,
+    message_type: game_service_response::MessageType,
+) -> GameServiceResponse {
+    let prompt =
+        (message_type != game_service_response::MessageType::Status).then(|| game.prompt.clone());
+
+    GameServiceResponse {
+        client_id: user_id.to_string(),
+        game_id: game.game_id.clone(),
+        game_user_status: Some(get_game_user_status(game, user_id)),
+        prompt,
+        message_type: message_type.into(),
+        players_status: game
+            .users_in_game
+            .iter()
+            .map(|player_id| get_game_user_status(game, player_id))
+            .collect(),
+    }
+}
+
+/// Establish a streaming connection with the player of a game
+///
+/// The player sends the progress in the prompt and receives the status of all the players
+/// A player who was disconnected can open the stream again to resume the game within the resume window
+pub async fn game_service(
+    state: &MyGrpc,
+    user: models::User,
+    request: GameServiceRequest,
+    request_stream: tonic::Streaming<GameServiceRequest>,
+) -> Result<tonic::Response<<MyGrpc as grpc_server::Grpc>::GameServiceStream>, errors::ApiError> {
+    let game_id = request.game_id;
+    let user_id = user.user_id;
+    logging::record_game_id(&game_id);
+
+    if user.game_id.as_ref() != Some(&game_id) {
+        Err(errors::ApiError::BadRequest {
+            message: "The user is not playing this game".to_string(),
+        })?
+    }
+
+    let progress = request.progress as usize;
+    let (game, resumed) = state
+        .store
+        .update_game(&game_id, |game| resume_game(game, &user_id, progress))
+        .await
+        .to_not_found(errors::ApiError::GameNotFound {
+            game_id: game_id.clone(),
+        })?;
+
+    let Some((message_type, player_update, stream_generation)) = resumed else {
+        expire_resume_window(&state.store, &game_id, &user_id).await?;
+        Err(errors::ApiError::GameNotResumable {
+            game_id: game_id.clone(),
+        })?
+    };
+
+    if message_type == game_service_response::MessageType::Resume {
+        tracing::info!(
+            "User {user_id} resumed the game at {}",
+            game.get_player(&user_id).progress
+        );
+    }
+
+    finish_player(&state.store, &game, &user_id, player_update).await?;
+
+    if !player_update.has_game_ended {
+        state
+            .store
+            .add_active_game(&game_id)
+            .await
+            .to_internal_api_error()?;
+    }
+
+    let (response_sender, response_receiver) = mpsc::channel::<Result<_, _>>(16);
+
+    response_sender
+        .send(Ok(get_game_response(&game, &user_id, message_type)))
+        .await
+        .map_err(|_| errors::ApiError::InternalServerError)?;
+
+    // Spawn a tokio task to receive the progress of the player until the player disconnects
+    logging::spawn_in_current_span(handle_game_stream(
+        state.store.clone(),
+        user_id,
+        game_id,
+        stream_generation,
+        request_stream,
+        response_sender,
+    ));
+
+    let output_stream = tokio_stream::wrappers::ReceiverStream::new(response_receiver);
+    Ok(tonic::Response::new(
+        Box::pin(output_stream) as <MyGrpc as grpc_server::Grpc>::GameServiceStream
+    ))
+}
+
+async fn handle_game_stream(
+    store: Store,
+    user_id: String,
+    game_id: String,
+    stream_generation: u32,
+    mut request_stream: tonic::Streaming<GameServiceRequest>,
+    response_sender: mpsc::Sender<Result<GameServiceResponse, tonic::Status>>,
+) {
+    loop {
+        let request = tokio::select! {
+            request = request_stream.message() => request,
+            _ = store.shutdown_token.cancelled() => break,
+        };
+
+        match request {
+            Ok(Some(request)) => {
+                let progress = request.progress as usize;
+
+                match update_progress(
+                    &store,
+                    &game_id,
+                    &user_id,
+                    progress,
+                    &request.keystroke_intervals_ms,
+                )
+                .await
+                {
+                    Ok(game) => {
+                        let response = get_game_response(
+                            &game,
+                            &user_id,
+                            game_service_response::MessageType::Status,
+                        );
+
+                        if response_sender.send(Ok(response)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(error) => {
+                        tracing::error!(?error);
+                        let _ = response_sender.send(Err(error.into())).await;
+                        break;
+                    }
+                }
+            }
+            Ok(None) => break,
+            Err(status) => {
+                tracing::debug!(?status, "The game stream of user {user_id} failed");
+                break;
+            }
+        }
+    }
+
+    player_disconnected(&store, &game_id, &user_id, stream_generation).await;
+}
+
+/// What changed for a player when the game was updated
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct PlayerUpdate {
+    /// The player has just finished or dropped out of the game
+    is_done: bool,
+    /// The game has just ended with this update
+    has_game_ended: bool,
+}
+
+impl PlayerUpdate {
+    /// The player is done, which ends the game when this was the last player still playing
+    fn done(game: &mut models::Game) -> Self {
+        let has_game_ended = game.is_over() && game.is_in_progress();
+
+        if has_game_ended {
+            game.game_status = models::GameStatus::End;
+        }
+
+        Self {
+            is_done: true,
+            has_game_ended,
+        }
+    }
+}
+
+/// Start or resume the game of the player, `None` if the player can no longer play
+///
+/// Returns the generation of the new game stream of the player along with the update
+fn resume_game(
+    game: &mut models::Game,
+    user_id: &str,
+    progress: usize,
+) -> Option<(game_service_response::MessageType, PlayerUpdate, u32)> {
+    get_resume_window(game, user_id, utils::current_timestamp_millis())?;
+
+    let player = game.get_player(user_id);
+    let message_type = if player.status == models::PlayerStatus::Playing && player.progress == 0 {
+        game_service_response::MessageType::Init
+    } else {
+        game_service_response::MessageType::Resume
+    };
+
+    game.game_status = models::GameStatus::InProgress;
+    game.set_player_status(user_id, models::PlayerStatus::Playing);
+    let stream_generation = game.open_player_stream(user_id);
+    let player_update = apply_progress(game, user_id, progress, &[]).unwrap_or_default();
+
+    Some((message_type, player_update, stream_generation))
+}
+
+async fn update_progress(
+    store: &Store,
+    game_id: &str,
+    user_id: &str,
+    progress: usize,
+    keystroke_intervals: &[u32],
+) -> Result<models::Game, errors::ApiError> {
+    let (game, player_update) = store
+        .update_game(game_id, |game| {
+            apply_progress(game, user_id, progress, keystroke_intervals)
+        })
+        .await
+        .to_not_found(errors::ApiError::GameNotFound {
+            game_id: game_id.to_string(),
+        })?;
+
+    if let Some(player_update) = player_update {
+        finish_player(store, &game, user_id, player_update).await?;
+    }
+
+    Ok(game)
+}
+
+/// Apply the progress reported by the player once it has passed the anti-cheat checks
+///
+/// A rejected progress is not applied, the player can keep playing but the result is no longer ranked
+/// Returns `None` when the player is no longer playing, the game is then left unchanged
+fn apply_progress(
+    game: &mut models::Game,
+    user_id: &str,
+    progress: usize,
+    keystroke_intervals: &[u32],
+) -> Option<PlayerUpdate> {
+    let player = game.get_player(user_id);
+
+    if player.status != models::PlayerStatus::Playing {
+        return None;
+    }
+
+    let check = anti_cheat::check_progress(
+        &game.prompt,
+        &player,
+        progress,
+        keystroke_intervals,
+        utils::current_timestamp_millis(),
+    );
+
+    for kind in check.flags {
+        tracing::warn!("The progress {progress} of user {user_id} was flagged with {kind:?}");
+        game.flag_player(user_id, kind, progress);
+    }
+
+    game.set_player_timing(user_id, check.timing);
+
+    if check.is_accepted && game.update_progress(user_id, progress) {
+        Some(PlayerUpdate::done(game))
+    } else {
+        Some(PlayerUpdate::default())
+    }
+}
+
+/// The player is no longer part of the game once the player has finished or dropped out
+///
+/// Only the update that made the player done reaches this point, so the user and the game are counted once
+async fn finish_player(
+    store: &Store,
+    game: &models::Game,
+    user_id: &str,
+    player_update: PlayerUpdate,
+) -> Result<(), errors::ApiError> {
+    if !player_update.is_done {
+        return Ok(());
+    }
+
+    store
+        .update_user(user_id, |user| {
+            (user.game_id.as_ref() == Some(&game.game_id)).then(|| {
+                user.clear_game_id();
+                user.games_played += 1;
+            })
+        })
+        .await
+        .to_not_found(errors::ApiError::UserNotFound {
+            user_id: user_id.to_string(),
+        })?;
+
+    tracing::info!(
+        "User {user_id} is done with the game with status {:?}",
+        game.get_player(user_id).status
+    );
+
+    if player_update.has_game_ended {
+        store
+            .remove_active_game(&game.game_id)
+            .await
+            .to_internal_api_error()?;
+
+        metrics::metrics().games_finished.inc();
+        tracing::info!("The game {} is over", game.game_id);
+    }
+
+    Ok(())
+}
+
+/// Mark the player as disconnected and start the resume window of the player
+///
+/// A stream that closes after the player has resumed the game on a newer stream leaves the player playing
+async fn player_disconnected(store: &Store, game_id: &str, user_id: &str, stream_generation: u32) {
+    let since = utils::current_timestamp_millis();
+    let disconnected = store
+        .update_game(game_id, |game| {
+            let player = game.get_player(user_id);
+
+            (game.is_in_progress()
+                && player.status == models::PlayerStatus::Playing
+                && player.stream_generation == stream_generation)
+                .then(|| {
+                    game.set_player_status(user_id, models::PlayerStatus::Disconnected { since })
+                })
+        })
+        .await;
+
+    match disconnected {
+        Ok((_, Some(()))) => {}
+        Ok((_, None)) => return,
+        Err(error) => {
+            tracing::error!(?error);
+            return;
+        }
+    }
+
+    tracing::info!("User {user_id} was disconnected from the game {game_id}");
+
+    let store = store.clone();
+    let game_id = game_id.to_string();
+    let user_id = user_id.to_string();
+
+    // The window is checked again when the player pings, in case this server stops in the meantime
+    logging::spawn_in_current_span(async move {
+        tokio::time::sleep(types::GAME_RESUME_WINDOW).await;
+
+        if let Err(error) = expire_resume_window(&store, &game_id, &user_id).await {
+            tracing::error!(?error);
+        }
+    });
+}
+
+/// Get the time left for the player to resume the game, `None` if the player can no longer play
+fn get_resume_window(game: &models::Game, user_id: &str, now: u64) -> Option<std::time::Duration> {
+    if !game.is_in_progress() {
+        return None;
+    }
+
+    match game.get_player(user_id).status {
+        models::PlayerStatus::Playing => Some(types::GAME_RESUME_WINDOW),
+        models::PlayerStatus::Disconnected { since } => {
+            let disconnected_for = std::time::Duration::from_millis(now.saturating_sub(since));
+
+            types::GAME_RESUME_WINDOW
+                .checked_sub(disconnected_for)
+                .filter(|resume_window| !resume_window.is_zero())
+        }
+        models::PlayerStatus::Finished | models::PlayerStatus::DidNotFinish => None,
+    }
+}
+
+/// A disconnected player does not finish the game once the resume window is over
+///
+/// Returns `None` when the player is not disconnected or can still resume the game
+fn drop_out_player(game: &mut models::Game, user_id: &str, now: u64) -> Option<PlayerUpdate> {
+    let is_disconnected = matches!(
+        game.get_player(user_id).status,
+        models::PlayerStatus::Disconnected { .. }
+    );
+
+    if !is_disconnected || get_resume_window(game, user_id, now).is_some() {
+        return None;
+    }
+
+    game.set_player_status(user_id, models::PlayerStatus::DidNotFinish);
+    Some(PlayerUpdate::done(game))
+}
+
+/// Mark the player as not finishing the game when the resume window of the player is over
+async fn expire_resume_window(
+    store: &Store,
+    game_id: &str,
+    user_id: &str,
+) -> Result<(), errors::ApiError> {
+    let now = utils::current_timestamp_millis();
+    let (game, player_update) = store
+        .update_game(game_id, |game| drop_out_player(game, user_id, now))
+        .await
+        .to_not_found(errors::ApiError::GameNotFound {
+            game_id: game_id.to_string(),
+        })?;
+
+    match player_update {
+        Some(player_update) => finish_player(store, &game, user_id, player_update).await,
+        None => Ok(()),
+    }
+}
+
+/// Get the game that the user can resume, which is offered to the user on ping
+pub async fn get_resumable_game(
+    store: &Store,
+    user: &models::User,
+) -> Result<Option<ResumableGame>, errors::ApiError> {
+    let Some(game_id) = &user.game_id else {
+        return Ok(None);
+    };
+
+    let game = match store.find_game(game_id).await {
+        Ok(game) => game,
+        // The game might have been removed by an admin
+        Err(error) if error.is_not_found() => return Ok(None),
+        Err(error) => Err(error).to_internal_api_error()?,
+    };
+
+    let Some(resume_window) =
+        get_resume_window(&game, &user.user_id, utils::current_timestamp_millis())
+    else {
+        expire_resume_window(store, game_id, &user.user_id).await?;
+        return Ok(None);
+    };
+
+    Ok(Some(ResumableGame {
+        game_id: game.game_id.clone(),
+        prompt: game.prompt.clone(),
+        progress: game.get_player(&user.user_id).progress as u32,
+        resume_window_seconds: resume_window.as_secs(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_game(user_ids: &[&str]) -> models::Game {
+        let users = user_ids
+            .iter()
+            .map(|user_id| models::User {
+                user_id: user_id.to_string(),
+                ..models::User::new()
+            })
+            .collect::<Vec<_>>();
+
+        models::Game::new(&users, "prompt".to_string())
+    }
+
+    #[test]
+    fn late_progress_does_not_change_a_finished_player() {
+        let mut game = new_game(&["first", "second"]);
+        game.set_player_status("first", models::PlayerStatus::Finished);
+
+        assert_eq!(apply_progress(&mut game, "first", 2, &[]), None);
+        assert_eq!(
+            game.get_player("first").status,
+            models::PlayerStatus::Finished
+        );
+        assert!(resume_game(&mut game, "first", 2).is_none());
+        assert_eq!(
+            game.get_player("first").status,
+            models::PlayerStatus::Finished
+        );
+    }
+
+    #[test]
+    fn the_last_player_done_ends_the_game() {
+        let mut game = new_game(&["first", "second"]);
+        game.game_status = models::GameStatus::InProgress;
+        game.set_player_status("first", models::PlayerStatus::Finished);
+        game.set_player_status("second", models::PlayerStatus::DidNotFinish);
+
+        assert_eq!(
+            PlayerUpdate::done(&mut game),
+            PlayerUpdate {
+                is_done: true,
+                has_game_ended: true
+            }
+        );
+        assert!(!game.is_in_progress());
+
+        // The game is only counted as ended once
+        assert!(!PlayerUpdate::done(&mut game).has_game_ended);
+    }
+
+    #[test]
+    fn the_resume_window_is_only_read() {
+        let mut game = new_game(&["first", "second"]);
+        game.set_player_status("first", models::PlayerStatus::Disconnected { since: 1_000 });
+        let window_end = 1_000 + types::GAME_RESUME_WINDOW.as_millis() as u64;
+
+        assert_eq!(
+            get_resume_window(&game, "first", 1_000),
+            Some(types::GAME_RESUME_WINDOW)
+        );
+        assert_eq!(get_resume_window(&game, "first", window_end), None);
+        assert_eq!(
+            game.get_player("first").status,
+            models::PlayerStatus::Disconnected { since: 1_000 }
+        );
+        assert_eq!(
+            get_resume_window(&game, "second", window_end),
+            Some(types::GAME_RESUME_WINDOW)
+        );
+    }
+
+    #[test]
+    fn disconnected_players_drop_out_once_the_resume_window_is_over() {
+        let mut game = new_game(&["first", "second"]);
+        game.set_player_status("first", models::PlayerStatus::Disconnected { since: 1_000 });
+        let window_end = 1_000 + types::GAME_RESUME_WINDOW.as_millis() as u64;
+
+        assert_eq!(drop_out_player(&mut game, "first", window_end - 1), None);
+        assert_eq!(drop_out_player(&mut game, "second", window_end), None);
+
+        assert_eq!(
+            drop_out_player(&mut game, "first", window_end),
+            Some(PlayerUpdate {
+                is_done: true,
+                has_game_ended: false
+            })
+        );
+        assert_eq!(
+            game.get_player("first").status,
+            models::PlayerStatus::DidNotFinish
+        );
+        assert_eq!(drop_out_player(&mut game, "first", window_end), None);
+    }
+}
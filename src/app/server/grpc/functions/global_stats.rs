@@ -0,0 +1,144 @@
+use tokio::sync::mpsc;
+
+use crate::app::server::{
+    errors::{self, ResultExtApp},
+    grpc::storage::interface::{game::GameInterface, stats::StatsInterface, user::UserInterface},
+    logging,
+};
+
+use crate::app::server::grpc::{
+    server::{grpc_server, GlobalStatsRequest, GlobalStatsResponse, MyGrpc},
+    storage::{models, Store},
+    types,
+};
+
+impl From<models::GlobalStats> for GlobalStatsResponse {
+    fn from(global_stats: models::GlobalStats) -> Self {
+        Self {
+            active_players: global_stats.active_players as u32,
+            active_games: global_stats.active_games as u32,
+        }
+    }
+}
+
+/// Establish a streaming connection with the client to send the global stats periodically
+///
+/// The user is considered to be online as long as this stream is open, the user and the game of the user are
+/// renewed as active every time the stats are sent
+pub async fn global_stats(
+    state: &MyGrpc,
+    user: models::User,
+    _request: GlobalStatsRequest,
+) -> Result<tonic::Response<<MyGrpc as grpc_server::Grpc>::GlobalStatsStream>, errors::ApiError> {
+    let (response_sender, response_receiver) = mpsc::channel::<Result<_, _>>(8);
+
+    state
+        .store
+        .add_active_player(&user.user_id)
+        .await
+        .to_internal_api_error()?;
+    state.store.open_global_stats_stream(&user.user_id);
+
+    let cloned_store = state.store.clone();
+
+    // Spawn a tokio task to send the stats until the client disconnects
+    logging::spawn_in_current_span(async move {
+        let mut interval = tokio::time::interval(types::GLOBAL_STATS_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    renew_active_player(&cloned_store, &user.user_id).await;
+
+                    match cloned_store.get_global_stats().await {
+                        Ok(global_stats) => {
+                            let response = GlobalStatsResponse::from(global_stats);
+                            if response_sender.send(Ok(response)).await.is_err() {
+                                break;
+                            }
+                        }
+                        Err(error) => tracing::error!(?error),
+                    }
+                }
+                _ = response_sender.closed() => break,
+                _ = cloned_store.shutdown_token.cancelled() => break,
+            }
+        }
+
+        // The user is still online while another stream of the user is open, such as after a reconnect
+        if cloned_store.close_global_stats_stream(&user.user_id) == 0 {
+            remove_active_player(&cloned_store, &user.user_id).await;
+        }
+    });
+
+    let output_stream = tokio_stream::wrappers::ReceiverStream::new(response_receiver);
+    Ok(tonic::Response::new(
+        Box::pin(output_stream) as <MyGrpc as grpc_server::Grpc>::GlobalStatsStream
+    ))
+}
+
+/// Keep the user and the game of the user active, they expire once no instance renews them
+async fn renew_active_player(store: &Store, user_id: &str) {
+    if let Err(error) = store.add_active_player(user_id).await {
+        tracing::error!(?error);
+        return;
+    }
+
+    // The game of the user is read again, the user might have started or finished a game
+    match store.find_user(user_id).await {
+        Ok(models::User {
+            game_id: Some(game_id),
+            ..
+        }) => {
+            if let Err(error) = store.add_active_game(&game_id).await {
+                tracing::error!(?error);
+            }
+        }
+        Ok(_) => {}
+        Err(error) => tracing::error!(?error),
+    }
+}
+
+/// Mark the user as offline, the game of the user is no longer active
+/// if none of the players in that game are online
+async fn remove_active_player(store: &Store, user_id: &str) {
+    if let Err(error) = store.remove_active_player(user_id).await {
+        tracing::error!(?error);
+        return;
+    }
+
+    // Fetch the user again, the user might have started a game after connecting
+    let game_id = match store.find_user(user_id).await {
+        Ok(user) => user.game_id,
+        Err(error) => {
+            tracing::error!(?error);
+            None
+        }
+    };
+
+    if let Some(game_id) = game_id {
+        let game = match store.find_game(&game_id).await {
+            Ok(game) => game,
+            Err(error) => {
+                tracing::error!(?error);
+                return;
+            }
+        };
+
+        for player_id in game.users_in_game.iter() {
+            match store.is_player_active(player_id).await {
+                Ok(true) => return,
+                Ok(false) => {}
+                Err(error) => {
+                    tracing::error!(?error);
+                    return;
+                }
+            }
+        }
+
+        // The game is finished once the players finish or drop out, as it can still be resumed
+        if let Err(error) = store.remove_active_game(&game_id).await {
+            tracing::error!(?error);
+        }
+    }
+}
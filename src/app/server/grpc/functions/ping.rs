@@ -1,9 +1,11 @@
 use crate::app::server::{
     errors::{self, ResultExtApp},
     grpc::storage::interface::user::UserInterface,
+    logging, rate_limit,
 };
 
 use crate::app::server::grpc::{
+    functions::game_service,
     server::{MyGrpc, PingRequest, PingResponse},
     storage::models,
 };
@@ -12,8 +14,8 @@ pub async fn ping(
     state: &MyGrpc,
     request: tonic::Request<PingRequest>,
 ) -> Result<tonic::Response<PingResponse>, tonic::Status> {
-    let ping_request = request.into_inner();
-    tracing::info!(?ping_request);
+    let (_, extensions, ping_request) = request.into_parts();
+    tracing::debug!(?ping_request);
     let optional_user_id = ping_request.user_id;
 
     let ping_response = match optional_user_id {
@@ -24,9 +26,20 @@ pub async fn ping(
                 },
             )?;
 
+            // The guests and the unknown users are only limited by their ip address
+            rate_limit::check_user_rate(
+                state.rate_limiter.as_deref(),
+                &extensions,
+                &db_user.user_id,
+            )?;
+
+            // A user who was disconnected in the middle of a game is offered to resume it
+            let resumable_game = game_service::get_resumable_game(&state.store, &db_user).await?;
+
             PingResponse {
                 user_id: db_user.user_id,
                 user_name: db_user.user_name,
+                resumable_game,
             }
         }
         None => {
@@ -43,11 +56,13 @@ pub async fn ping(
             PingResponse {
                 user_id: db_user.user_id,
                 user_name: db_user.user_name,
+                resumable_game: None,
             }
         }
     };
 
-    tracing::info!(?ping_response);
+    logging::record_user_id(&ping_response.user_id);
+    tracing::debug!(?ping_response);
 
     Ok(tonic::Response::new(ping_response))
 }
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use crate::app::{
+    server::{
+        errors::{self, ResultExtApp},
+        grpc::storage::interface::{room::RoomInterface, user::UserInterface},
+    },
+    utils,
+};
+
+use crate::app::server::grpc::{
+    server::{ListRoomsRequest, ListRoomsResponse, MyGrpc, PublicRoom},
+    storage::models,
+};
+
+/// Most rooms sent in one list, the older rooms are left out
+const MAX_LISTED_ROOMS: usize = 50;
+
+/// List the public rooms that can still be joined, the newest rooms first
+pub async fn list_rooms(
+    state: &MyGrpc,
+    _user: models::User,
+    _request: ListRoomsRequest,
+) -> Result<tonic::Response<ListRoomsResponse>, errors::ApiError> {
+    let mut rooms = state
+        .store
+        .get_public_rooms()
+        .await
+        .to_internal_api_error()?
+        .into_iter()
+        .filter(|room| !room.users.is_empty() && room.users.len() < room.room_size.into())
+        .collect::<Vec<_>>();
+
+    rooms.sort_by_key(|room| std::cmp::Reverse(room.created_at));
+    rooms.truncate(MAX_LISTED_ROOMS);
+
+    // The host keeps the room listed under their name only while they are waiting in it
+    let host_ids = rooms
+        .iter()
+        .filter_map(|room| {
+            room.host_id
+                .clone()
+                .filter(|host_id| room.users.contains(host_id))
+        })
+        .collect::<Vec<_>>();
+
+    let hosts = if host_ids.is_empty() {
+        HashMap::new()
+    } else {
+        state
+            .store
+            .get_multiple_users(host_ids)
+            .await
+            .to_internal_api_error()?
+            .into_iter()
+            .map(|host| (host.user_id.clone(), host))
+            .collect::<HashMap<_, _>>()
+    };
+
+    let now = utils::current_timestamp_millis();
+    let public_rooms = rooms
+        .into_iter()
+        .map(|room| PublicRoom {
+            host: room
+                .host_id
+                .as_ref()
+                .filter(|host_id| room.users.contains(host_id))
+                .and_then(|host_id| hosts.get(host_id))
+                .cloned()
+                .map(Into::into),
+            room_id: room.room_id,
+            room_size: room.room_size.into(),
+            player_count: room.users.len() as u32,
+            age_seconds: now.saturating_sub(room.created_at) / 1000,
+        })
+        .collect();
+
+    Ok(tonic::Response::new(ListRoomsResponse {
+        rooms: public_rooms,
+    }))
+}
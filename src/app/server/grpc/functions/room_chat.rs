@@ -0,0 +1,106 @@
+use crate::app::{
+    server::{
+        errors::{self, ResultExtApp},
+        grpc::storage::interface::{room::RoomInterface, session::SessionInterface},
+        logging,
+    },
+    utils,
+};
+
+use crate::app::server::grpc::{
+    chat_filter,
+    server::{ChatMessage, MyGrpc, SendRoomChatRequest, SendRoomChatResponse},
+    storage::{models, Store},
+    types,
+};
+use tokio::sync::mpsc;
+
+/// Send a chat message to all the users waiting in the room of the user, the sender included
+///
+/// The message is published to every server, the servers deliver it over the room streams of their users
+pub async fn send_room_chat(
+    state: &MyGrpc,
+    user: models::User,
+    request: SendRoomChatRequest,
+) -> Result<tonic::Response<SendRoomChatResponse>, errors::ApiError> {
+    let Some(room_id) = user.room_id else {
+        Err(errors::ApiError::BadRequest {
+            message: "The user is not waiting in a room".to_string(),
+        })?
+    };
+    logging::record_room_id(&room_id);
+
+    let message = chat_filter::filter_message(&request.message)?;
+
+    let room =
+        state
+            .store
+            .find_room(&room_id)
+            .await
+            .to_not_found(errors::ApiError::RoomNotFound {
+                room_id: room_id.clone(),
+            })?;
+
+    let published_chat = types::PublishedChat {
+        room_id,
+        user_ids: room.users,
+        user_id: user.user_id,
+        user_name: user.user_name,
+        message: message.clone(),
+        sent_at: utils::current_timestamp_millis(),
+    };
+
+    state
+        .store
+        .redis_client
+        .serialize_and_publish(types::ROOM_CHAT_CHANNEL, &published_chat)
+        .await
+        .to_internal_api_error()?;
+
+    Ok(tonic::Response::new(SendRoomChatResponse { message }))
+}
+
+/// Send the chat messages published by every server to the users connected to this server
+///
+/// Runs until the server shuts down, the subscription is made before the server starts serving
+pub async fn deliver_room_chat(store: Store, mut subscription: mpsc::UnboundedReceiver<String>) {
+    loop {
+        let published_message = tokio::select! {
+            Some(published_message) = subscription.recv() => published_message,
+            _ = store.shutdown_token.cancelled() => return,
+            else => {
+                tracing::error!("The room chat subscription has ended");
+                return;
+            }
+        };
+
+        let published_chat = match serde_json::from_str::<types::PublishedChat>(&published_message)
+        {
+            Ok(published_chat) => published_chat,
+            Err(error) => {
+                tracing::warn!(?error, "Received an invalid chat message");
+                continue;
+            }
+        };
+
+        let room_id = published_chat.room_id.clone();
+        let user_ids = published_chat.user_ids.clone();
+        let chat_message = ChatMessage::from(published_chat);
+
+        for user_id in user_ids {
+            let room_message = types::RoomMessage::Chat {
+                room_id: room_id.clone(),
+                chat_message: chat_message.clone(),
+            };
+
+            // The users connected to the other servers receive the message from their own server
+            match store.send_message_to_user(&user_id, room_message).await {
+                Ok(()) => {}
+                Err(error) if error.is_not_found() => {}
+                Err(error) => {
+                    tracing::debug!(?error, "Cannot send the chat message to the user {user_id}")
+                }
+            }
+        }
+    }
+}
@@ -1,23 +1,31 @@
-use rand::Rng;
 use tokio::sync::mpsc::{self};
 
 use crate::app::{
     server::{
         errors::{self, ResultExtApp},
         grpc::storage::interface::{
-            game::GameInterface, room::RoomInterface, session::SessionInterface,
-            user::UserInterface,
+            game::GameInterface, invite::InviteInterface, room::RoomInterface,
+            session::SessionInterface, stats::StatsInterface, user::UserInterface,
         },
+        logging, metrics,
     },
     types::RoomServiceRequestType,
+    utils,
 };
 
 use crate::app::server::grpc::{
-    server::{grpc_server, MyGrpc, RoomServiceRequest},
-    storage::models,
+    invite_code,
+    server::{
+        grpc_server, LeaveRoomRequest, LeaveRoomResponse, MyGrpc, RoomServiceRequest,
+        RoomServiceResponse,
+    },
+    storage::{models, Store},
     types,
 };
 
+/// Attempts to find an invite code that is not taken, before giving up on the invite of a room
+const MAX_INVITE_CODE_ATTEMPTS: usize = 5;
+
 /// Establish a streaming connection with the client
 /// This helps in notifying about the current users in the room
 ///
@@ -34,85 +42,133 @@ pub async fn room_service(
         },
     )?;
 
+    // A draining server lets the existing rooms finish, but does not accept new ones
+    if state.store.is_draining() {
+        Err(errors::ApiError::ServerDraining)?
+    }
+
     let current_user_id = user.user_id.clone();
 
-    let (response_sender, response_receiver) = mpsc::channel::<Result<_, _>>(128);
+    let hosted_room_id = match request_type {
+        RoomServiceRequestType::CreateRoom => {
+            // The ids share the keys of the store and a private room must not be found by guessing its id,
+            // the users join with the invite code of the room or from the room browser
+            if request.room_id.is_some() {
+                Err(errors::ApiError::BadRequest {
+                    message: "The id of a new room is chosen by the server".to_string(),
+                })?
+            }
 
-    // Insert the user into channel so that async communication can take place
-    state
-        .store
-        .insert_channel(&current_user_id, response_sender.clone())
-        .to_internal_api_error()?;
+            let room_id = utils::generate_time_ordered_id("room");
+            logging::record_room_id(&room_id);
+
+            reserve_hosted_room(state, &current_user_id, &room_id).await?;
+            Some(room_id)
+        }
+        RoomServiceRequestType::JoinRoom => None,
+    };
+
+    let (response_sender, response_receiver) = mpsc::channel::<Result<_, _>>(128);
 
     // Authenticate user
     let mut user_from_db = user;
 
-    match request_type {
-        RoomServiceRequestType::CreateRoom => {
-            let room_id = request.room_id.unwrap_or_else(|| {
-                let mut rng = rand::thread_rng();
-                rng.gen_range(100000..1000000).to_string()
-            });
-
-            // If the room already exists then the client must retry with a different room_id
-            match state.store.find_room(&room_id).await {
-                Ok(_) => Err(errors::ApiError::RoomAlreadyExists { room_id })?,
-                Err(error) => {
-                    if error.is_not_found() {
-                        let room = models::Room::new(room_id, 2);
-                        let db_room = state
-                            .store
-                            .insert_room(room)
-                            .await
-                            .to_internal_api_error()?;
-
-                        state
-                            .store
-                            .send_message_to_user(
-                                &user_from_db.user_id,
-                                types::RoomMessage::RoomCreated {
-                                    room_id: db_room.room_id,
-                                    users: vec![user_from_db.clone()],
-                                },
-                            )
-                            .await
-                            .to_internal_api_error()?;
-                    } else {
-                        Err(error).to_internal_api_error()?
-                    }
-                }
+    match hosted_room_id {
+        Some(room_id) => {
+            if let Err(error) = create_room(
+                state,
+                &mut user_from_db,
+                room_id.clone(),
+                &request,
+                &response_sender,
+            )
+            .await
+            {
+                release_hosted_room(&state.store, &current_user_id, &room_id).await;
+                Err(error)?
             }
         }
-        RoomServiceRequestType::JoinRoom => {
+        None => {
             // This can also be used to join the common room by not passing the `room_id`
-            let room_id = request
-                .room_id
-                .unwrap_or(types::COMMON_ROOM_KEY.to_string());
-
+            let invite = match &request.invite_code {
+                Some(invite_code) => {
+                    Some(find_invite(&state.store, invite_code, &current_user_id).await?)
+                }
+                None => None,
+            };
+            let room_id = match &invite {
+                Some(invite) => invite.room_id.clone(),
+                None => request
+                    .room_id
+                    .unwrap_or(types::COMMON_ROOM_KEY.to_string()),
+            };
+            logging::record_room_id(&room_id);
+
+            let has_invite = invite.is_some();
+            let taken_invite = invite.filter(is_unused_single_use_invite);
+
+            // The user is added with a compare and set, so the users joining at the same time are all kept
             // The room should already exist, or else return an error
-            let mut room = state.store.find_room(&room_id).await.to_not_found(
-                errors::ApiError::RoomNotFound {
-                    room_id: room_id.clone(),
-                },
-            )?;
-
-            // Check if user is not in the current room
-            if room.users.contains(&current_user_id) {
-                Err(errors::ApiError::BadRequest {
-                    message: "User trying to join the same room".to_string(),
-                })?
+            let joined_room = state
+                .store
+                .update_room(&room_id, |room| {
+                    let can_join = can_join_room(room, &current_user_id, has_invite)
+                        && !room.users.contains(&current_user_id)
+                        && room.users.len() < usize::from(room.room_size);
+
+                    can_join.then(|| {
+                        if taken_invite.is_some() && !room.is_host(&current_user_id) {
+                            room.invite_code = None;
+                        }
+                        room.add_user(current_user_id.clone())
+                    })
+                })
+                .await;
+
+            // The single use invite is only used once the user has joined the room with it
+            if let Some(invite) = taken_invite {
+                let has_used_invite = matches!(
+                    &joined_room,
+                    Ok((room, Some(_))) if !room.is_host(&current_user_id)
+                );
+                let used_by = has_used_invite.then(|| current_user_id.clone());
+
+                return_invite(&state.store, models::Invite { used_by, ..invite }).await;
             }
 
-            // Check if the length of the room is max
-            // This can happen in cases when there is a slight delay in starting the game when all users are already in the room
-            if room.users.len() == usize::from(room.room_size) {
+            let (room, room_size) = joined_room.to_not_found(errors::ApiError::RoomNotFound {
+                room_id: room_id.clone(),
+            })?;
+
+            let Some(room_size) = room_size else {
+                // A reconnecting user is still part of the room when the previous session was not cleaned up yet
+                // The new session takes over and receives the room details again
+                if room.users.contains(&current_user_id) {
+                    replace_session_channel(&state.store, &current_user_id, &response_sender)?;
+                    send_room_details(&state.store, &room, &current_user_id).await?;
+
+                    return Ok(start_session(
+                        &state.store,
+                        user_from_db,
+                        response_sender,
+                        response_receiver,
+                    ));
+                }
+
+                if !can_join_room(&room, &current_user_id, has_invite) {
+                    Err(errors::ApiError::InviteRequired {
+                        room_id: room_id.clone(),
+                    })?
+                }
+
+                // This can happen in cases when there is a slight delay in starting the game when all users are already in the room
                 Err(errors::ApiError::BadRequest {
                     message: "Maximum capacity has been reached for the room".to_string(),
                 })?
-            }
+            };
 
-            // Add the current user to the room
-            let room_size = room.add_user(current_user_id.clone());
+            // Insert the user into channel so that async communication can take place
+            replace_session_channel(&state.store, &current_user_id, &response_sender)?;
 
             // Update the user that he has been assigned to a room
             user_from_db.assign_room_id(room_id.clone());
@@ -143,32 +199,64 @@ pub async fn room_service(
                 // Create the game
                 let test_prompt = "This is a sample prompt for the game".to_string();
                 let game = models::Game::new(&all_users_in_room, test_prompt);
-                state
+                let game = state
                     .store
                     .insert_game(game)
                     .await
                     .to_internal_api_error()?;
-
-                // The game can be started, inform all the connected users of this room
-                for user_id in users_in_the_room {
+                logging::record_game_id(&game.game_id);
+
+                // Assign the game to all the users, so that the game can be tracked
+                // as active until all of its players go offline, and can be resumed
+                // The users leave the room as the room is closed once the game starts
+                for mut user in all_users_in_room.clone() {
+                    user.assign_game_id(game.game_id.clone());
+                    user.clear_room_id();
                     state
                         .store
-                        .send_message_to_user(
-                            &user_id,
-                            types::RoomMessage::AllUsersJoined {
-                                room_id: room_id.clone(),
-                                users: all_users_in_room.clone(),
-                            },
-                        )
+                        .insert_user(user)
                         .await
-                        .unwrap();
+                        .to_internal_api_error()?;
+                }
+
+                state
+                    .store
+                    .add_active_game(&game.game_id)
+                    .await
+                    .to_internal_api_error()?;
+
+                let metrics = metrics::metrics();
+                metrics.games_started.inc();
+
+                let current_time = utils::current_timestamp_millis();
+                for joined_at in room.joined_at.values() {
+                    let wait_time = current_time.saturating_sub(*joined_at);
+                    metrics.matchmaking_wait.observe(wait_time as f64 / 1000.0);
+                }
+
+                // The game can be started, inform all the connected users of this room
+                for user_id in &users_in_the_room {
+                    let message = types::RoomMessage::AllUsersJoined {
+                        room_id: room_id.clone(),
+                        users: all_users_in_room.clone(),
+                    };
+
+                    // The user might have disconnected in the meantime, the game can still be resumed
+                    if let Err(error) = state.store.send_message_to_user(user_id, message).await {
+                        tracing::debug!(?error, "Cannot inform the user {user_id}");
+                    }
                 }
 
                 if room_id == types::COMMON_ROOM_KEY {
-                    room.users.clear();
+                    // The users who join while the game is created wait for the next game
                     state
                         .store
-                        .insert_room(room)
+                        .update_room(&room_id, |room| {
+                            for user_id in &users_in_the_room {
+                                room.remove_user(user_id.clone());
+                            }
+                            Some(())
+                        })
                         .await
                         .to_internal_api_error()?;
                 } else {
@@ -179,12 +267,7 @@ pub async fn room_service(
                         .to_internal_api_error()?;
                 }
             } else {
-                // Update the room in database with the new user
-                state
-                    .store
-                    .insert_room(room)
-                    .await
-                    .to_internal_api_error()?;
+                let room_invite_code = room.invite_code.clone();
 
                 // The current user has joined this room
                 // Inform all other users, except current user, that this person has joined the room
@@ -194,17 +277,15 @@ pub async fn room_service(
                     .collect::<Vec<_>>();
 
                 for user_id in users_in_room_except_self {
-                    state
-                        .store
-                        .send_message_to_user(
-                            &user_id,
-                            types::RoomMessage::UserJoined {
-                                room_id: room_id.clone(),
-                                users: all_users_in_room.clone(),
-                            },
-                        )
-                        .await
-                        .unwrap();
+                    let message = types::RoomMessage::UserJoined {
+                        room_id: room_id.clone(),
+                        users: all_users_in_room.clone(),
+                    };
+
+                    // The other user might be disconnecting as well
+                    if let Err(error) = state.store.send_message_to_user(&user_id, message).await {
+                        tracing::debug!(?error, "Cannot inform the user {user_id}");
+                    }
                 }
 
                 state
@@ -213,6 +294,7 @@ pub async fn room_service(
                         &user_from_db.user_id,
                         types::RoomMessage::RoomCreated {
                             room_id: room_id.clone(),
+                            invite_code: room_invite_code,
                             users: all_users_in_room.clone(),
                         },
                     )
@@ -222,54 +304,347 @@ pub async fn room_service(
         }
     };
 
-    let cloned_store = state.store.clone();
-    let cloned_response_sender = response_sender.clone();
+    Ok(start_session(
+        &state.store,
+        user_from_db,
+        response_sender,
+        response_receiver,
+    ))
+}
+
+/// Stream the messages of the session to the client, the session is removed once the client disconnects
+fn start_session(
+    store: &Store,
+    user_from_db: models::User,
+    response_sender: mpsc::Sender<Result<RoomServiceResponse, tonic::Status>>,
+    response_receiver: mpsc::Receiver<Result<RoomServiceResponse, tonic::Status>>,
+) -> tonic::Response<<MyGrpc as grpc_server::Grpc>::RoomServiceStream> {
+    let cloned_store = store.clone();
 
     // Spawn a tokio task to remove the user session from the session store
-    tokio::spawn(async move {
-        let mut interval = tokio::time::interval(std::time::Duration::from_millis(100));
-        interval.tick().await;
-
-        loop {
-            interval.tick().await;
-
-            if cloned_response_sender.is_closed() {
-                let room_id = user_from_db.room_id.clone();
-                if let Some(room_id) = room_id {
-                    let room = cloned_store.find_room(&room_id).await.to_not_found(
-                        errors::ApiError::RoomNotFound {
-                            room_id: room_id.clone(),
-                        },
-                    );
-
-                    match room {
-                        Ok(mut room) => {
-                            room.remove_user(user_from_db.user_id.clone());
-                            cloned_store.insert_room(room).await.unwrap();
-                            log::info!(
-                                "Removed user {} from the room {}",
-                                user_from_db.user_id,
-                                room_id
-                            );
-
-                            cloned_store
-                                .remove_channel(&user_from_db.user_id)
-                                .to_internal_api_error()
-                                .unwrap();
-                        }
-                        Err(error) => {
-                            tracing::error!(?error);
-                        }
-                    };
-                };
+    logging::spawn_in_current_span(async move {
+        // The receiver is dropped once the client disconnects
+        response_sender.closed().await;
 
-                break;
-            }
+        // A reconnected client replaces the channel of this session, which must be kept
+        if cloned_store.is_current_channel(&user_from_db.user_id, &response_sender) {
+            remove_user_session(&cloned_store, &user_from_db.user_id).await;
         }
     });
 
     let output_stream = tokio_stream::wrappers::ReceiverStream::new(response_receiver);
-    Ok(tonic::Response::new(
-        Box::pin(output_stream) as <MyGrpc as grpc_server::Grpc>::RoomServiceStream
-    ))
+    tonic::Response::new(Box::pin(output_stream) as <MyGrpc as grpc_server::Grpc>::RoomServiceStream)
+}
+
+/// The new room stream of the user takes over the session, once the request has been accepted
+///
+/// A request that fails leaves the current session alone, so that the user keeps receiving the messages of the
+/// current room
+fn replace_session_channel(
+    store: &Store,
+    user_id: &str,
+    channel: &mpsc::Sender<Result<RoomServiceResponse, tonic::Status>>,
+) -> Result<(), errors::ApiError> {
+    store
+        .insert_channel(user_id, channel.clone())
+        .to_internal_api_error()
+}
+
+/// Send the details of the room again to a user who is already in the room
+///
+/// A reconnecting user is still part of the room when the previous session was not cleaned up yet,
+/// the new session takes over
+async fn send_room_details(
+    store: &Store,
+    room: &models::Room,
+    user_id: &str,
+) -> Result<(), errors::ApiError> {
+    let all_users_in_room = store
+        .get_multiple_users(room.users.clone())
+        .await
+        .to_internal_api_error()?;
+
+    store
+        .send_message_to_user(
+            user_id,
+            types::RoomMessage::RoomCreated {
+                room_id: room.room_id.clone(),
+                invite_code: room.invite_code.clone(),
+                users: all_users_in_room,
+            },
+        )
+        .await
+        .to_internal_api_error()
+}
+
+/// Create the invite code of a room, the private rooms can only be joined with their invite code
+async fn create_invite(
+    store: &Store,
+    room_id: &str,
+    is_single_use: bool,
+) -> Result<String, errors::ApiError> {
+    for _ in 0..MAX_INVITE_CODE_ATTEMPTS {
+        let invite_code = invite_code::generate_invite_code();
+        let invite = models::Invite::new(invite_code, room_id.to_string(), is_single_use);
+
+        // The code is only written if it is free, so two rooms never get the same code
+        match store.insert_invite(invite).await {
+            Ok(invite) => return Ok(invite.invite_code),
+            Err(errors::DbError::DuplicateValue) => continue,
+            Err(error) => Err(error).to_internal_api_error()?,
+        }
+    }
+
+    tracing::error!("Could not find a free invite code for the room {room_id}");
+    Err(errors::ApiError::InternalServerError)
+}
+
+/// Find the invite of a code typed by a user, the expired invites are removed
+///
+/// A single use invite that is not used yet is taken, so that only one of the users joining with it at the same
+/// time gets it, the invite must then be given back with `return_invite`
+/// The user who has used a single use invite can still rejoin the room with it, such as after a reconnect
+async fn find_invite(
+    store: &Store,
+    invite_code: &str,
+    user_id: &str,
+) -> Result<models::Invite, errors::ApiError> {
+    let not_found = || errors::ApiError::InviteNotFound {
+        invite_code: invite_code.to_string(),
+    };
+
+    let normalized_code = invite_code::normalize_invite_code(invite_code).ok_or_else(not_found)?;
+    let mut invite = store
+        .find_invite(&normalized_code)
+        .await
+        .to_not_found(not_found())?;
+
+    if invite.is_expired() {
+        store
+            .delete_invite(&invite.invite_code)
+            .await
+            .to_internal_api_error()?;
+        Err(not_found())?
+    }
+
+    if is_unused_single_use_invite(&invite) {
+        invite = store
+            .take_invite(&invite.invite_code)
+            .await
+            .to_not_found(not_found())?;
+
+        // Another user might have used the invite since it was read
+        if invite.used_by.is_some() {
+            return_invite(store, invite.clone()).await;
+        }
+    }
+
+    match &invite.used_by {
+        Some(used_by) if used_by != user_id => Err(not_found()),
+        _ => Ok(invite),
+    }
+}
+
+/// A single use invite that is not used yet is taken by the users joining with it, until the join is settled
+fn is_unused_single_use_invite(invite: &models::Invite) -> bool {
+    invite.is_single_use && invite.used_by.is_none()
+}
+
+/// Give back a single use invite taken by a join, along with the user who has used it
+///
+/// The code of the invite is free, as the invite was taken
+async fn return_invite(store: &Store, invite: models::Invite) {
+    if let Err(error) = store.insert_invite(invite).await {
+        tracing::error!(?error, "Could not give back the invite");
+    }
+}
+
+/// The private rooms are joined with their invite code, their id is not enough
+///
+/// The host can rejoin the room with its id, the other users rejoin it with the invite code they joined with
+fn can_join_room(room: &models::Room, user_id: &str, has_invite: bool) -> bool {
+    has_invite || room.is_public || room.room_id == types::COMMON_ROOM_KEY || room.is_host(user_id)
+}
+
+/// Create the room of the host, the room id is already reserved for the host
+async fn create_room(
+    state: &MyGrpc,
+    user_from_db: &mut models::User,
+    room_id: String,
+    request: &RoomServiceRequest,
+    response_sender: &mpsc::Sender<Result<RoomServiceResponse, tonic::Status>>,
+) -> Result<(), errors::ApiError> {
+    // The creator waits in the room like the users that join it
+    let mut room = models::Room::new(room_id, 2);
+    room.host_id = Some(user_from_db.user_id.clone());
+    room.is_public = request.is_public;
+    room.add_user(user_from_db.user_id.clone());
+    room.invite_code =
+        Some(create_invite(&state.store, &room.room_id, request.single_use_invite).await?);
+
+    let db_room = state
+        .store
+        .insert_room(room)
+        .await
+        .to_internal_api_error()?;
+
+    user_from_db.assign_room_id(db_room.room_id.clone());
+    *user_from_db = state
+        .store
+        .insert_user(user_from_db.clone())
+        .await
+        .to_internal_api_error()?;
+
+    replace_session_channel(&state.store, &user_from_db.user_id, response_sender)?;
+
+    state
+        .store
+        .send_message_to_user(
+            &user_from_db.user_id,
+            types::RoomMessage::RoomCreated {
+                room_id: db_room.room_id,
+                invite_code: db_room.invite_code,
+                users: vec![user_from_db.clone()],
+            },
+        )
+        .await
+        .to_internal_api_error()
+}
+
+/// Rooms that never fill up stay open, a user cannot keep creating them
+///
+/// The room is counted as soon as it is reserved, so the concurrent requests of a user cannot go over the limit
+async fn reserve_hosted_room(
+    state: &MyGrpc,
+    user_id: &str,
+    room_id: &str,
+) -> Result<(), errors::ApiError> {
+    let limit = state
+        .rate_limiter
+        .as_ref()
+        .map_or(usize::MAX, |rate_limiter| rate_limiter.max_rooms_per_user());
+
+    let is_reserved = state
+        .store
+        .reserve_hosted_room(user_id, room_id, limit)
+        .await
+        .to_internal_api_error()?;
+
+    if !is_reserved {
+        Err(errors::ApiError::TooManyRooms { limit })?
+    }
+
+    Ok(())
+}
+
+/// The room could not be created, it is not counted against the limit of the user
+async fn release_hosted_room(store: &Store, user_id: &str, room_id: &str) {
+    if let Err(error) = store.release_hosted_room(user_id, room_id).await {
+        tracing::error!("Could not release the hosted room {room_id} of {user_id}: {error:?}");
+    }
+}
+
+/// Leave the room that the user is waiting in
+///
+/// The client closes the room stream on its own, the session channel is removed once the stream is closed
+pub async fn leave_room(
+    state: &MyGrpc,
+    user: models::User,
+    _request: LeaveRoomRequest,
+) -> Result<tonic::Response<LeaveRoomResponse>, errors::ApiError> {
+    let room_id = leave_current_room(&state.store, &user.user_id).await?;
+
+    if let Some(room_id) = &room_id {
+        logging::record_room_id(room_id);
+    }
+
+    Ok(tonic::Response::new(LeaveRoomResponse { room_id }))
+}
+
+/// Remove the user from the room that the user is in and remove the session channel of the user
+pub async fn remove_user_session(store: &Store, user_id: &str) {
+    match leave_current_room(store, user_id).await {
+        Ok(_) => {}
+        // The user was deleted by an administrator, who has already removed the user from the room
+        Err(errors::ApiError::UserNotFound { .. }) => {
+            tracing::debug!("The user {user_id} does not exist anymore")
+        }
+        Err(error) => tracing::error!(?error),
+    }
+
+    if store.remove_channel(user_id).is_err() {
+        tracing::debug!("The session of user {user_id} was already removed");
+    }
+}
+
+/// Remove the user from the room that the user is waiting in and inform the remaining users of the room
+///
+/// Returns the id of the room that the user has left
+pub async fn leave_current_room(
+    store: &Store,
+    user_id: &str,
+) -> Result<Option<String>, errors::ApiError> {
+    // Read the user again, the room of the user might have changed since the session started
+    let mut user = store
+        .find_user(user_id)
+        .await
+        .to_not_found(errors::ApiError::UserNotFound {
+            user_id: user_id.to_string(),
+        })?;
+
+    let Some(room_id) = user.room_id.clone() else {
+        return Ok(None);
+    };
+
+    user.clear_room_id();
+    store.insert_user(user).await.to_internal_api_error()?;
+
+    let room = match store
+        .update_room(&room_id, |room| {
+            room.users
+                .contains(&user_id.to_string())
+                .then(|| room.remove_user(user_id.to_string()))
+        })
+        .await
+    {
+        Ok((room, _)) => room,
+        // The room is deleted once the game starts
+        Err(error) if error.is_not_found() => return Ok(Some(room_id)),
+        Err(error) => Err(error).to_internal_api_error()?,
+    };
+
+    let remaining_user_ids = room.users.clone();
+
+    // Nobody is waiting in an empty room, so it is closed, the common room is always kept
+    if remaining_user_ids.is_empty() && room_id != types::COMMON_ROOM_KEY {
+        store.delete_room(&room_id).await.to_internal_api_error()?;
+    }
+
+    tracing::info!("Removed user {user_id} from the room {room_id}");
+
+    if remaining_user_ids.is_empty() {
+        return Ok(Some(room_id));
+    }
+
+    let remaining_users = store
+        .get_multiple_users(remaining_user_ids.clone())
+        .await
+        .to_internal_api_error()?;
+
+    for remaining_user_id in remaining_user_ids {
+        let message = types::RoomMessage::UserLeft {
+            room_id: room_id.clone(),
+            users: remaining_users.clone(),
+        };
+
+        // The remaining user might be disconnecting as well
+        if let Err(error) = store
+            .send_message_to_user(&remaining_user_id, message)
+            .await
+        {
+            tracing::debug!(?error, "Cannot inform the user {remaining_user_id}");
+        }
+    }
+
+    Ok(Some(room_id))
 }
@@ -0,0 +1,51 @@
+use tokio_util::sync::CancellationToken;
+use tonic::server::NamedService;
+use tonic_health::{server::HealthReporter, ServingStatus};
+
+use super::{
+    server::{grpc_server::GrpcServer, MyGrpc},
+    storage::Store,
+    types,
+};
+
+/// Update the status of the overall server and the game service
+pub async fn set_serving_status(health_reporter: &mut HealthReporter, status: ServingStatus) {
+    // The empty service name is the overall health of the server
+    health_reporter.set_service_status("", status).await;
+    health_reporter
+        .set_service_status(<GrpcServer<MyGrpc> as NamedService>::NAME, status)
+        .await;
+}
+
+/// Keep the health status in sync with the readiness of the server
+///
+/// The server is ready as long as redis can be reached and the server is not being drained
+/// The status is reported until the shutdown token is cancelled
+pub async fn report_readiness(
+    mut health_reporter: HealthReporter,
+    store: Store,
+    shutdown_token: CancellationToken,
+) {
+    let mut interval = tokio::time::interval(types::HEALTH_CHECK_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {},
+            _ = shutdown_token.cancelled() => break,
+        }
+
+        let status = if store.is_draining() {
+            ServingStatus::NotServing
+        } else {
+            match store.redis_client.ping().await {
+                Ok(()) => ServingStatus::Serving,
+                Err(error) => {
+                    tracing::error!(?error, "Redis is not reachable");
+                    ServingStatus::NotServing
+                }
+            }
+        };
+
+        set_serving_status(&mut health_reporter, status).await;
+    }
+}
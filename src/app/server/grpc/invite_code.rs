@@ -0,0 +1,38 @@
+use rand::Rng;
+
+/// The characters that are easily mistaken for one another, such as `0` and `O` or `1`, `I` and `L`, are left out
+const INVITE_CODE_ALPHABET: &[u8] = b"23456789ABCDEFGHJKMNPQRSTUVWXYZ";
+/// About 887 million codes, a code is generated again in the rare case that it is taken
+pub const INVITE_CODE_LENGTH: usize = 6;
+/// Prefix of the links that the client accepts on the command line
+pub const JOIN_LINK_PREFIX: &str = "blazer://join/";
+
+pub fn generate_invite_code() -> String {
+    let mut rng = rand::thread_rng();
+
+    (0..INVITE_CODE_LENGTH)
+        .map(|_| char::from(INVITE_CODE_ALPHABET[rng.gen_range(0..INVITE_CODE_ALPHABET.len())]))
+        .collect()
+}
+
+/// Get the stored form of a code typed by a user, `None` if it cannot be a valid code
+///
+/// The codes are case insensitive, and the spaces and dashes used to read them out are ignored
+pub fn normalize_invite_code(invite_code: &str) -> Option<String> {
+    let invite_code = invite_code
+        .chars()
+        .filter(|character| !character.is_whitespace() && *character != '-')
+        .map(|character| character.to_ascii_uppercase())
+        .collect::<String>();
+
+    let is_valid = invite_code.len() == INVITE_CODE_LENGTH
+        && invite_code
+            .bytes()
+            .all(|character| INVITE_CODE_ALPHABET.contains(&character));
+
+    is_valid.then_some(invite_code)
+}
+
+pub fn get_join_link(invite_code: &str) -> String {
+    format!("{JOIN_LINK_PREFIX}{invite_code}")
+}
@@ -1,33 +1,49 @@
-use fred::{interfaces::KeysInterface, types::MultipleKeys};
+use std::sync::Arc;
 
 use crate::app::server::errors;
 
+mod backend;
+mod memory;
+mod redis;
+
+pub use backend::{Backend, BackendResult, Write};
+
+/// Stores the values as json, on redis or on any other backend that runs the same commands
 #[derive(Clone)]
 pub struct RedisClient {
-    client: fred::clients::RedisClient,
+    backend: Arc<dyn Backend>,
 }
 
 impl RedisClient {
     pub fn new(inner_client: fred::clients::RedisClient) -> Self {
+        Self::with_backend(redis::RedisBackend::new(inner_client))
+    }
+
+    /// A client that keeps all the data in the memory of this process
+    ///
+    /// Used to run the server without a redis server, such as in the integration tests
+    pub fn in_memory() -> Self {
+        Self::with_backend(memory::MemoryBackend::default())
+    }
+
+    pub fn with_backend(backend: impl Backend + 'static) -> Self {
         Self {
-            client: inner_client,
+            backend: Arc::new(backend),
         }
     }
 }
 
 type DbResult<T> = Result<T, errors::DbError>;
 
+/// Number of times an update is tried before giving up on a value that keeps changing
+const MAX_UPDATE_ATTEMPTS: usize = 16;
+
 impl RedisClient {
-    pub async fn get_and_deserialize<
-        K: Into<fred::types::RedisKey> + Send,
-        V: serde::de::DeserializeOwned,
-    >(
+    pub async fn get_and_deserialize<V: serde::de::DeserializeOwned>(
         &self,
-        key: K,
+        key: &str,
     ) -> DbResult<V> {
-        let get_command_result = self.client.get::<Option<String>, _>(key).await;
-
-        match get_command_result {
+        match self.backend.get(key).await {
             Ok(value_string_optional) => match value_string_optional {
                 Some(value_string) => match serde_json::from_str::<V>(&value_string) {
                     Ok(value) => Ok(value),
@@ -42,27 +58,18 @@ impl RedisClient {
         }
     }
 
-    pub async fn serialize_and_set<
-        K: Into<fred::types::RedisKey> + Send,
-        V: serde::Serialize + serde::de::DeserializeOwned,
-    >(
+    pub async fn serialize_and_set<V: serde::Serialize + serde::de::DeserializeOwned>(
         &self,
-        key: K,
+        key: &str,
         value: V,
     ) -> DbResult<V> {
         let serialized_value = serde_json::to_string(&value);
 
         match serialized_value {
-            Ok(serialized_value) => {
-                match self
-                    .client
-                    .set::<String, _, _>(key, serialized_value, None, None, false)
-                    .await
-                {
-                    Ok(_) => Ok(value),
-                    Err(error) => Err(errors::DbError::Others(error)),
-                }
-            }
+            Ok(serialized_value) => match self.backend.set(key, serialized_value).await {
+                Ok(_) => Ok(value),
+                Err(error) => Err(errors::DbError::Others(error)),
+            },
             Err(serialization_error) => {
                 log::error!("serialization_error {serialization_error:?}");
                 Err(errors::DbError::ParsingFailure)
@@ -70,19 +77,105 @@ impl RedisClient {
         }
     }
 
-    pub async fn get_multiple_keys<
-        K: Into<MultipleKeys> + Send,
-        V: serde::Serialize + serde::de::DeserializeOwned,
-    >(
+    /// Set the value unless the key already exists, the value is removed by redis once it expires
+    ///
+    /// Fails with `DuplicateValue` when the key exists
+    pub async fn serialize_and_set_if_absent<V: serde::Serialize>(
         &self,
-        keys: K,
-    ) -> DbResult<Vec<V>> {
-        let get_command_result = self.client.mget::<Vec<String>, _>(keys).await;
+        key: &str,
+        value: V,
+        expires_in: std::time::Duration,
+    ) -> DbResult<V> {
+        let serialized_value = self.serialize(&value)?;
 
-        match get_command_result {
+        match self
+            .backend
+            .set_if_absent(key, serialized_value, expires_in)
+            .await
+        {
+            Ok(true) => Ok(value),
+            Ok(false) => Err(errors::DbError::DuplicateValue),
+            Err(error) => Err(errors::DbError::Others(error)),
+        }
+    }
+
+    /// Remove the value and return it, the concurrent callers get `NotFound` once the value is taken
+    pub async fn get_delete_and_deserialize<V: serde::de::DeserializeOwned>(
+        &self,
+        key: &str,
+    ) -> DbResult<V> {
+        match self.backend.get_and_delete(key).await {
+            Ok(Some(value)) => serde_json::from_str(&value).map_err(|deserialize_error| {
+                log::error!("{deserialize_error:?}");
+                errors::DbError::NotFound
+            }),
+            Ok(None) => Err(errors::DbError::NotFound),
+            Err(error) => Err(errors::DbError::Others(error)),
+        }
+    }
+
+    /// Update the value without overwriting the changes written by someone else in the meantime
+    ///
+    /// The update runs on the latest value and runs again when the value was changed before it was written
+    /// Nothing is written when the update returns `None`, the current value is returned along with the outcome
+    pub async fn update_and_serialize<V, T>(
+        &self,
+        key: &str,
+        mut update: impl FnMut(&mut V) -> Option<T> + Send,
+    ) -> DbResult<(V, Option<T>)>
+    where
+        V: serde::Serialize + serde::de::DeserializeOwned + Send,
+        T: Send,
+    {
+        for _ in 0..MAX_UPDATE_ATTEMPTS {
+            let value_string = self
+                .backend
+                .get(key)
+                .await
+                .map_err(errors::DbError::Others)?
+                .ok_or(errors::DbError::NotFound)?;
+
+            let mut value =
+                serde_json::from_str::<V>(&value_string).map_err(|deserialize_error| {
+                    tracing::error!(?deserialize_error);
+                    errors::DbError::ParsingFailure
+                })?;
+
+            let Some(outcome) = update(&mut value) else {
+                return Ok((value, None));
+            };
+
+            let serialized_value =
+                serde_json::to_string(&value).map_err(|serialization_error| {
+                    tracing::error!(?serialization_error);
+                    errors::DbError::ParsingFailure
+                })?;
+
+            if self
+                .backend
+                .compare_and_set(key, &value_string, serialized_value)
+                .await
+                .map_err(errors::DbError::Others)?
+            {
+                return Ok((value, Some(outcome)));
+            }
+
+            tracing::debug!("The key {key} was changed during the update, trying again");
+        }
+
+        Err(errors::DbError::UpdateConflict)
+    }
+
+    /// The keys that do not exist are skipped, they might have been deleted since their ids were read
+    pub async fn get_multiple_keys<V: serde::Serialize + serde::de::DeserializeOwned>(
+        &self,
+        keys: Vec<String>,
+    ) -> DbResult<Vec<V>> {
+        match self.backend.get_multiple(keys).await {
             Ok(value_string_optional) => {
                 let result = value_string_optional
                     .iter()
+                    .flatten()
                     .map(|value_string| serde_json::from_str::<V>(value_string))
                     .collect::<Result<Vec<_>, _>>();
 
@@ -96,8 +189,7 @@ impl RedisClient {
     }
 
     pub async fn delete_key(&self, key: &str) -> DbResult<()> {
-        let delete_result = self.client.del::<usize, _>(key).await;
-        match delete_result {
+        match self.backend.delete(key).await {
             Ok(_) => {
                 tracing::info!("Key {key} has been successfully deleted");
                 Ok(())
@@ -108,4 +200,135 @@ impl RedisClient {
             }
         }
     }
+
+    /// Apply all the writes at once, see `Backend::write_atomically`
+    pub async fn write_atomically(&self, writes: Vec<Write>) -> DbResult<()> {
+        self.backend
+            .write_atomically(writes)
+            .await
+            .map_err(errors::DbError::Others)
+    }
+
+    /// Serialize the value for a `Write::Set` of `write_atomically`
+    pub fn serialize<V: serde::Serialize>(&self, value: &V) -> DbResult<String> {
+        serde_json::to_string(value).map_err(|serialization_error| {
+            log::error!("serialization_error {serialization_error:?}");
+            errors::DbError::ParsingFailure
+        })
+    }
+
+    pub async fn add_to_set(&self, key: &str, member: &str) -> DbResult<()> {
+        self.backend
+            .add_to_set(key, member)
+            .await
+            .map_err(errors::DbError::Others)
+    }
+
+    pub async fn remove_from_set(&self, key: &str, member: &str) -> DbResult<()> {
+        self.backend
+            .remove_from_set(key, member)
+            .await
+            .map_err(errors::DbError::Others)
+    }
+
+    /// Add the member unless the set already has `limit` members, returns whether the member is in the set
+    pub async fn add_to_set_within_limit(
+        &self,
+        key: &str,
+        member: &str,
+        limit: usize,
+    ) -> DbResult<bool> {
+        self.backend
+            .add_to_set_within_limit(key, member, limit)
+            .await
+            .map_err(errors::DbError::Others)
+    }
+
+    pub async fn is_member_of_set(&self, key: &str, member: &str) -> DbResult<bool> {
+        self.backend
+            .is_member_of_set(key, member)
+            .await
+            .map_err(errors::DbError::Others)
+    }
+
+    /// Get the number of members in the set, a set that does not exist has no members
+    pub async fn get_set_size(&self, key: &str) -> DbResult<usize> {
+        self.backend
+            .get_set_size(key)
+            .await
+            .map_err(errors::DbError::Others)
+    }
+
+    pub async fn get_set_members(&self, key: &str) -> DbResult<Vec<String>> {
+        self.backend
+            .get_set_members(key)
+            .await
+            .map_err(errors::DbError::Others)
+    }
+
+    pub async fn add_to_sorted_set(&self, key: &str, member: &str, score: f64) -> DbResult<()> {
+        self.backend
+            .add_to_sorted_set(key, member, score)
+            .await
+            .map_err(errors::DbError::Others)
+    }
+
+    pub async fn remove_from_sorted_set(&self, key: &str, member: &str) -> DbResult<()> {
+        self.backend
+            .remove_from_sorted_set(key, member)
+            .await
+            .map_err(errors::DbError::Others)
+    }
+
+    /// Get the score of the member, `None` if the member is not in the sorted set
+    pub async fn get_sorted_set_score(&self, key: &str, member: &str) -> DbResult<Option<f64>> {
+        self.backend
+            .get_sorted_set_score(key, member)
+            .await
+            .map_err(errors::DbError::Others)
+    }
+
+    pub async fn count_sorted_set_from(&self, key: &str, min_score: f64) -> DbResult<usize> {
+        self.backend
+            .count_sorted_set_from(key, min_score)
+            .await
+            .map_err(errors::DbError::Others)
+    }
+
+    pub async fn remove_sorted_set_below(&self, key: &str, min_score: f64) -> DbResult<()> {
+        self.backend
+            .remove_sorted_set_below(key, min_score)
+            .await
+            .map_err(errors::DbError::Others)
+    }
+
+    /// Check whether the redis server can be reached
+    pub async fn ping(&self) -> DbResult<()> {
+        self.backend.ping().await.map_err(errors::DbError::Others)
+    }
+
+    /// Publish the value to the subscribers of the channel, on every server
+    pub async fn serialize_and_publish<V: serde::Serialize>(
+        &self,
+        channel: &str,
+        value: &V,
+    ) -> DbResult<()> {
+        let message = self.serialize(value)?;
+
+        self.backend
+            .publish(channel, message)
+            .await
+            .map_err(errors::DbError::Others)
+    }
+
+    /// Receive the messages published on the channel from now on, see `Backend::subscribe`
+    pub async fn subscribe(
+        &self,
+        channel: &str,
+    ) -> DbResult<tokio::sync::mpsc::UnboundedReceiver<String>> {
+        self.backend
+            .subscribe(channel)
+            .await
+            .map_err(errors::DbError::Others)
+    }
 }
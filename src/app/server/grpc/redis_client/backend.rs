@@ -0,0 +1,66 @@
+use tokio::sync::mpsc;
+
+pub type BackendResult<T> = Result<T, fred::error::RedisError>;
+
+/// A write that is applied along with the other writes of `write_atomically`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Write {
+    Set { key: String, value: String },
+    Delete { key: String },
+    AddToSet { key: String, member: String },
+    RemoveFromSet { key: String, member: String },
+}
+
+/// The redis commands used by the server
+///
+/// The values are stored as strings, they are serialized by the `RedisClient`
+#[tonic::async_trait]
+pub trait Backend: Send + Sync {
+    async fn get(&self, key: &str) -> BackendResult<Option<String>>;
+    async fn set(&self, key: &str, value: String) -> BackendResult<()>;
+    /// Set the value with an expiry, unless the key already exists, returns whether the value was set
+    async fn set_if_absent(
+        &self,
+        key: &str,
+        value: String,
+        expires_in: std::time::Duration,
+    ) -> BackendResult<bool>;
+    /// Remove the value and return it, only one of the concurrent callers gets the value
+    async fn get_and_delete(&self, key: &str) -> BackendResult<Option<String>>;
+    /// Set the value only if the key still holds `expected`, returns whether the value was set
+    async fn compare_and_set(
+        &self,
+        key: &str,
+        expected: &str,
+        value: String,
+    ) -> BackendResult<bool>;
+    /// The value of every key, `None` for the keys that do not exist
+    async fn get_multiple(&self, keys: Vec<String>) -> BackendResult<Vec<Option<String>>>;
+    async fn delete(&self, key: &str) -> BackendResult<()>;
+    /// Apply all the writes at once, the other clients see either none or all of them
+    async fn write_atomically(&self, writes: Vec<Write>) -> BackendResult<()>;
+    async fn add_to_set(&self, key: &str, member: &str) -> BackendResult<()>;
+    async fn remove_from_set(&self, key: &str, member: &str) -> BackendResult<()>;
+    /// Add the member unless the set already has `limit` members, returns whether the member is in the set
+    async fn add_to_set_within_limit(
+        &self,
+        key: &str,
+        member: &str,
+        limit: usize,
+    ) -> BackendResult<bool>;
+    async fn is_member_of_set(&self, key: &str, member: &str) -> BackendResult<bool>;
+    async fn get_set_size(&self, key: &str) -> BackendResult<usize>;
+    async fn get_set_members(&self, key: &str) -> BackendResult<Vec<String>>;
+    async fn add_to_sorted_set(&self, key: &str, member: &str, score: f64) -> BackendResult<()>;
+    async fn remove_from_sorted_set(&self, key: &str, member: &str) -> BackendResult<()>;
+    async fn get_sorted_set_score(&self, key: &str, member: &str) -> BackendResult<Option<f64>>;
+    /// Number of members with a score of at least `min_score`
+    async fn count_sorted_set_from(&self, key: &str, min_score: f64) -> BackendResult<usize>;
+    /// Remove the members with a score lower than `min_score`
+    async fn remove_sorted_set_below(&self, key: &str, min_score: f64) -> BackendResult<()>;
+    async fn ping(&self) -> BackendResult<()>;
+    /// Send the message to the subscribers of the channel, on every server sharing the backend
+    async fn publish(&self, channel: &str, message: String) -> BackendResult<()>;
+    /// Receive the messages published on the channel from now on, until the receiver is dropped
+    async fn subscribe(&self, channel: &str) -> BackendResult<mpsc::UnboundedReceiver<String>>;
+}
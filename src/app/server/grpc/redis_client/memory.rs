@@ -0,0 +1,328 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use tokio::sync::mpsc;
+
+use super::backend::{Backend, BackendResult, Write};
+
+/// Keeps the values and sets of the redis commands used by the server in the memory of the process
+///
+/// The data is not shared between instances and is lost on restart, this is meant for tests
+#[derive(Default)]
+pub struct MemoryBackend {
+    state: Mutex<MemoryState>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    values: HashMap<String, String>,
+    sets: HashMap<String, HashSet<String>>,
+    sorted_sets: HashMap<String, HashMap<String, f64>>,
+    subscribers: HashMap<String, Vec<mpsc::UnboundedSender<String>>>,
+    /// Time at which the values written with an expiry are removed
+    expirations: HashMap<String, Instant>,
+}
+
+impl MemoryState {
+    /// The expired values are removed once they are accessed, they are never returned
+    fn remove_if_expired(&mut self, key: &str) {
+        if self
+            .expirations
+            .get(key)
+            .is_some_and(|expires_at| *expires_at <= Instant::now())
+        {
+            self.expirations.remove(key);
+            self.values.remove(key);
+        }
+    }
+
+    fn set_value(&mut self, key: String, value: String) {
+        // A key holds a single type of value, as in redis
+        self.sets.remove(&key);
+        self.sorted_sets.remove(&key);
+        self.expirations.remove(&key);
+        self.values.insert(key, value);
+    }
+
+    fn delete_key(&mut self, key: &str) {
+        self.values.remove(key);
+        self.sets.remove(key);
+        self.sorted_sets.remove(key);
+        self.expirations.remove(key);
+    }
+}
+
+#[tonic::async_trait]
+impl Backend for MemoryBackend {
+    async fn get(&self, key: &str) -> BackendResult<Option<String>> {
+        let mut state = self.state.lock().unwrap();
+        state.remove_if_expired(key);
+        Ok(state.values.get(key).cloned())
+    }
+
+    async fn set(&self, key: &str, value: String) -> BackendResult<()> {
+        self.state.lock().unwrap().set_value(key.to_string(), value);
+        Ok(())
+    }
+
+    async fn set_if_absent(
+        &self,
+        key: &str,
+        value: String,
+        expires_in: Duration,
+    ) -> BackendResult<bool> {
+        let mut state = self.state.lock().unwrap();
+        state.remove_if_expired(key);
+
+        if state.values.contains_key(key) {
+            return Ok(false);
+        }
+
+        state.set_value(key.to_string(), value);
+        state
+            .expirations
+            .insert(key.to_string(), Instant::now() + expires_in);
+        Ok(true)
+    }
+
+    async fn get_and_delete(&self, key: &str) -> BackendResult<Option<String>> {
+        let mut state = self.state.lock().unwrap();
+        state.remove_if_expired(key);
+        state.expirations.remove(key);
+        Ok(state.values.remove(key))
+    }
+
+    async fn compare_and_set(
+        &self,
+        key: &str,
+        expected: &str,
+        value: String,
+    ) -> BackendResult<bool> {
+        let mut state = self.state.lock().unwrap();
+        state.remove_if_expired(key);
+
+        match state.values.get_mut(key) {
+            Some(current) if current == expected => {
+                *current = value;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn get_multiple(&self, keys: Vec<String>) -> BackendResult<Vec<Option<String>>> {
+        let mut state = self.state.lock().unwrap();
+        Ok(keys
+            .iter()
+            .map(|key| {
+                state.remove_if_expired(key);
+                state.values.get(key).cloned()
+            })
+            .collect())
+    }
+
+    async fn delete(&self, key: &str) -> BackendResult<()> {
+        self.state.lock().unwrap().delete_key(key);
+        Ok(())
+    }
+
+    async fn write_atomically(&self, writes: Vec<Write>) -> BackendResult<()> {
+        let mut state = self.state.lock().unwrap();
+
+        for write in writes {
+            match write {
+                Write::Set { key, value } => state.set_value(key, value),
+                Write::Delete { key } => state.delete_key(&key),
+                Write::AddToSet { key, member } => {
+                    state.sets.entry(key).or_default().insert(member);
+                }
+                Write::RemoveFromSet { key, member } => {
+                    if let Some(set) = state.sets.get_mut(&key) {
+                        set.remove(&member);
+
+                        if set.is_empty() {
+                            state.sets.remove(&key);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn add_to_set(&self, key: &str, member: &str) -> BackendResult<()> {
+        self.state
+            .lock()
+            .unwrap()
+            .sets
+            .entry(key.to_string())
+            .or_default()
+            .insert(member.to_string());
+        Ok(())
+    }
+
+    async fn remove_from_set(&self, key: &str, member: &str) -> BackendResult<()> {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(set) = state.sets.get_mut(key) {
+            set.remove(member);
+
+            // Redis deletes the sets that have no members left
+            if set.is_empty() {
+                state.sets.remove(key);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn add_to_set_within_limit(
+        &self,
+        key: &str,
+        member: &str,
+        limit: usize,
+    ) -> BackendResult<bool> {
+        let mut state = self.state.lock().unwrap();
+        let set = state.sets.entry(key.to_string()).or_default();
+
+        if set.contains(member) {
+            return Ok(true);
+        }
+
+        let is_added = set.len() < limit && set.insert(member.to_string());
+
+        if set.is_empty() {
+            state.sets.remove(key);
+        }
+
+        Ok(is_added)
+    }
+
+    async fn is_member_of_set(&self, key: &str, member: &str) -> BackendResult<bool> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .sets
+            .get(key)
+            .is_some_and(|set| set.contains(member)))
+    }
+
+    async fn get_set_size(&self, key: &str) -> BackendResult<usize> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .sets
+            .get(key)
+            .map_or(0, HashSet::len))
+    }
+
+    async fn get_set_members(&self, key: &str) -> BackendResult<Vec<String>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .sets
+            .get(key)
+            .map(|set| set.iter().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    async fn add_to_sorted_set(&self, key: &str, member: &str, score: f64) -> BackendResult<()> {
+        self.state
+            .lock()
+            .unwrap()
+            .sorted_sets
+            .entry(key.to_string())
+            .or_default()
+            .insert(member.to_string(), score);
+        Ok(())
+    }
+
+    async fn remove_from_sorted_set(&self, key: &str, member: &str) -> BackendResult<()> {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(sorted_set) = state.sorted_sets.get_mut(key) {
+            sorted_set.remove(member);
+
+            if sorted_set.is_empty() {
+                state.sorted_sets.remove(key);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn get_sorted_set_score(&self, key: &str, member: &str) -> BackendResult<Option<f64>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .sorted_sets
+            .get(key)
+            .and_then(|sorted_set| sorted_set.get(member).copied()))
+    }
+
+    async fn count_sorted_set_from(&self, key: &str, min_score: f64) -> BackendResult<usize> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .sorted_sets
+            .get(key)
+            .map_or(0, |sorted_set| {
+                sorted_set
+                    .values()
+                    .filter(|score| **score >= min_score)
+                    .count()
+            }))
+    }
+
+    async fn remove_sorted_set_below(&self, key: &str, min_score: f64) -> BackendResult<()> {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(sorted_set) = state.sorted_sets.get_mut(key) {
+            sorted_set.retain(|_, score| *score >= min_score);
+
+            if sorted_set.is_empty() {
+                state.sorted_sets.remove(key);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The memory of the process is always reachable
+    async fn ping(&self) -> BackendResult<()> {
+        Ok(())
+    }
+
+    async fn publish(&self, channel: &str, message: String) -> BackendResult<()> {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(subscribers) = state.subscribers.get_mut(channel) {
+            // The subscribers that dropped their receiver are removed
+            subscribers.retain(|subscriber| subscriber.send(message.clone()).is_ok());
+        }
+
+        Ok(())
+    }
+
+    async fn subscribe(&self, channel: &str) -> BackendResult<mpsc::UnboundedReceiver<String>> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.state
+            .lock()
+            .unwrap()
+            .subscribers
+            .entry(channel.to_string())
+            .or_default()
+            .push(sender);
+
+        Ok(receiver)
+    }
+}
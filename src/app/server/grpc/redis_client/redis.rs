@@ -0,0 +1,280 @@
+use fred::{
+    interfaces::{
+        ClientLike, KeysInterface, LuaInterface, PubsubInterface, SetsInterface,
+        SortedSetsInterface, TransactionInterface,
+    },
+    types::{Expiration, SetOptions, ZRange, ZRangeBound, ZRangeKind},
+};
+use tokio::sync::{broadcast::error::RecvError, mpsc};
+
+use super::backend::{Backend, BackendResult, Write};
+use crate::app::server::metrics::observe_redis_command;
+
+/// Sets the value only if the key still holds the expected value, the script runs atomically on redis
+const COMPARE_AND_SET_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    redis.call("SET", KEYS[1], ARGV[2])
+    return 1
+end
+return 0
+"#;
+
+/// Adds the member unless the set is full, the script runs atomically on redis
+const ADD_TO_SET_WITHIN_LIMIT_SCRIPT: &str = r#"
+if redis.call("SISMEMBER", KEYS[1], ARGV[1]) == 1 then
+    return 1
+end
+if redis.call("SCARD", KEYS[1]) >= tonumber(ARGV[2]) then
+    return 0
+end
+redis.call("SADD", KEYS[1], ARGV[1])
+return 1
+"#;
+
+/// Runs the commands on a redis server
+pub struct RedisBackend {
+    client: fred::clients::RedisClient,
+}
+
+impl RedisBackend {
+    pub fn new(client: fred::clients::RedisClient) -> Self {
+        Self { client }
+    }
+}
+
+#[tonic::async_trait]
+impl Backend for RedisBackend {
+    async fn get(&self, key: &str) -> BackendResult<Option<String>> {
+        observe_redis_command("GET", self.client.get(key)).await
+    }
+
+    async fn set(&self, key: &str, value: String) -> BackendResult<()> {
+        observe_redis_command(
+            "SET",
+            self.client
+                .set::<String, _, _>(key, value, None, None, false),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn set_if_absent(
+        &self,
+        key: &str,
+        value: String,
+        expires_in: std::time::Duration,
+    ) -> BackendResult<bool> {
+        // At least a millisecond, redis rejects an expiry of zero
+        let expires_in_ms = i64::try_from(expires_in.as_millis())
+            .unwrap_or(i64::MAX)
+            .max(1);
+
+        observe_redis_command(
+            "SET",
+            self.client.set::<Option<String>, _, _>(
+                key,
+                value,
+                Some(Expiration::PX(expires_in_ms)),
+                Some(SetOptions::NX),
+                false,
+            ),
+        )
+        .await
+        .map(|is_set| is_set.is_some())
+    }
+
+    async fn get_and_delete(&self, key: &str) -> BackendResult<Option<String>> {
+        observe_redis_command("GETDEL", self.client.getdel(key)).await
+    }
+
+    async fn compare_and_set(
+        &self,
+        key: &str,
+        expected: &str,
+        value: String,
+    ) -> BackendResult<bool> {
+        observe_redis_command(
+            "EVAL",
+            self.client.eval::<i64, _, _, _>(
+                COMPARE_AND_SET_SCRIPT,
+                key,
+                vec![expected.to_string(), value],
+            ),
+        )
+        .await
+        .map(|is_set| is_set == 1)
+    }
+
+    async fn get_multiple(&self, keys: Vec<String>) -> BackendResult<Vec<Option<String>>> {
+        observe_redis_command("MGET", self.client.mget(keys)).await
+    }
+
+    async fn delete(&self, key: &str) -> BackendResult<()> {
+        observe_redis_command("DEL", self.client.del::<usize, _>(key))
+            .await
+            .map(|_| ())
+    }
+
+    async fn write_atomically(&self, writes: Vec<Write>) -> BackendResult<()> {
+        let transaction = self.client.multi();
+
+        // The commands are queued on the client and sent along with EXEC
+        for write in writes {
+            match write {
+                Write::Set { key, value } => {
+                    transaction
+                        .set::<(), _, _>(key, value, None, None, false)
+                        .await?
+                }
+                Write::Delete { key } => transaction.del::<(), _>(key).await?,
+                Write::AddToSet { key, member } => {
+                    transaction.sadd::<(), _, _>(key, member).await?
+                }
+                Write::RemoveFromSet { key, member } => {
+                    transaction.srem::<(), _, _>(key, member).await?
+                }
+            }
+        }
+
+        observe_redis_command("EXEC", transaction.exec::<()>(true)).await
+    }
+
+    async fn add_to_set(&self, key: &str, member: &str) -> BackendResult<()> {
+        observe_redis_command("SADD", self.client.sadd::<usize, _, _>(key, member))
+            .await
+            .map(|_| ())
+    }
+
+    async fn remove_from_set(&self, key: &str, member: &str) -> BackendResult<()> {
+        observe_redis_command("SREM", self.client.srem::<usize, _, _>(key, member))
+            .await
+            .map(|_| ())
+    }
+
+    async fn add_to_set_within_limit(
+        &self,
+        key: &str,
+        member: &str,
+        limit: usize,
+    ) -> BackendResult<bool> {
+        observe_redis_command(
+            "EVAL",
+            self.client.eval::<i64, _, _, _>(
+                ADD_TO_SET_WITHIN_LIMIT_SCRIPT,
+                key,
+                vec![member.to_string(), limit.to_string()],
+            ),
+        )
+        .await
+        .map(|is_member| is_member == 1)
+    }
+
+    async fn is_member_of_set(&self, key: &str, member: &str) -> BackendResult<bool> {
+        observe_redis_command("SISMEMBER", self.client.sismember(key, member)).await
+    }
+
+    async fn get_set_size(&self, key: &str) -> BackendResult<usize> {
+        observe_redis_command("SCARD", self.client.scard(key)).await
+    }
+
+    async fn get_set_members(&self, key: &str) -> BackendResult<Vec<String>> {
+        observe_redis_command("SMEMBERS", self.client.smembers(key)).await
+    }
+
+    async fn add_to_sorted_set(&self, key: &str, member: &str, score: f64) -> BackendResult<()> {
+        observe_redis_command(
+            "ZADD",
+            self.client
+                .zadd::<usize, _, _>(key, None, None, false, false, (score, member)),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn remove_from_sorted_set(&self, key: &str, member: &str) -> BackendResult<()> {
+        observe_redis_command("ZREM", self.client.zrem::<usize, _, _>(key, member))
+            .await
+            .map(|_| ())
+    }
+
+    async fn get_sorted_set_score(&self, key: &str, member: &str) -> BackendResult<Option<f64>> {
+        observe_redis_command("ZSCORE", self.client.zscore(key, member)).await
+    }
+
+    async fn count_sorted_set_from(&self, key: &str, min_score: f64) -> BackendResult<usize> {
+        observe_redis_command("ZCOUNT", self.client.zcount(key, min_score, f64::INFINITY)).await
+    }
+
+    async fn remove_sorted_set_below(&self, key: &str, min_score: f64) -> BackendResult<()> {
+        let below_min_score = ZRange {
+            kind: ZRangeKind::Exclusive,
+            range: ZRangeBound::Score(min_score),
+        };
+
+        observe_redis_command(
+            "ZREMRANGEBYSCORE",
+            self.client
+                .zremrangebyscore::<usize, _, _, _>(key, f64::NEG_INFINITY, below_min_score),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn ping(&self) -> BackendResult<()> {
+        observe_redis_command("PING", self.client.ping::<String>())
+            .await
+            .map(|_| ())
+    }
+
+    async fn publish(&self, channel: &str, message: String) -> BackendResult<()> {
+        observe_redis_command(
+            "PUBLISH",
+            self.client.publish::<i64, _, _>(channel, message),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn subscribe(&self, channel: &str) -> BackendResult<mpsc::UnboundedReceiver<String>> {
+        // A subscribed connection cannot run the other commands, the subscription has a connection of its own
+        let subscriber = self.client.clone_new();
+        subscriber.connect();
+        subscriber.wait_for_connect().await?;
+
+        let mut messages = subscriber.on_message();
+        subscriber.subscribe::<(), _>(channel).await?;
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let channel = channel.to_string();
+
+        tokio::spawn(async move {
+            loop {
+                let message = match messages.recv().await {
+                    Ok(message) => message,
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Skipped {skipped} messages published on {channel}");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                if message.channel != channel {
+                    continue;
+                }
+
+                let Ok(value) = message.value.convert::<String>() else {
+                    tracing::warn!("Received a message that is not a string on {channel}");
+                    continue;
+                };
+
+                if sender.send(value).is_err() {
+                    break;
+                }
+            }
+
+            let _ = subscriber.quit().await;
+        });
+
+        Ok(receiver)
+    }
+}
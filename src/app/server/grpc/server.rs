@@ -1,24 +1,26 @@
-use std::{
-    fmt::Debug,
-    sync::{Arc, Mutex},
-};
+use std::{fmt::Debug, sync::Arc};
 
 pub use blazer_grpc::{
-    grpc_client, grpc_server, PingRequest, PingResponse, RoomServiceRequest, RoomServiceResponse,
+    admin_client, admin_server, game_service_response, game_user_status, grpc_client, grpc_server,
+    room_service_request, AdminCheatFlag, AdminDrainRequest, AdminDrainResponse,
+    AdminEmptyResponse, AdminEntityRequest, AdminGameDetails, AdminListRequest, AdminListResponse,
+    AdminRoomDetails, AdminUserDetails, ChatMessage, GameServiceRequest, GameServiceResponse,
+    GameUserStatus, GlobalStatsRequest, GlobalStatsResponse, LeaveRoomRequest, LeaveRoomResponse,
+    ListRoomsRequest, ListRoomsResponse, PingRequest, PingResponse, PublicRoom, ResumableGame,
+    RoomServiceRequest, RoomServiceResponse, SendRoomChatRequest, SendRoomChatResponse,
     UserDetails, FILE_DESCRIPTOR_SET,
 };
 
-use super::{functions, redis_client::RedisClient, storage::models, types};
+use super::{functions, storage::models, types};
 
 use crate::app::server::{
     errors::{self, ResultExtApp},
-    grpc::{
-        server::blazer_grpc::{GameServiceRequest, GameServiceResponse},
-        storage::{
-            interface::{room::RoomInterface, user::UserInterface},
-            Store,
-        },
+    grpc::storage::{
+        interface::{room::RoomInterface, user::UserInterface},
+        Store,
     },
+    logging,
+    rate_limit::{self, RateLimiter},
 };
 
 mod blazer_grpc {
@@ -41,16 +43,13 @@ impl From<models::User> for UserDetails {
 
 pub struct MyGrpc {
     pub store: Store,
+    /// The calls are not limited when the rate limit is not configured
+    pub rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl MyGrpc {
-    pub async fn new(redis_client: RedisClient) -> Self {
+    pub async fn new(store: Store, rate_limiter: Option<Arc<RateLimiter>>) -> Self {
         // Create the common room if not exists at the application startup
-        let store = Store {
-            redis_client,
-            room_users_state: Arc::new(Mutex::new(std::collections::HashMap::new())),
-        };
-
         let common_room = store.find_room(types::COMMON_ROOM_KEY).await;
 
         match common_room {
@@ -70,7 +69,10 @@ impl MyGrpc {
                 }
             }
         }
-        Self { store }
+        Self {
+            store,
+            rate_limiter,
+        }
     }
 }
 
@@ -82,6 +84,10 @@ type GameServiceStream = std::pin::Pin<
     Box<dyn tokio_stream::Stream<Item = Result<GameServiceResponse, tonic::Status>> + Send>,
 >;
 
+type GlobalStatsStream = std::pin::Pin<
+    Box<dyn tokio_stream::Stream<Item = Result<GlobalStatsResponse, tonic::Status>> + Send>,
+>;
+
 trait GetAuthData {
     fn get_user_id(&self) -> String;
 }
@@ -100,6 +106,30 @@ impl GetAuthData for RoomServiceRequest {
     }
 }
 
+impl GetAuthData for LeaveRoomRequest {
+    fn get_user_id(&self) -> String {
+        self.client_id.clone()
+    }
+}
+
+impl GetAuthData for SendRoomChatRequest {
+    fn get_user_id(&self) -> String {
+        self.client_id.clone()
+    }
+}
+
+impl GetAuthData for ListRoomsRequest {
+    fn get_user_id(&self) -> String {
+        self.client_id.clone()
+    }
+}
+
+impl GetAuthData for GlobalStatsRequest {
+    fn get_user_id(&self) -> String {
+        self.client_id.clone()
+    }
+}
+
 /// A generic wrapper for all the server functions
 /// Authenticates the user and fetches user data
 async fn server_wrap<'a, Req, Res, Fut>(
@@ -111,11 +141,15 @@ where
     Req: GetAuthData + Debug,
     Fut: std::future::Future<Output = Result<tonic::Response<Res>, errors::ApiError>>,
 {
-    tracing::info!(?request);
-    let request = request.into_inner();
+    let user_id = request.get_ref().get_user_id();
+    let (_, extensions, request) = request.into_parts();
+    tracing::debug!(?request);
 
-    let user_id = request.get_user_id();
     let user = authenticate(state, user_id).await?;
+    // Only the users that exist have a bucket, the made up ids are limited by their ip address
+    rate_limit::check_user_rate(state.rate_limiter.as_deref(), &extensions, &user.user_id)?;
+    // Only the users that exist are recorded, the ids sent by the clients are not trusted
+    logging::record_user_id(&user.user_id);
     let result = func(state, user, request).await;
 
     match &result {
@@ -130,6 +164,7 @@ where
 impl grpc_server::Grpc for MyGrpc {
     type RoomServiceStream = CreateRoomStream;
     type GameServiceStream = GameServiceStream;
+    type GlobalStatsStream = GlobalStatsStream;
 
     async fn ping(
         &self,
@@ -148,10 +183,71 @@ impl grpc_server::Grpc for MyGrpc {
         .await
     }
 
+    async fn leave_room(
+        &self,
+        request: tonic::Request<LeaveRoomRequest>,
+    ) -> Result<tonic::Response<LeaveRoomResponse>, tonic::Status> {
+        server_wrap(self, request, |state, user, request| async {
+            functions::room_service::leave_room(state, user, request).await
+        })
+        .await
+    }
+
+    async fn send_room_chat(
+        &self,
+        request: tonic::Request<SendRoomChatRequest>,
+    ) -> Result<tonic::Response<SendRoomChatResponse>, tonic::Status> {
+        server_wrap(self, request, |state, user, request| async {
+            functions::room_chat::send_room_chat(state, user, request).await
+        })
+        .await
+    }
+
+    async fn list_rooms(
+        &self,
+        request: tonic::Request<ListRoomsRequest>,
+    ) -> Result<tonic::Response<ListRoomsResponse>, tonic::Status> {
+        server_wrap(self, request, |state, user, request| async {
+            functions::room_browser::list_rooms(state, user, request).await
+        })
+        .await
+    }
+
+    /// The first message of the stream is used to authenticate the user
     async fn game_service(
         &self,
-        _request: tonic::Request<tonic::Streaming<GameServiceRequest>>,
+        request: tonic::Request<tonic::Streaming<GameServiceRequest>>,
     ) -> Result<tonic::Response<Self::GameServiceStream>, tonic::Status> {
-        todo!()
+        let (_, extensions, mut request_stream) = request.into_parts();
+        let request = request_stream
+            .message()
+            .await?
+            .ok_or(errors::ApiError::BadRequest {
+                message: "The game stream was closed before the first message".to_string(),
+            })?;
+        tracing::debug!(?request);
+
+        let user = authenticate(self, request.client_id.clone()).await?;
+        rate_limit::check_user_rate(self.rate_limiter.as_deref(), &extensions, &user.user_id)?;
+        logging::record_user_id(&user.user_id);
+
+        let result =
+            functions::game_service::game_service(self, user, request, request_stream).await;
+
+        if let Err(error) = &result {
+            tracing::error!(?error);
+        }
+
+        Ok(result?)
+    }
+
+    async fn global_stats(
+        &self,
+        request: tonic::Request<GlobalStatsRequest>,
+    ) -> Result<tonic::Response<Self::GlobalStatsStream>, tonic::Status> {
+        server_wrap(self, request, |state, user, request| async {
+            functions::global_stats::global_stats(state, user, request).await
+        })
+        .await
     }
 }
@@ -1,8 +1,10 @@
 use std::{
     collections::HashMap,
-    sync::{Arc, Mutex},
+    sync::{atomic::AtomicBool, Arc, Mutex},
 };
 
+use tokio_util::sync::CancellationToken;
+
 use crate::app::server::{
     errors::DbError,
     grpc::{
@@ -23,6 +25,37 @@ pub struct Store {
     pub redis_client: RedisClient,
     pub room_users_state:
         SessionState<tokio::sync::mpsc::Sender<Result<RoomServiceResponse, tonic::Status>>>,
+    /// Set when the server is being drained, no new rooms or games are accepted
+    pub draining: Arc<AtomicBool>,
+    /// Cancelled when the server is shutting down, all the long lived streams must end
+    pub shutdown_token: CancellationToken,
+    /// Holds the number of sessions, updated whenever a session is added or removed
+    pub session_count: Arc<tokio::sync::watch::Sender<usize>>,
+    /// Number of global stats streams opened by every user on this instance, a user can have several streams
+    /// while reconnecting
+    pub global_stats_streams: SessionState<usize>,
+}
+
+impl Store {
+    pub fn new(redis_client: RedisClient) -> Self {
+        Self {
+            redis_client,
+            room_users_state: Arc::new(Mutex::new(HashMap::new())),
+            draining: Arc::new(AtomicBool::new(false)),
+            shutdown_token: CancellationToken::new(),
+            session_count: Arc::new(tokio::sync::watch::Sender::new(0)),
+            global_stats_streams: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    pub fn start_draining(&self) {
+        self.draining
+            .store(true, std::sync::atomic::Ordering::SeqCst)
+    }
 }
 
 impl StorageInterface for Store {}
@@ -1,9 +1,16 @@
 pub mod game;
+pub mod invite;
 pub mod room;
 pub mod session;
+pub mod stats;
 pub mod user;
 
 pub trait StorageInterface:
-    user::UserInterface + room::RoomInterface + session::SessionInterface + game::GameInterface
+    user::UserInterface
+    + room::RoomInterface
+    + session::SessionInterface
+    + game::GameInterface
+    + invite::InviteInterface
+    + stats::StatsInterface
 {
 }
@@ -1,18 +1,55 @@
-use crate::app::server::grpc::storage::{models, StorageResult, Store};
+use crate::app::server::grpc::{
+    storage::{models, StorageResult, Store},
+    types,
+};
 
 #[allow(async_fn_in_trait)]
 pub trait GameInterface {
     async fn insert_game(&self, game: models::Game) -> StorageResult<models::Game>;
     async fn find_game(&self, game_id: &str) -> StorageResult<models::Game>;
+    /// Update the game without losing the changes made by the other players at the same time
+    ///
+    /// The update can run several times and only has an effect when it returns `Some`
+    async fn update_game<T: Send>(
+        &self,
+        game_id: &str,
+        update: impl FnMut(&mut models::Game) -> Option<T> + Send,
+    ) -> StorageResult<(models::Game, Option<T>)>;
+    async fn delete_game(&self, game_id: &str) -> StorageResult<()>;
+    async fn get_all_game_ids(&self) -> StorageResult<Vec<String>>;
 }
 
 impl GameInterface for Store {
     async fn insert_game(&self, game: models::Game) -> StorageResult<models::Game> {
         let game_id = game.game_id.clone();
-        self.redis_client.serialize_and_set(game_id, game).await
+        self.redis_client
+            .add_to_set(types::GAMES_KEY, &game_id)
+            .await?;
+        self.redis_client.serialize_and_set(&game_id, game).await
     }
 
     async fn find_game(&self, game_id: &str) -> StorageResult<models::Game> {
         self.redis_client.get_and_deserialize(game_id).await
     }
+
+    async fn update_game<T: Send>(
+        &self,
+        game_id: &str,
+        update: impl FnMut(&mut models::Game) -> Option<T> + Send,
+    ) -> StorageResult<(models::Game, Option<T>)> {
+        self.redis_client
+            .update_and_serialize(game_id, update)
+            .await
+    }
+
+    async fn delete_game(&self, game_id: &str) -> StorageResult<()> {
+        self.redis_client
+            .remove_from_set(types::GAMES_KEY, game_id)
+            .await?;
+        self.redis_client.delete_key(game_id).await
+    }
+
+    async fn get_all_game_ids(&self) -> StorageResult<Vec<String>> {
+        self.redis_client.get_set_members(types::GAMES_KEY).await
+    }
 }
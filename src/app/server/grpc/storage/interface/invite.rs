@@ -0,0 +1,54 @@
+use crate::app::{
+    server::grpc::{
+        storage::{models, StorageResult, Store},
+        types,
+    },
+    utils,
+};
+
+#[allow(async_fn_in_trait)]
+pub trait InviteInterface {
+    /// Fails with `DuplicateValue` when the code is already used, the invite is removed by redis once it expires
+    async fn insert_invite(&self, invite: models::Invite) -> StorageResult<models::Invite>;
+    /// The invites that have just expired can still be found until redis removes them
+    async fn find_invite(&self, invite_code: &str) -> StorageResult<models::Invite>;
+    /// Remove the invite and return it, a single use code is only taken by one of the users joining at once
+    async fn take_invite(&self, invite_code: &str) -> StorageResult<models::Invite>;
+    async fn delete_invite(&self, invite_code: &str) -> StorageResult<()>;
+}
+
+fn get_invite_key(invite_code: &str) -> String {
+    format!("{}:{invite_code}", types::INVITE_KEY)
+}
+
+impl InviteInterface for Store {
+    async fn insert_invite(&self, invite: models::Invite) -> StorageResult<models::Invite> {
+        let expires_in = std::time::Duration::from_millis(
+            invite
+                .expires_at
+                .saturating_sub(utils::current_timestamp_millis()),
+        );
+
+        self.redis_client
+            .serialize_and_set_if_absent(&get_invite_key(&invite.invite_code), invite, expires_in)
+            .await
+    }
+
+    async fn find_invite(&self, invite_code: &str) -> StorageResult<models::Invite> {
+        self.redis_client
+            .get_and_deserialize(&get_invite_key(invite_code))
+            .await
+    }
+
+    async fn take_invite(&self, invite_code: &str) -> StorageResult<models::Invite> {
+        self.redis_client
+            .get_delete_and_deserialize(&get_invite_key(invite_code))
+            .await
+    }
+
+    async fn delete_invite(&self, invite_code: &str) -> StorageResult<()> {
+        self.redis_client
+            .delete_key(&get_invite_key(invite_code))
+            .await
+    }
+}
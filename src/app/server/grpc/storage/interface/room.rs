@@ -1,23 +1,186 @@
-use crate::app::server::grpc::storage::{models, StorageResult, Store};
+use crate::app::server::grpc::{
+    redis_client::Write,
+    storage::{interface::invite::InviteInterface, models, StorageResult, Store},
+    types,
+};
 
 #[allow(async_fn_in_trait)]
 pub trait RoomInterface {
     async fn insert_room(&self, room: models::Room) -> StorageResult<models::Room>;
     async fn find_room(&self, room_id: &str) -> StorageResult<models::Room>;
+    /// Update the room without losing the users that join or leave it at the same time, see `update_game`
+    async fn update_room<T: Send>(
+        &self,
+        room_id: &str,
+        update: impl FnMut(&mut models::Room) -> Option<T> + Send,
+    ) -> StorageResult<(models::Room, Option<T>)>;
     async fn delete_room(&self, room_id: &str) -> StorageResult<()>;
+    async fn get_all_room_ids(&self) -> StorageResult<Vec<String>>;
+    /// Number of rooms that have players waiting for the game to start
+    async fn get_waiting_rooms_count(&self) -> StorageResult<usize>;
+    /// Reserve the room for its host, unless the host already has `limit` rooms, returns whether it is reserved
+    ///
+    /// The rooms are counted and reserved at once, so the concurrent requests of a user cannot go over the limit
+    async fn reserve_hosted_room(
+        &self,
+        user_id: &str,
+        room_id: &str,
+        limit: usize,
+    ) -> StorageResult<bool>;
+    /// Release the reservation of a room that could not be created
+    async fn release_hosted_room(&self, user_id: &str, room_id: &str) -> StorageResult<()>;
+    /// The public rooms, including the ones that are full and about to start their game
+    async fn get_public_rooms(&self) -> StorageResult<Vec<models::Room>>;
+}
+
+fn get_hosted_rooms_key(user_id: &str) -> String {
+    format!("{}:{user_id}", types::HOSTED_ROOMS_KEY)
+}
+
+impl Store {
+    /// The waiting rooms are indexed, so that they can be counted without reading every room
+    async fn index_waiting_room(&self, room: &models::Room) -> StorageResult<()> {
+        if room.is_waiting() {
+            self.redis_client
+                .add_to_set(types::WAITING_ROOMS_KEY, &room.room_id)
+                .await
+        } else {
+            self.redis_client
+                .remove_from_set(types::WAITING_ROOMS_KEY, &room.room_id)
+                .await
+        }
+    }
 }
 
 impl RoomInterface for Store {
     async fn insert_room(&self, room: models::Room) -> StorageResult<models::Room> {
         let room_id = room.room_id.clone();
-        self.redis_client.serialize_and_set(room_id, room).await
+        let add_to_set = |key: &str| Write::AddToSet {
+            key: key.to_string(),
+            member: room_id.clone(),
+        };
+
+        // The room and its indexes are written at once, a room is never listed without being stored
+        let mut writes = vec![add_to_set(types::ROOMS_KEY)];
+
+        if let Some(host_id) = &room.host_id {
+            writes.push(add_to_set(&get_hosted_rooms_key(host_id)));
+        }
+
+        // An existing room that is written again leaves the waiting rooms once it is full
+        if room.is_waiting() {
+            writes.push(add_to_set(types::WAITING_ROOMS_KEY));
+        } else {
+            writes.push(Write::RemoveFromSet {
+                key: types::WAITING_ROOMS_KEY.to_string(),
+                member: room_id.clone(),
+            });
+        }
+
+        if room.is_public {
+            writes.push(add_to_set(types::PUBLIC_ROOMS_KEY));
+        }
+
+        writes.push(Write::Set {
+            key: room_id.clone(),
+            value: self.redis_client.serialize(&room)?,
+        });
+
+        self.redis_client.write_atomically(writes).await?;
+        Ok(room)
     }
 
     async fn find_room(&self, room_id: &str) -> StorageResult<models::Room> {
         self.redis_client.get_and_deserialize(room_id).await
     }
 
+    async fn update_room<T: Send>(
+        &self,
+        room_id: &str,
+        update: impl FnMut(&mut models::Room) -> Option<T> + Send,
+    ) -> StorageResult<(models::Room, Option<T>)> {
+        let (room, outcome) = self
+            .redis_client
+            .update_and_serialize(room_id, update)
+            .await?;
+
+        if outcome.is_some() {
+            self.index_waiting_room(&room).await?;
+        }
+
+        Ok((room, outcome))
+    }
+
     async fn delete_room(&self, room_id: &str) -> StorageResult<()> {
-        self.redis_client.delete_key(room_id).await
+        let room = match self.find_room(room_id).await {
+            Ok(room) => Some(room),
+            Err(error) if error.is_not_found() => None,
+            Err(error) => return Err(error),
+        };
+
+        // The invite code of the room cannot be used once the room is gone
+        if let Some(invite_code) = room.as_ref().and_then(|room| room.invite_code.as_ref()) {
+            self.delete_invite(invite_code).await?;
+        }
+
+        let remove_from_set = |key: &str| Write::RemoveFromSet {
+            key: key.to_string(),
+            member: room_id.to_string(),
+        };
+
+        let mut writes = vec![
+            remove_from_set(types::WAITING_ROOMS_KEY),
+            remove_from_set(types::PUBLIC_ROOMS_KEY),
+            remove_from_set(types::ROOMS_KEY),
+            Write::Delete {
+                key: room_id.to_string(),
+            },
+        ];
+
+        if let Some(host_id) = room.as_ref().and_then(|room| room.host_id.as_ref()) {
+            writes.push(remove_from_set(&get_hosted_rooms_key(host_id)));
+        }
+
+        self.redis_client.write_atomically(writes).await
+    }
+
+    async fn get_all_room_ids(&self) -> StorageResult<Vec<String>> {
+        self.redis_client.get_set_members(types::ROOMS_KEY).await
+    }
+
+    async fn get_waiting_rooms_count(&self) -> StorageResult<usize> {
+        self.redis_client
+            .get_set_size(types::WAITING_ROOMS_KEY)
+            .await
+    }
+
+    async fn reserve_hosted_room(
+        &self,
+        user_id: &str,
+        room_id: &str,
+        limit: usize,
+    ) -> StorageResult<bool> {
+        self.redis_client
+            .add_to_set_within_limit(&get_hosted_rooms_key(user_id), room_id, limit)
+            .await
+    }
+
+    async fn release_hosted_room(&self, user_id: &str, room_id: &str) -> StorageResult<()> {
+        self.redis_client
+            .remove_from_set(&get_hosted_rooms_key(user_id), room_id)
+            .await
+    }
+
+    async fn get_public_rooms(&self) -> StorageResult<Vec<models::Room>> {
+        let room_ids = self
+            .redis_client
+            .get_set_members(types::PUBLIC_ROOMS_KEY)
+            .await?;
+
+        if room_ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        self.redis_client.get_multiple_keys(room_ids).await
     }
 }
@@ -1,3 +1,4 @@
+use crate::app::server::errors::DbError;
 use crate::app::server::grpc::{
     server::RoomServiceResponse,
     storage::{StorageResult, Store},
@@ -13,43 +14,104 @@ type SessionChannel = tokio::sync::mpsc::Sender<Result<RoomServiceResponse, toni
 pub trait SessionInterface {
     fn insert_channel(&self, user_id: &str, channel: SessionChannel) -> StorageResult<()>;
     fn remove_channel(&self, user_id: &str) -> StorageResult<()>;
+    /// Whether the given channel is the one currently stored for the user
+    fn is_current_channel(&self, user_id: &str, channel: &SessionChannel) -> bool;
     fn send_message_to_user(
         &self,
         user_id: &str,
         message: RoomMessage,
     ) -> impl std::future::Future<Output = StorageResult<()>>;
+    /// End the session of the user with the given status
+    ///
+    /// The channel is removed by the session cleanup once the stream is closed
+    fn close_channel(
+        &self,
+        user_id: &str,
+        status: tonic::Status,
+    ) -> impl std::future::Future<Output = StorageResult<()>>;
+    fn get_session_count(&self) -> usize;
+    /// Receive the number of sessions every time it changes
+    fn watch_session_count(&self) -> tokio::sync::watch::Receiver<usize>;
+    fn get_connected_user_ids(&self) -> Vec<String>;
 }
 
 impl SessionInterface for Store {
     fn insert_channel(&self, user_id: &str, channel: SessionChannel) -> StorageResult<()> {
         let mut connected_users = self.room_users_state.lock().unwrap();
         connected_users.insert(user_id.to_string(), channel);
+        self.session_count.send_replace(connected_users.len());
         Ok(())
     }
 
     fn remove_channel(&self, user_id: &str) -> StorageResult<()> {
         let mut connected_users = self.room_users_state.lock().unwrap();
-        let user_channel = connected_users.remove(user_id).unwrap().clone();
+        let user_channel = connected_users.remove(user_id).ok_or(DbError::NotFound)?;
+        self.session_count.send_replace(connected_users.len());
         drop(user_channel);
         Ok(())
     }
 
+    fn is_current_channel(&self, user_id: &str, channel: &SessionChannel) -> bool {
+        self.room_users_state
+            .lock()
+            .unwrap()
+            .get(user_id)
+            .is_some_and(|user_channel| user_channel.same_channel(channel))
+    }
+
     fn send_message_to_user(
         &self,
         user_id: &str,
         message: RoomMessage,
     ) -> impl std::future::Future<Output = StorageResult<()>> {
         let grpc_response = RoomServiceResponse::from(message);
-        let user_channel = self.room_users_state.lock().unwrap();
-
-        let user_channel = user_channel
-            .get(user_id)
-            .expect("The user channel cannot be found")
-            .clone();
+        let user_channel = self.room_users_state.lock().unwrap().get(user_id).cloned();
 
         async move {
-            user_channel.send(Ok(grpc_response)).await.unwrap();
+            let user_channel = user_channel.ok_or(DbError::NotFound)?;
+
+            // The receiver is dropped when the client disconnects, the session cleanup removes the channel
+            if user_channel.send(Ok(grpc_response)).await.is_err() {
+                tracing::warn!("The session channel is already closed");
+            }
+
             Ok(())
         }
     }
+
+    fn close_channel(
+        &self,
+        user_id: &str,
+        status: tonic::Status,
+    ) -> impl std::future::Future<Output = StorageResult<()>> {
+        let user_channel = self.room_users_state.lock().unwrap().get(user_id).cloned();
+
+        async move {
+            match user_channel {
+                Some(user_channel) => {
+                    // The receiver might already be dropped if the client disconnected
+                    let _ = user_channel.send(Err(status)).await;
+                    Ok(())
+                }
+                None => Err(DbError::NotFound),
+            }
+        }
+    }
+
+    fn get_session_count(&self) -> usize {
+        self.room_users_state.lock().unwrap().len()
+    }
+
+    fn watch_session_count(&self) -> tokio::sync::watch::Receiver<usize> {
+        self.session_count.subscribe()
+    }
+
+    fn get_connected_user_ids(&self) -> Vec<String> {
+        self.room_users_state
+            .lock()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect()
+    }
 }
@@ -0,0 +1,121 @@
+use crate::app::{
+    server::grpc::{
+        storage::{models, StorageResult, Store},
+        types,
+    },
+    utils,
+};
+
+/// Track the players and games that are currently active across all the server instances
+///
+/// The data is kept in redis sorted sets so that every instance sees the same numbers, the entries expire
+/// unless they are renewed by the instance that added them
+#[allow(async_fn_in_trait)]
+pub trait StatsInterface {
+    /// Add the player, or renew the player if it is already active
+    async fn add_active_player(&self, user_id: &str) -> StorageResult<()>;
+    async fn remove_active_player(&self, user_id: &str) -> StorageResult<()>;
+    async fn is_player_active(&self, user_id: &str) -> StorageResult<bool>;
+    /// Add the game, or renew the game if it is already active
+    async fn add_active_game(&self, game_id: &str) -> StorageResult<()>;
+    async fn remove_active_game(&self, game_id: &str) -> StorageResult<()>;
+    async fn is_game_active(&self, game_id: &str) -> StorageResult<bool>;
+    /// Count the active players and games, the expired entries are removed
+    async fn get_global_stats(&self) -> StorageResult<models::GlobalStats>;
+    /// Count a global stats stream of the user on this instance, returns the number of streams of the user
+    fn open_global_stats_stream(&self, user_id: &str) -> usize;
+    /// Returns the number of streams of the user that are still open on this instance
+    fn close_global_stats_stream(&self, user_id: &str) -> usize;
+}
+
+fn get_expiry_score() -> f64 {
+    (utils::current_timestamp_millis() + types::ACTIVE_ENTRY_LIFETIME.as_millis() as u64) as f64
+}
+
+fn get_current_score() -> f64 {
+    utils::current_timestamp_millis() as f64
+}
+
+impl Store {
+    async fn is_active(&self, key: &str, member: &str) -> StorageResult<bool> {
+        let expiry_score = self.redis_client.get_sorted_set_score(key, member).await?;
+        Ok(expiry_score.is_some_and(|expiry_score| expiry_score >= get_current_score()))
+    }
+
+    async fn count_active(&self, key: &str) -> StorageResult<usize> {
+        let current_score = get_current_score();
+        self.redis_client
+            .remove_sorted_set_below(key, current_score)
+            .await?;
+        self.redis_client
+            .count_sorted_set_from(key, current_score)
+            .await
+    }
+}
+
+impl StatsInterface for Store {
+    async fn add_active_player(&self, user_id: &str) -> StorageResult<()> {
+        self.redis_client
+            .add_to_sorted_set(types::ACTIVE_PLAYERS_KEY, user_id, get_expiry_score())
+            .await
+    }
+
+    async fn remove_active_player(&self, user_id: &str) -> StorageResult<()> {
+        self.redis_client
+            .remove_from_sorted_set(types::ACTIVE_PLAYERS_KEY, user_id)
+            .await
+    }
+
+    async fn is_player_active(&self, user_id: &str) -> StorageResult<bool> {
+        self.is_active(types::ACTIVE_PLAYERS_KEY, user_id).await
+    }
+
+    async fn add_active_game(&self, game_id: &str) -> StorageResult<()> {
+        self.redis_client
+            .add_to_sorted_set(types::ACTIVE_GAMES_KEY, game_id, get_expiry_score())
+            .await
+    }
+
+    async fn remove_active_game(&self, game_id: &str) -> StorageResult<()> {
+        self.redis_client
+            .remove_from_sorted_set(types::ACTIVE_GAMES_KEY, game_id)
+            .await
+    }
+
+    async fn is_game_active(&self, game_id: &str) -> StorageResult<bool> {
+        self.is_active(types::ACTIVE_GAMES_KEY, game_id).await
+    }
+
+    async fn get_global_stats(&self) -> StorageResult<models::GlobalStats> {
+        let active_players = self.count_active(types::ACTIVE_PLAYERS_KEY).await?;
+        let active_games = self.count_active(types::ACTIVE_GAMES_KEY).await?;
+
+        Ok(models::GlobalStats {
+            active_players,
+            active_games,
+        })
+    }
+
+    fn open_global_stats_stream(&self, user_id: &str) -> usize {
+        let mut global_stats_streams = self.global_stats_streams.lock().unwrap();
+        let stream_count = global_stats_streams.entry(user_id.to_string()).or_default();
+        *stream_count += 1;
+        *stream_count
+    }
+
+    fn close_global_stats_stream(&self, user_id: &str) -> usize {
+        let mut global_stats_streams = self.global_stats_streams.lock().unwrap();
+        let Some(stream_count) = global_stats_streams.get_mut(user_id) else {
+            return 0;
+        };
+
+        *stream_count = stream_count.saturating_sub(1);
+        let stream_count = *stream_count;
+
+        if stream_count == 0 {
+            global_stats_streams.remove(user_id);
+        }
+
+        stream_count
+    }
+}
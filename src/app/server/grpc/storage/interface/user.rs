@@ -1,16 +1,30 @@
-use crate::app::server::grpc::storage::{models, StorageResult, Store};
+use crate::app::server::grpc::{
+    storage::{models, StorageResult, Store},
+    types,
+};
 
 #[allow(async_fn_in_trait)]
 pub trait UserInterface {
     async fn insert_user(&self, user: models::User) -> StorageResult<models::User>;
     async fn find_user(&self, user_id: &str) -> StorageResult<models::User>;
     async fn get_multiple_users(&self, user_ids: Vec<String>) -> StorageResult<Vec<models::User>>;
+    /// Update the user without losing the changes made at the same time, see `update_game`
+    async fn update_user<T: Send>(
+        &self,
+        user_id: &str,
+        update: impl FnMut(&mut models::User) -> Option<T> + Send,
+    ) -> StorageResult<(models::User, Option<T>)>;
+    async fn delete_user(&self, user_id: &str) -> StorageResult<()>;
+    async fn get_all_user_ids(&self) -> StorageResult<Vec<String>>;
 }
 
 impl UserInterface for Store {
     async fn insert_user(&self, user: models::User) -> StorageResult<models::User> {
         let user_id = user.user_id.clone();
-        self.redis_client.serialize_and_set(user_id, user).await
+        self.redis_client
+            .add_to_set(types::USERS_KEY, &user_id)
+            .await?;
+        self.redis_client.serialize_and_set(&user_id, user).await
     }
 
     async fn find_user(&self, user_id: &str) -> StorageResult<models::User> {
@@ -20,4 +34,25 @@ impl UserInterface for Store {
     async fn get_multiple_users(&self, user_ids: Vec<String>) -> StorageResult<Vec<models::User>> {
         self.redis_client.get_multiple_keys(user_ids).await
     }
+
+    async fn update_user<T: Send>(
+        &self,
+        user_id: &str,
+        update: impl FnMut(&mut models::User) -> Option<T> + Send,
+    ) -> StorageResult<(models::User, Option<T>)> {
+        self.redis_client
+            .update_and_serialize(user_id, update)
+            .await
+    }
+
+    async fn delete_user(&self, user_id: &str) -> StorageResult<()> {
+        self.redis_client
+            .remove_from_set(types::USERS_KEY, user_id)
+            .await?;
+        self.redis_client.delete_key(user_id).await
+    }
+
+    async fn get_all_user_ids(&self) -> StorageResult<Vec<String>> {
+        self.redis_client.get_set_members(types::USERS_KEY).await
+    }
 }
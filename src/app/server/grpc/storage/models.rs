@@ -1,4 +1,6 @@
-use crate::app::utils;
+use std::collections::HashMap;
+
+use crate::app::{server::grpc::types, utils};
 
 #[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
 pub struct User {
@@ -10,19 +12,101 @@ pub struct User {
     pub game_id: Option<String>,
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Copy, Clone)]
+#[derive(serde::Deserialize, serde::Serialize, Copy, Clone, Debug)]
 pub enum GameStatus {
     Init,
     InProgress,
     End,
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[derive(serde::Deserialize, serde::Serialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PlayerStatus {
+    Playing,
+    /// Time at which the player was disconnected, in milliseconds since unix epoch
+    Disconnected {
+        since: u64,
+    },
+    Finished,
+    DidNotFinish,
+}
+
+/// Timing of the progress reported by a player, which is checked by the anti-cheat
+#[derive(serde::Deserialize, serde::Serialize, Copy, Clone, Debug, Default)]
+pub struct TypingTiming {
+    /// Time of the first progress reported by the player, in milliseconds since unix epoch
+    pub started_at: Option<u64>,
+    /// Progress of the player when the first progress was reported, which is not zero for a resumed game
+    pub start_progress: usize,
+    /// Time of the last progress that was accepted, in milliseconds since unix epoch
+    pub last_update_at: Option<u64>,
+    /// Intervals between the keystrokes, in milliseconds
+    pub keystroke_count: u32,
+    pub keystroke_interval_sum: f64,
+    pub keystroke_interval_sum_of_squares: f64,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Copy, Clone, Debug)]
+pub struct PlayerProgress {
+    /// Number of characters of the prompt typed by the player
+    pub progress: usize,
+    pub status: PlayerStatus,
+    #[serde(default)]
+    pub timing: TypingTiming,
+    /// The result of the player does not count for the ranking once the progress failed a check
+    #[serde(default)]
+    pub unranked: bool,
+    /// Incremented every time the player opens a game stream, only the latest stream can disconnect the player
+    #[serde(default)]
+    pub stream_generation: u32,
+}
+
+impl Default for PlayerProgress {
+    fn default() -> Self {
+        Self {
+            progress: 0,
+            status: PlayerStatus::Playing,
+            timing: TypingTiming::default(),
+            unranked: false,
+            stream_generation: 0,
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CheatFlagKind {
+    /// The progress is longer than the prompt
+    ProgressBeyondPrompt,
+    /// Too many characters were typed since the previous update
+    ImpossibleBurst,
+    /// The characters were typed faster than anyone can type since the start of the game
+    ImpossibleKeystrokeRate,
+    /// The intervals between the keystrokes are too regular to be typed by a person
+    SuspiciousKeystrokeTiming,
+}
+
+/// A failed check of the progress of a player, kept on the game so that it can be reviewed
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct CheatFlag {
+    pub user_id: String,
+    pub kind: CheatFlagKind,
+    /// Progress reported by the player when the check failed
+    pub progress: usize,
+    /// Time at which the check failed, in milliseconds since unix epoch
+    pub flagged_at: u64,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
 pub struct Game {
     pub game_id: String,
     pub users_in_game: Vec<String>,
     pub game_status: GameStatus,
     pub prompt: String,
+    /// Progress of every player, this is the source of truth when a player resumes the game
+    #[serde(default)]
+    pub players: HashMap<String, PlayerProgress>,
+    /// Only the first failure of every check is kept for every player
+    #[serde(default)]
+    pub cheat_flags: Vec<CheatFlag>,
 }
 
 impl Game {
@@ -34,7 +118,94 @@ impl Game {
             users_in_game: users.iter().map(|user| user.user_id.clone()).collect(),
             game_status: GameStatus::Init,
             prompt,
+            players: users
+                .iter()
+                .map(|user| (user.user_id.clone(), PlayerProgress::default()))
+                .collect(),
+            cheat_flags: vec![],
+        }
+    }
+
+    pub fn is_in_progress(&self) -> bool {
+        !matches!(self.game_status, GameStatus::End)
+    }
+
+    /// The game is over once every player has either finished or dropped out
+    pub fn is_over(&self) -> bool {
+        self.users_in_game.iter().all(|user_id| {
+            matches!(
+                self.get_player(user_id).status,
+                PlayerStatus::Finished | PlayerStatus::DidNotFinish
+            )
+        })
+    }
+
+    pub fn get_player(&self, user_id: &str) -> PlayerProgress {
+        self.players.get(user_id).copied().unwrap_or_default()
+    }
+
+    pub fn set_player_status(&mut self, user_id: &str, status: PlayerStatus) {
+        self.players.entry(user_id.to_string()).or_default().status = status;
+    }
+
+    /// A new game stream of the player replaces the previous one, returns the generation of the new stream
+    pub fn open_player_stream(&mut self, user_id: &str) -> u32 {
+        let player = self.players.entry(user_id.to_string()).or_default();
+        player.stream_generation = player.stream_generation.wrapping_add(1);
+        player.stream_generation
+    }
+
+    pub fn set_player_timing(&mut self, user_id: &str, timing: TypingTiming) {
+        self.players.entry(user_id.to_string()).or_default().timing = timing;
+    }
+
+    /// Record a failed check of the progress of the player, whose result is no longer ranked
+    pub fn flag_player(&mut self, user_id: &str, kind: CheatFlagKind, progress: usize) {
+        self.players
+            .entry(user_id.to_string())
+            .or_default()
+            .unranked = true;
+
+        let is_flagged = self
+            .cheat_flags
+            .iter()
+            .any(|flag| flag.user_id == user_id && flag.kind == kind);
+
+        if !is_flagged {
+            self.cheat_flags.push(CheatFlag {
+                user_id: user_id.to_string(),
+                kind,
+                progress,
+                flagged_at: utils::current_timestamp_millis(),
+            });
+        }
+    }
+
+    /// Update the progress of a playing player, the progress can only move forward
+    ///
+    /// Returns true if the player has just finished the prompt
+    pub fn update_progress(&mut self, user_id: &str, progress: usize) -> bool {
+        let prompt_length = self.prompt.chars().count();
+        let player = self.players.entry(user_id.to_string()).or_default();
+
+        if player.status != PlayerStatus::Playing {
+            return false;
         }
+
+        player.progress = player.progress.max(progress.min(prompt_length));
+
+        if player.progress == prompt_length {
+            player.status = PlayerStatus::Finished;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl Default for User {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -55,13 +226,40 @@ impl User {
     pub fn assign_room_id(&mut self, room_id: String) {
         self.room_id = Some(room_id)
     }
+
+    pub fn assign_game_id(&mut self, game_id: String) {
+        self.game_id = Some(game_id)
+    }
+
+    pub fn clear_room_id(&mut self) {
+        self.room_id = None
+    }
+
+    pub fn clear_game_id(&mut self) {
+        self.game_id = None
+    }
 }
 
-#[derive(Clone, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 pub struct Room {
     pub room_id: String,
     pub room_size: u8,
     pub users: Vec<String>,
+    /// Time at which each user joined the room, in milliseconds since unix epoch
+    #[serde(default)]
+    pub joined_at: HashMap<String, u64>,
+    /// The user who created the room, the common room has no host
+    #[serde(default)]
+    pub host_id: Option<String>,
+    /// Code that the other users can join the room with, removed once a single use code is used
+    #[serde(default)]
+    pub invite_code: Option<String>,
+    /// The room is listed in the room browser while it is waiting for players
+    #[serde(default)]
+    pub is_public: bool,
+    /// Time at which the room was created, in milliseconds since unix epoch
+    #[serde(default)]
+    pub created_at: u64,
 }
 
 impl Room {
@@ -70,14 +268,30 @@ impl Room {
             room_id,
             room_size,
             users: vec![],
+            joined_at: HashMap::new(),
+            host_id: None,
+            invite_code: None,
+            is_public: false,
+            created_at: utils::current_timestamp_millis(),
         }
     }
 
+    pub fn is_host(&self, user_id: &str) -> bool {
+        self.host_id.as_deref() == Some(user_id)
+    }
+
     pub fn add_user(&mut self, user_id: String) -> usize {
+        self.joined_at
+            .insert(user_id.clone(), utils::current_timestamp_millis());
         self.users.push(user_id);
         self.users.len()
     }
 
+    /// The room has players waiting for more players to join
+    pub fn is_waiting(&self) -> bool {
+        !self.users.is_empty() && self.users.len() < usize::from(self.room_size)
+    }
+
     pub fn remove_user(&mut self, user_id_to_be_removed: String) -> usize {
         let position = self
             .users
@@ -88,6 +302,47 @@ impl Room {
             self.users.remove(index);
         }
 
+        self.joined_at.remove(&user_id_to_be_removed);
+
         self.users.len()
     }
 }
+
+/// A short code to join a room, which is shared by the host of the room
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Invite {
+    pub invite_code: String,
+    pub room_id: String,
+    /// Time after which the code can no longer be used, in milliseconds since unix epoch
+    pub expires_at: u64,
+    /// The code can only be used by one user
+    pub is_single_use: bool,
+    /// The user who has joined the room with a single use code, who can still rejoin the room with it
+    #[serde(default)]
+    pub used_by: Option<String>,
+}
+
+impl Invite {
+    pub fn new(invite_code: String, room_id: String, is_single_use: bool) -> Self {
+        let lifetime = types::INVITE_LIFETIME.as_millis() as u64;
+
+        Self {
+            invite_code,
+            room_id,
+            expires_at: utils::current_timestamp_millis() + lifetime,
+            is_single_use,
+            used_by: None,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        utils::current_timestamp_millis() >= self.expires_at
+    }
+}
+
+/// Statistics about the players and games across all the server instances
+#[derive(Clone, Copy, Debug)]
+pub struct GlobalStats {
+    pub active_players: usize,
+    pub active_games: usize,
+}
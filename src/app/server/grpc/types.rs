@@ -1,14 +1,50 @@
-use crate::app::{server::grpc::server::RoomServiceResponse, types::RoomServiceResponseType};
+use crate::app::{
+    server::grpc::server::{ChatMessage, RoomServiceResponse},
+    types::RoomServiceResponseType,
+};
 
 use super::storage::models;
 
 pub const COMMON_ROOM_KEY: &str = "COMMON_ROOM";
 pub const COMMON_ROOM_SIZE: u8 = 2;
 
+/// Redis sets that index all the stored entities, so that they can be listed
+pub const USERS_KEY: &str = "USERS";
+pub const ROOMS_KEY: &str = "ROOMS";
+pub const GAMES_KEY: &str = "GAMES";
+
+/// Prefix of the redis sets of the rooms created by each user, that have not started their game yet
+pub const HOSTED_ROOMS_KEY: &str = "HOSTED_ROOMS";
+/// Redis set of the rooms that have players waiting for the game to start
+pub const WAITING_ROOMS_KEY: &str = "WAITING_ROOMS";
+/// Redis set of the rooms that are listed in the room browser
+pub const PUBLIC_ROOMS_KEY: &str = "PUBLIC_ROOMS";
+/// Prefix of the keys of the invite codes of the rooms
+pub const INVITE_KEY: &str = "INVITE";
+/// Time for which an invite code can be used, the code is also removed along with its room
+pub const INVITE_LIFETIME: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+/// Redis channel on which the chat messages are published, so that every server can deliver them
+pub const ROOM_CHAT_CHANNEL: &str = "ROOM_CHAT";
+/// Redis sorted set of users who currently have an open global stats stream
+///
+/// The members are scored by the time at which they expire, so that the members of a crashed instance do not stay
+pub const ACTIVE_PLAYERS_KEY: &str = "ACTIVE_PLAYERS";
+/// Redis sorted set of games that have at least one player still connected, scored like the active players
+pub const ACTIVE_GAMES_KEY: &str = "ACTIVE_GAMES";
+/// How often the global stats are sent to the connected clients, the active players and games are renewed as well
+pub const GLOBAL_STATS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+/// Time for which an active player or game is counted without being renewed
+pub const ACTIVE_ENTRY_LIFETIME: std::time::Duration = std::time::Duration::from_secs(15);
+/// Time given to a disconnected player to resume the game, the player does not finish the game otherwise
+pub const GAME_RESUME_WINDOW: std::time::Duration = std::time::Duration::from_secs(60);
+/// How often the connectivity to redis is checked to report the readiness of the server
+pub const HEALTH_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
 /// Message that can be sent between the client session channels
 pub enum RoomMessage {
     RoomCreated {
         room_id: String,
+        invite_code: Option<String>,
         users: Vec<models::User>,
     },
     RoomJoined {
@@ -23,6 +59,40 @@ pub enum RoomMessage {
         room_id: String,
         users: Vec<models::User>,
     },
+    /// A user has left the room, the remaining users are sent
+    UserLeft {
+        room_id: String,
+        users: Vec<models::User>,
+    },
+    ServerShutdown,
+    /// A chat message of a user of the room, which is sent to every user of the room including the sender
+    Chat {
+        room_id: String,
+        chat_message: ChatMessage,
+    },
+}
+
+/// A chat message published to every server, each server sends it to the users of the room connected to it
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct PublishedChat {
+    pub room_id: String,
+    /// The users of the room when the message was sent
+    pub user_ids: Vec<String>,
+    pub user_id: String,
+    pub user_name: String,
+    pub message: String,
+    pub sent_at: u64,
+}
+
+impl From<PublishedChat> for ChatMessage {
+    fn from(value: PublishedChat) -> Self {
+        ChatMessage {
+            user_id: value.user_id,
+            user_name: value.user_name,
+            message: value.message,
+            sent_at: value.sent_at,
+        }
+    }
 }
 
 impl From<RoomMessage> for RoomServiceResponse {
@@ -32,21 +102,57 @@ impl From<RoomMessage> for RoomServiceResponse {
                 room_id,
                 message_type: RoomServiceResponseType::GameStart.to_u8().into(),
                 user_details: users.into_iter().map(From::from).collect::<Vec<_>>(),
+                chat_message: None,
+                invite_code: None,
             },
-            RoomMessage::RoomCreated { room_id, users } => RoomServiceResponse {
+            RoomMessage::RoomCreated {
+                room_id,
+                invite_code,
+                users,
+            } => RoomServiceResponse {
                 room_id,
                 message_type: RoomServiceResponseType::Init.to_u8().into(),
                 user_details: users.into_iter().map(From::from).collect::<Vec<_>>(),
+                chat_message: None,
+                invite_code,
             },
             RoomMessage::RoomJoined { room_id, users } => RoomServiceResponse {
                 room_id,
                 message_type: RoomServiceResponseType::Init.to_u8().into(),
                 user_details: users.into_iter().map(From::from).collect::<Vec<_>>(),
+                chat_message: None,
+                invite_code: None,
             },
             RoomMessage::UserJoined { room_id, users } => RoomServiceResponse {
                 room_id,
                 message_type: RoomServiceResponseType::UserJoined.to_u8().into(),
                 user_details: users.into_iter().map(From::from).collect::<Vec<_>>(),
+                chat_message: None,
+                invite_code: None,
+            },
+            RoomMessage::UserLeft { room_id, users } => RoomServiceResponse {
+                room_id,
+                message_type: RoomServiceResponseType::UserLeft.to_u8().into(),
+                user_details: users.into_iter().map(From::from).collect::<Vec<_>>(),
+                chat_message: None,
+                invite_code: None,
+            },
+            RoomMessage::ServerShutdown => RoomServiceResponse {
+                room_id: String::new(),
+                message_type: RoomServiceResponseType::ServerShutdown.to_u8().into(),
+                user_details: vec![],
+                chat_message: None,
+                invite_code: None,
+            },
+            RoomMessage::Chat {
+                room_id,
+                chat_message,
+            } => RoomServiceResponse {
+                room_id,
+                message_type: RoomServiceResponseType::Chat.to_u8().into(),
+                user_details: vec![],
+                chat_message: Some(chat_message),
+                invite_code: None,
             },
         }
     }
@@ -1,7 +1,7 @@
 use fred::interfaces::ClientLike;
 
 use super::redis_client::RedisClient;
-use crate::app::types::RedisConfig;
+use crate::app::types::{RedisConfig, TlsConfig};
 
 pub async fn create_redis_client(
     redis_config: RedisConfig,
@@ -23,8 +23,100 @@ pub async fn create_redis_client(
     // connect to the server, returning a handle to a task that drives the connection
     client.connect();
 
-    // wait for the client to connect
-    let _ = client.wait_for_connect().await;
+    // wait for the client to connect, the server cannot serve any request without redis
+    client.wait_for_connect().await?;
 
     Ok(RedisClient::new(client))
 }
+
+/// Read the certificates of the server, along with the CA of the clients when mTLS is enabled
+pub async fn get_server_tls_config(
+    tls_config: &TlsConfig,
+) -> std::io::Result<tonic::transport::ServerTlsConfig> {
+    let certificate = tokio::fs::read(&tls_config.certificate_path).await?;
+    let key = tokio::fs::read(&tls_config.key_path).await?;
+
+    let mut server_tls_config = tonic::transport::ServerTlsConfig::new()
+        .identity(tonic::transport::Identity::from_pem(certificate, key));
+
+    if let Some(client_ca_path) = &tls_config.client_ca_path {
+        let client_ca = tokio::fs::read(client_ca_path).await?;
+        server_tls_config =
+            server_tls_config.client_ca_root(tonic::transport::Certificate::from_pem(client_ca));
+    }
+
+    Ok(server_tls_config)
+}
+
+/// Get the status code of a grpc response from the http headers
+///
+/// Errors returned by the handlers are sent in the headers ( trailers only response ),
+/// a response without the status header has been started successfully
+pub fn get_grpc_code(headers: &tonic::codegen::http::HeaderMap) -> tonic::Code {
+    headers
+        .get("grpc-status")
+        .and_then(|status| status.to_str().ok())
+        .and_then(|status| status.parse::<i32>().ok())
+        .map(tonic::Code::from)
+        .unwrap_or(tonic::Code::Ok)
+}
+
+/// Label of the rpcs that are not served by the server
+pub const UNKNOWN_RPC: &str = "unknown";
+
+/// Paths of all the rpcs served by the server
+const KNOWN_RPC_PATHS: &[&str] = &[
+    "/server.Grpc/Ping",
+    "/server.Grpc/RoomService",
+    "/server.Grpc/LeaveRoom",
+    "/server.Grpc/SendRoomChat",
+    "/server.Grpc/ListRooms",
+    "/server.Grpc/GameService",
+    "/server.Grpc/GlobalStats",
+    "/server.Admin/ListRooms",
+    "/server.Admin/GetRoom",
+    "/server.Admin/DeleteRoom",
+    "/server.Admin/ListGames",
+    "/server.Admin/GetGame",
+    "/server.Admin/DeleteGame",
+    "/server.Admin/ListUsers",
+    "/server.Admin/GetUser",
+    "/server.Admin/DeleteUser",
+    "/server.Admin/KickSession",
+    "/server.Admin/Drain",
+    "/grpc.health.v1.Health/Check",
+    "/grpc.health.v1.Health/Watch",
+    "/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo",
+];
+
+/// Get the path of the rpc that is called, `UNKNOWN_RPC` for the paths that are not served
+///
+/// The paths are sent by the clients, so only the known ones can be used as labels or keys
+pub fn get_rpc_path(path: &str) -> &'static str {
+    KNOWN_RPC_PATHS
+        .iter()
+        .find(|known_path| **known_path == path)
+        .copied()
+        .unwrap_or(UNKNOWN_RPC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_rpcs_keep_their_path() {
+        assert_eq!(get_rpc_path("/server.Grpc/Ping"), "/server.Grpc/Ping");
+        assert_eq!(
+            get_rpc_path("/grpc.health.v1.Health/Check"),
+            "/grpc.health.v1.Health/Check"
+        );
+    }
+
+    #[test]
+    fn other_paths_are_unknown() {
+        assert_eq!(get_rpc_path("/server.Grpc/Random"), UNKNOWN_RPC);
+        assert_eq!(get_rpc_path("/server.Grpc/Ping/extra"), UNKNOWN_RPC);
+        assert_eq!(get_rpc_path(""), UNKNOWN_RPC);
+    }
+}
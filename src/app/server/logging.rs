@@ -0,0 +1,224 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tonic::codegen::{http, Body};
+use tracing::Instrument;
+use tracing_subscriber::EnvFilter;
+
+use crate::app::{
+    server::grpc::utils::get_grpc_code,
+    types::{LogFormat, LoggingConfig, REQUEST_ID_METADATA_KEY},
+    utils,
+};
+
+/// Install the global tracing subscriber based on the logging config
+pub fn init_tracing(logging_config: &LoggingConfig) {
+    let env_filter = EnvFilter::try_new(&logging_config.filter).unwrap_or_else(|error| {
+        eprintln!("Invalid log filter {}: {error}", logging_config.filter);
+        EnvFilter::new("info")
+    });
+
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(env_filter)
+        .with_file(true)
+        .with_line_number(true);
+
+    match logging_config.format {
+        LogFormat::Pretty => subscriber.init(),
+        LogFormat::Json => subscriber
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .init(),
+    }
+}
+
+/// Longest request id accepted from a client, longer ids are replaced by a generated one
+const MAX_REQUEST_ID_LENGTH: usize = 64;
+
+/// The request id sent by the client is only used if it is short and made of the characters of an id
+///
+/// The request id ends up in every log line of the call, so it must not be used to flood or forge the logs
+fn is_valid_request_id(request_id: &str) -> bool {
+    !request_id.is_empty()
+        && request_id.len() <= MAX_REQUEST_ID_LENGTH
+        && request_id
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || "-_.:".contains(character))
+}
+
+/// Spawn a task that stays in the span of the current rpc, so that the logs of the task carry the ids of the rpc
+pub fn spawn_in_current_span<F>(future: F) -> tokio::task::JoinHandle<F::Output>
+where
+    F: std::future::Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tokio::spawn(future.in_current_span())
+}
+
+/// Record the identifiers of the entities involved in the rpc on the span of the current call
+pub fn record_user_id(user_id: &str) {
+    tracing::Span::current().record("user_id", user_id);
+}
+
+pub fn record_room_id(room_id: &str) {
+    tracing::Span::current().record("room_id", room_id);
+}
+
+pub fn record_game_id(game_id: &str) {
+    tracing::Span::current().record("game_id", game_id);
+}
+
+/// Opens a span for every rpc call
+///
+/// The request id is taken from the request metadata, or generated if the client did not send a valid one
+/// and is sent back in the response metadata
+///
+/// The call is logged once the response body ends, so that the latency of the streams covers the whole stream
+#[derive(Clone, Default)]
+pub struct RequestTracingLayer;
+
+impl<S> tower::Layer<S> for RequestTracingLayer {
+    type Service = RequestTracingService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestTracingService { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct RequestTracingService<S> {
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> tower::Service<http::Request<ReqBody>> for RequestTracingService<S>
+where
+    S: tower::Service<http::Request<ReqBody>, Response = http::Response<ResBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<TracedBody<ResBody>>;
+    type Error = S::Error;
+    type Future =
+        Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<ReqBody>) -> Self::Future {
+        let request_id = request
+            .headers()
+            .get(REQUEST_ID_METADATA_KEY)
+            .and_then(|request_id| request_id.to_str().ok())
+            .filter(|request_id| is_valid_request_id(request_id))
+            .map(ToString::to_string)
+            .unwrap_or_else(|| utils::generate_time_ordered_id("req"));
+
+        let span = tracing::info_span!(
+            "rpc",
+            method = request.uri().path(),
+            request_id = request_id.as_str(),
+            user_id = tracing::field::Empty,
+            room_id = tracing::field::Empty,
+            game_id = tracing::field::Empty,
+        );
+
+        let start_time = std::time::Instant::now();
+        let response_future = {
+            let _entered = span.enter();
+            self.inner.call(request)
+        };
+
+        let response_span = span.clone();
+        Box::pin(
+            async move {
+                let mut response = response_future.await?;
+                let code = get_grpc_code(response.headers());
+
+                if let Ok(request_id) = http::HeaderValue::from_str(&request_id) {
+                    response
+                        .headers_mut()
+                        .insert(REQUEST_ID_METADATA_KEY, request_id);
+                }
+
+                Ok(response.map(|body| TracedBody {
+                    inner: body,
+                    span: response_span,
+                    start_time,
+                    code,
+                }))
+            }
+            .instrument(span),
+        )
+    }
+}
+
+/// Body of a response, which logs the call once the body ends or is dropped by a client that went away
+pub struct TracedBody<B> {
+    inner: B,
+    span: tracing::Span,
+    start_time: std::time::Instant,
+    /// Status of the call, taken from the headers and then from the trailers of the response
+    code: tonic::Code,
+}
+
+impl<B: Body + Unpin> Body for TracedBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        Pin::new(&mut self.inner).poll_data(cx)
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+        let poll = Pin::new(&mut self.inner).poll_trailers(cx);
+
+        if let Poll::Ready(Ok(Some(trailers))) = &poll {
+            self.code = get_grpc_code(trailers);
+        }
+
+        poll
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+}
+
+impl<B> Drop for TracedBody<B> {
+    fn drop(&mut self) {
+        let latency_ms = self.start_time.elapsed().as_millis() as u64;
+        let code = self.code;
+
+        self.span
+            .in_scope(|| tracing::info!(latency_ms, ?code, "rpc completed"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_ids_made_of_id_characters_are_valid() {
+        assert!(is_valid_request_id("req_0190a1b2-c3d4.e5:f6"));
+        assert!(is_valid_request_id(&"a".repeat(MAX_REQUEST_ID_LENGTH)));
+    }
+
+    #[test]
+    fn other_request_ids_are_invalid() {
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id(&"a".repeat(MAX_REQUEST_ID_LENGTH + 1)));
+        assert!(!is_valid_request_id("req with spaces"));
+        assert!(!is_valid_request_id("req\nforged log line"));
+        assert!(!is_valid_request_id("req_é"));
+    }
+}
@@ -0,0 +1,270 @@
+use std::{
+    convert::Infallible,
+    future::Future,
+    pin::Pin,
+    sync::LazyLock,
+    task::{Context, Poll},
+};
+
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts,
+    Registry, TextEncoder,
+};
+use tonic::codegen::http;
+
+use crate::app::server::grpc::{
+    storage::{
+        interface::{room::RoomInterface, session::SessionInterface},
+        Store,
+    },
+    utils::{get_grpc_code, get_rpc_path},
+};
+
+/// Longest time taken by a scrape, the scrape is aborted if redis does not respond in time
+const SCRAPE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// All the metrics exported by the server
+pub struct Metrics {
+    registry: Registry,
+    pub rpc_requests: IntCounterVec,
+    pub active_sessions: IntGauge,
+    pub rooms_waiting: IntGauge,
+    pub games_started: IntCounter,
+    pub games_finished: IntCounter,
+    pub redis_command_duration: HistogramVec,
+    pub matchmaking_wait: Histogram,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("blazer".to_string()), None)
+            .expect("Invalid metrics namespace");
+
+        let rpc_requests = IntCounterVec::new(
+            Opts::new("rpc_requests_total", "Number of rpc calls handled"),
+            &["method", "code"],
+        )
+        .unwrap();
+
+        let active_sessions = IntGauge::new(
+            "active_sessions",
+            "Number of room sessions connected to this server",
+        )
+        .unwrap();
+
+        let rooms_waiting = IntGauge::new(
+            "rooms_waiting",
+            "Number of rooms with players waiting for the game to start",
+        )
+        .unwrap();
+
+        let games_started =
+            IntCounter::new("games_started_total", "Number of games started").unwrap();
+
+        let games_finished = IntCounter::new(
+            "games_finished_total",
+            "Number of games over, once every player has finished or dropped out",
+        )
+        .unwrap();
+
+        let redis_command_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "redis_command_duration_seconds",
+                "Latency of the redis commands",
+            )
+            .buckets(vec![
+                0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0,
+            ]),
+            &["command"],
+        )
+        .unwrap();
+
+        let matchmaking_wait = Histogram::with_opts(
+            HistogramOpts::new(
+                "matchmaking_wait_seconds",
+                "Time spent by a player in a room before the game started",
+            )
+            .buckets(vec![
+                1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0, 300.0, 600.0,
+            ]),
+        )
+        .unwrap();
+
+        registry.register(Box::new(rpc_requests.clone())).unwrap();
+        registry
+            .register(Box::new(active_sessions.clone()))
+            .unwrap();
+        registry.register(Box::new(rooms_waiting.clone())).unwrap();
+        registry.register(Box::new(games_started.clone())).unwrap();
+        registry.register(Box::new(games_finished.clone())).unwrap();
+        registry
+            .register(Box::new(redis_command_duration.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(matchmaking_wait.clone()))
+            .unwrap();
+
+        Self {
+            registry,
+            rpc_requests,
+            active_sessions,
+            rooms_waiting,
+            games_started,
+            games_finished,
+            redis_command_duration,
+            matchmaking_wait,
+        }
+    }
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+/// Measure the time taken by a redis command
+pub async fn observe_redis_command<T>(
+    command: &str,
+    future: impl std::future::Future<Output = T>,
+) -> T {
+    let timer = metrics()
+        .redis_command_duration
+        .with_label_values(&[command])
+        .start_timer();
+
+    let result = future.await;
+    timer.observe_duration();
+    result
+}
+
+/// Update the gauges that are computed from the state of the store
+async fn update_gauges(store: &Store) {
+    metrics()
+        .active_sessions
+        .set(store.get_session_count() as i64);
+
+    match store.get_waiting_rooms_count().await {
+        Ok(rooms_waiting) => metrics().rooms_waiting.set(rooms_waiting as i64),
+        Err(error) => tracing::error!(?error),
+    }
+}
+
+/// Encode all the metrics in the prometheus text format
+pub async fn encode_metrics(store: &Store) -> String {
+    update_gauges(store).await;
+
+    let mut buffer = Vec::new();
+    TextEncoder::new()
+        .encode(&metrics().registry.gather(), &mut buffer)
+        .expect("Metrics cannot be encoded");
+
+    String::from_utf8(buffer).expect("Metrics are not valid utf8")
+}
+
+/// Serve the metrics over http on `/metrics`, until the shutdown future completes
+pub async fn serve_metrics(
+    tcp_listener: tokio::net::TcpListener,
+    store: Store,
+    shutdown: impl Future<Output = ()>,
+) {
+    // The connections share the service, so that the scrapes are run one at a time
+    let service = tower::ServiceBuilder::new()
+        .concurrency_limit(1)
+        .timeout(SCRAPE_TIMEOUT)
+        .service_fn(move |request| {
+            let store = store.clone();
+            async move { Ok::<_, Infallible>(handle_metrics_request(request, &store).await) }
+        });
+
+    let server_builder = match tcp_listener.into_std().map(hyper::Server::from_tcp) {
+        Ok(Ok(server_builder)) => server_builder,
+        Ok(Err(error)) => {
+            tracing::error!(?error, "Could not start the metrics server");
+            return;
+        }
+        Err(error) => {
+            tracing::error!(?error, "Could not start the metrics server");
+            return;
+        }
+    };
+
+    let server = server_builder
+        .serve(tower::make::Shared::new(service))
+        .with_graceful_shutdown(shutdown);
+
+    if let Err(error) = server.await {
+        tracing::error!(?error, "Metrics server stopped");
+    }
+}
+
+async fn handle_metrics_request(
+    request: hyper::Request<hyper::Body>,
+    store: &Store,
+) -> hyper::Response<hyper::Body> {
+    match (request.method(), request.uri().path()) {
+        (&hyper::Method::GET, "/metrics") => hyper::Response::builder()
+            .header(
+                hyper::header::CONTENT_TYPE,
+                TextEncoder::new().format_type(),
+            )
+            .body(hyper::Body::from(encode_metrics(store).await))
+            .unwrap(),
+        _ => hyper::Response::builder()
+            .status(hyper::StatusCode::NOT_FOUND)
+            .body(hyper::Body::empty())
+            .unwrap(),
+    }
+}
+
+/// Counts the rpc calls handled by the server, by method and status code
+#[derive(Clone, Default)]
+pub struct RpcMetricsLayer;
+
+impl<S> tower::Layer<S> for RpcMetricsLayer {
+    type Service = RpcMetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RpcMetricsService { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct RpcMetricsService<S> {
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> tower::Service<http::Request<ReqBody>> for RpcMetricsService<S>
+where
+    S: tower::Service<http::Request<ReqBody>, Response = http::Response<ResBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future =
+        Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<ReqBody>) -> Self::Future {
+        let method = get_rpc_path(request.uri().path());
+        let response_future = self.inner.call(request);
+
+        Box::pin(async move {
+            let response = response_future.await?;
+
+            // Errors returned by the handlers are sent in the headers ( trailers only response )
+            // Streams that fail midway are counted by the status with which they were started
+            let code = get_grpc_code(response.headers());
+
+            metrics()
+                .rpc_requests
+                .with_label_values(&[method, &format!("{code:?}")])
+                .inc();
+
+            Ok(response)
+        })
+    }
+}
@@ -0,0 +1,300 @@
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use tonic::{
+    codegen::http,
+    transport::server::{TcpConnectInfo, TlsConnectInfo},
+};
+
+use crate::app::{
+    server::{errors::ApiError, grpc::utils::get_rpc_path},
+    types::{RateLimitConfig, TokenBucketConfig},
+};
+
+/// The idle buckets are removed once there are more buckets than this
+const MAX_BUCKETS_BEFORE_CLEANUP: usize = 10_000;
+
+/// Name of the rpc, such as `Ping` for `/server.Grpc/Ping`, the paths that are not served share `unknown`
+///
+/// Added to the extensions of the request, so that the handlers can limit the calls of the user
+#[derive(Clone, Debug)]
+pub struct RpcMethod(pub &'static str);
+
+impl RpcMethod {
+    /// The paths are sent by the clients, so only the known rpcs get buckets of their own
+    fn from_path(path: &str) -> Self {
+        let rpc_path = get_rpc_path(path);
+        Self(rpc_path.rsplit('/').next().unwrap_or(rpc_path))
+    }
+}
+
+struct TokenBucket {
+    limit: TokenBucketConfig,
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * self.limit.per_second).min(f64::from(self.limit.burst));
+        self.updated_at = now;
+    }
+
+    fn is_full(&self) -> bool {
+        self.tokens >= f64::from(self.limit.burst)
+    }
+}
+
+/// Token buckets of the calls, for every rpc and every ip address or user
+struct Buckets {
+    buckets: HashMap<(&'static str, String), TokenBucket>,
+    /// The cleanup runs again once the number of buckets has doubled, so that its cost is spread over the calls
+    cleanup_threshold: usize,
+}
+
+impl Default for Buckets {
+    fn default() -> Self {
+        Self {
+            buckets: HashMap::new(),
+            cleanup_threshold: MAX_BUCKETS_BEFORE_CLEANUP,
+        }
+    }
+}
+
+impl Buckets {
+    fn try_take(&mut self, method: &'static str, key: &str, limit: TokenBucketConfig) -> bool {
+        let now = Instant::now();
+
+        if self.buckets.len() > self.cleanup_threshold {
+            // A full bucket behaves the same as a bucket that does not exist
+            self.buckets.retain(|_, bucket| {
+                bucket.refill(now);
+                !bucket.is_full()
+            });
+            self.cleanup_threshold = MAX_BUCKETS_BEFORE_CLEANUP.max(self.buckets.len() * 2);
+        }
+
+        let bucket = self
+            .buckets
+            .entry((method, key.to_string()))
+            .or_insert(TokenBucket {
+                limit,
+                tokens: f64::from(limit.burst),
+                updated_at: now,
+            });
+
+        bucket.refill(now);
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    ip_buckets: Mutex<Buckets>,
+    user_buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            ip_buckets: Mutex::default(),
+            user_buckets: Mutex::default(),
+        }
+    }
+
+    pub fn max_rooms_per_user(&self) -> usize {
+        self.config.max_rooms_per_user
+    }
+
+    pub fn check_ip(
+        &self,
+        method: &'static str,
+        ip_address: std::net::IpAddr,
+    ) -> Result<(), ApiError> {
+        let limit = self
+            .config
+            .methods
+            .get(method)
+            .and_then(|method_config| method_config.per_ip)
+            .unwrap_or(self.config.per_ip);
+
+        let is_allowed =
+            self.ip_buckets
+                .lock()
+                .unwrap()
+                .try_take(method, &ip_address.to_string(), limit);
+
+        get_result(is_allowed, method)
+    }
+
+    pub fn check_user(&self, method: &'static str, user_id: &str) -> Result<(), ApiError> {
+        let limit = self
+            .config
+            .methods
+            .get(method)
+            .and_then(|method_config| method_config.per_user)
+            .unwrap_or(self.config.per_user);
+
+        let is_allowed = self
+            .user_buckets
+            .lock()
+            .unwrap()
+            .try_take(method, user_id, limit);
+
+        get_result(is_allowed, method)
+    }
+}
+
+fn get_result(is_allowed: bool, method: &str) -> Result<(), ApiError> {
+    if is_allowed {
+        Ok(())
+    } else {
+        tracing::warn!("Rate limit exceeded for {method}");
+        Err(ApiError::RateLimited {
+            method: method.to_string(),
+        })
+    }
+}
+
+/// Limit the calls of the user that is making the request, the calls are not limited without a rate limiter
+///
+/// The user is only known to the handlers, once the request has been decoded
+pub fn check_user_rate(
+    rate_limiter: Option<&RateLimiter>,
+    extensions: &tonic::Extensions,
+    user_id: &str,
+) -> Result<(), ApiError> {
+    let Some(rate_limiter) = rate_limiter else {
+        return Ok(());
+    };
+
+    match extensions.get::<RpcMethod>() {
+        Some(RpcMethod(method)) => rate_limiter.check_user(method, user_id),
+        None => Ok(()),
+    }
+}
+
+/// The info of a TLS connection wraps the info of its TCP connection, which is read when it is not set on its own
+fn get_remote_address(extensions: &http::Extensions) -> Option<std::net::SocketAddr> {
+    extensions
+        .get::<TcpConnectInfo>()
+        .or_else(|| {
+            extensions
+                .get::<TlsConnectInfo<TcpConnectInfo>>()
+                .map(TlsConnectInfo::get_ref)
+        })
+        .and_then(TcpConnectInfo::remote_addr)
+}
+
+/// Limits the calls from every ip address, for every rpc
+///
+/// Rejected calls are answered with `ResourceExhausted` without reaching the handlers
+#[derive(Clone, Default)]
+pub struct RateLimitLayer {
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl RateLimitLayer {
+    pub fn new(rate_limiter: Option<Arc<RateLimiter>>) -> Self {
+        Self { rate_limiter }
+    }
+}
+
+impl<S> tower::Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            rate_limiter: self.rate_limiter.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl<S, ReqBody> tower::Service<http::Request<ReqBody>> for RateLimitService<S>
+where
+    S: tower::Service<http::Request<ReqBody>, Response = http::Response<tonic::body::BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future =
+        Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<ReqBody>) -> Self::Future {
+        let RpcMethod(method) = RpcMethod::from_path(request.uri().path());
+
+        let ip_address =
+            get_remote_address(request.extensions()).map(|remote_address| remote_address.ip());
+
+        if let (Some(rate_limiter), Some(ip_address)) = (&self.rate_limiter, ip_address) {
+            if let Err(api_error) = rate_limiter.check_ip(method, ip_address) {
+                let response = tonic::Status::from(api_error).to_http();
+                return Box::pin(async move { Ok(response) });
+            }
+        }
+
+        request.extensions_mut().insert(RpcMethod(method));
+        Box::pin(self.inner.call(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_rpcs_are_keyed_by_their_name() {
+        assert_eq!(RpcMethod::from_path("/server.Grpc/Ping").0, "Ping");
+        assert_eq!(
+            RpcMethod::from_path("/server.Grpc/RoomService").0,
+            "RoomService"
+        );
+    }
+
+    #[test]
+    fn unknown_paths_share_a_single_bucket() {
+        let limit = TokenBucketConfig {
+            burst: 1_000,
+            per_second: 1.0,
+        };
+        let rate_limiter = RateLimiter::new(RateLimitConfig {
+            per_ip: limit,
+            per_user: limit,
+            methods: HashMap::new(),
+            max_rooms_per_user: 10,
+        });
+        let ip_address = std::net::IpAddr::from([127, 0, 0, 1]);
+
+        for index in 0..100 {
+            let RpcMethod(method) = RpcMethod::from_path(&format!("/server.Grpc/Random{index}"));
+            let _ = rate_limiter.check_ip(method, ip_address);
+        }
+
+        assert_eq!(rate_limiter.ip_buckets.lock().unwrap().buckets.len(), 1);
+    }
+}
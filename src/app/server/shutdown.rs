@@ -0,0 +1,182 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio_stream::{wrappers::TcpListenerStream, Stream, StreamExt};
+use tokio_util::sync::{CancellationToken, WaitForCancellationFutureOwned};
+
+use crate::app::server::grpc::{
+    functions::room_service::remove_user_session,
+    storage::{
+        interface::{game::GameInterface, session::SessionInterface, user::UserInterface},
+        models, Store,
+    },
+    types::RoomMessage,
+};
+
+/// How often the connected sessions are checked while waiting for the games to finish
+const GAME_FINISH_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+fn get_signals() -> signal_hook_tokio::Signals {
+    signal_hook_tokio::Signals::new([signal_hook::consts::SIGINT, signal_hook::consts::SIGTERM])
+        .expect("Unable to register the signals for graceful shutdown")
+}
+
+/// Wait until the process receives a signal to shutdown
+pub async fn wait_for_signal() {
+    let mut signals = get_signals();
+
+    if let Some(signal) = signals.next().await {
+        match signal {
+            signal_hook::consts::SIGINT | signal_hook::consts::SIGTERM => {
+                tracing::warn!("Received signal {signal} to shutdown");
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// The connections accepted by the server, until the token is cancelled
+///
+/// The listener is closed once the token is cancelled, so that the new connections are refused while the
+/// connected sessions are drained
+pub struct Incoming {
+    listener: Option<TcpListenerStream>,
+    stop_accepting: Pin<Box<WaitForCancellationFutureOwned>>,
+}
+
+impl Incoming {
+    pub fn new(tcp_listener: tokio::net::TcpListener, stop_accepting: CancellationToken) -> Self {
+        Self {
+            listener: Some(TcpListenerStream::new(tcp_listener)),
+            stop_accepting: Box::pin(stop_accepting.cancelled_owned()),
+        }
+    }
+}
+
+impl Stream for Incoming {
+    type Item = std::io::Result<tokio::net::TcpStream>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.listener.is_some() && self.stop_accepting.as_mut().poll(cx).is_ready() {
+            tracing::info!("No longer accepting new connections");
+            self.listener = None;
+        }
+
+        // The stream does not end, the server would stop without waiting for the connected sessions otherwise
+        match self.listener.as_mut() {
+            Some(listener) => Pin::new(listener).poll_next(cx),
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// Check whether the user is playing a game that is still in progress
+async fn is_user_in_game(store: &Store, user_id: &str) -> bool {
+    let game_id = match store.find_user(user_id).await {
+        Ok(user) => user.game_id,
+        Err(error) => {
+            tracing::error!(?error);
+            None
+        }
+    };
+
+    let Some(game_id) = game_id else {
+        return false;
+    };
+
+    match store.find_game(&game_id).await {
+        Ok(game) => {
+            game.is_in_progress()
+                && !matches!(
+                    game.get_player(user_id).status,
+                    models::PlayerStatus::Finished | models::PlayerStatus::DidNotFinish
+                )
+        }
+        Err(error) => {
+            if !error.is_not_found() {
+                tracing::error!(?error);
+            }
+            false
+        }
+    }
+}
+
+/// Remove the user from the room and end the room stream of the user
+async fn close_user_session(store: &Store, user_id: &str) {
+    let status = tonic::Status::unavailable("The server is shutting down");
+    if store.close_channel(user_id, status).await.is_ok() {
+        remove_user_session(store, user_id).await;
+    }
+}
+
+/// Drain all the sessions connected to this server before shutting down
+///
+/// The connected players are informed about the shutdown, players waiting in rooms are removed
+/// right away and the games in progress are given the grace period to finish
+pub async fn drain_sessions(store: &Store, grace_period: std::time::Duration) {
+    store.start_draining();
+
+    let connected_user_ids = store.get_connected_user_ids();
+    tracing::info!(
+        "Informing {} connected players about the shutdown",
+        connected_user_ids.len()
+    );
+
+    for user_id in connected_user_ids.iter() {
+        if let Err(error) = store
+            .send_message_to_user(user_id, RoomMessage::ServerShutdown)
+            .await
+        {
+            tracing::warn!(?error, "Could not inform user {user_id} about the shutdown");
+        }
+    }
+
+    let mut players_in_game = Vec::new();
+    for user_id in connected_user_ids {
+        if is_user_in_game(store, &user_id).await {
+            players_in_game.push(user_id);
+        } else {
+            close_user_session(store, &user_id).await;
+        }
+    }
+
+    let deadline = tokio::time::Instant::now() + grace_period;
+
+    while !players_in_game.is_empty() && tokio::time::Instant::now() < deadline {
+        tracing::info!(
+            "Waiting for {} players to finish their games",
+            players_in_game.len()
+        );
+        tokio::time::sleep(GAME_FINISH_POLL_INTERVAL).await;
+
+        let connected_user_ids = store.get_connected_user_ids();
+        let mut still_playing = Vec::new();
+
+        for user_id in players_in_game {
+            if connected_user_ids.contains(&user_id) && is_user_in_game(store, &user_id).await {
+                still_playing.push(user_id);
+            } else {
+                close_user_session(store, &user_id).await;
+            }
+        }
+
+        players_in_game = still_playing;
+    }
+
+    if !players_in_game.is_empty() {
+        tracing::warn!(
+            "Grace period is over, removing {} players who are still in game",
+            players_in_game.len()
+        );
+    }
+
+    for user_id in players_in_game {
+        close_user_session(store, &user_id).await;
+    }
+
+    // End the long lived streams so that the connections can be closed
+    store.shutdown_token.cancel();
+}
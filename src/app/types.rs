@@ -2,6 +2,18 @@
 pub struct ServerConfig {
     pub server: Option<Server>,
     pub redis: Option<RedisConfig>,
+    /// The admin service is enabled only when this is configured
+    pub admin: Option<AdminConfig>,
+    /// The prometheus metrics are served only when this is configured
+    pub metrics: Option<MetricsConfig>,
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    #[serde(default)]
+    pub shutdown: ShutdownConfig,
+    /// The server accepts plaintext connections only when this is not configured
+    pub tls: Option<TlsConfig>,
+    /// The calls are not limited when this is not configured
+    pub rate_limit: Option<RateLimitConfig>,
     pub test_mode: bool,
 }
 
@@ -20,6 +32,93 @@ impl Default for Server {
     }
 }
 
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct AdminConfig {
+    /// Token expected in the `authorization` metadata as `Bearer <token>`
+    pub token: String,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct TlsConfig {
+    /// PEM encoded certificate chain of the server
+    pub certificate_path: String,
+    /// PEM encoded private key of the certificate
+    pub key_path: String,
+    /// PEM encoded CA certificates, the clients must present a certificate signed by one of them when set ( mTLS )
+    pub client_ca_path: Option<String>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct RateLimitConfig {
+    /// Calls of every rpc from a single ip address
+    pub per_ip: TokenBucketConfig,
+    /// Calls of every rpc by a single user
+    pub per_user: TokenBucketConfig,
+    /// Limits of specific rpcs by the method name, such as `Ping`, used instead of the limits above
+    #[serde(default)]
+    pub methods: std::collections::HashMap<String, MethodRateLimitConfig>,
+    /// Rooms that a single user can have created and waiting for players at once
+    pub max_rooms_per_user: usize,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Default)]
+pub struct MethodRateLimitConfig {
+    pub per_ip: Option<TokenBucketConfig>,
+    pub per_user: Option<TokenBucketConfig>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy)]
+pub struct TokenBucketConfig {
+    /// Calls that can be made at once after a pause
+    pub burst: u32,
+    /// Calls that are allowed again every second
+    pub per_second: f64,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct MetricsConfig {
+    /// Port on which `/metrics` is served over http, on the same host as the server
+    pub port: u16,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    #[default]
+    Pretty,
+    Json,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct LoggingConfig {
+    pub format: LogFormat,
+    /// Directives in the `RUST_LOG` format, for example `info,blazer=debug`
+    pub filter: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::Pretty,
+            filter: "info".to_string(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct ShutdownConfig {
+    /// Time given to the games in progress to finish, before the players are removed
+    pub grace_period_seconds: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            grace_period_seconds: 30,
+        }
+    }
+}
+
 #[derive(serde::Deserialize, Debug)]
 pub struct RedisConfig {
     pub username: Option<String>,
@@ -40,6 +139,9 @@ impl Default for RedisConfig {
     }
 }
 
+/// Metadata key used to correlate the logs of the client and the server for a request
+pub const REQUEST_ID_METADATA_KEY: &str = "x-request-id";
+
 pub enum RoomServiceRequestType {
     CreateRoom = 1,
     JoinRoom = 2,
@@ -49,6 +151,9 @@ pub enum RoomServiceResponseType {
     Init = 1,
     UserJoined = 2,
     GameStart = 3,
+    ServerShutdown = 4,
+    UserLeft = 5,
+    Chat = 6,
 }
 
 impl RoomServiceResponseType {
@@ -57,6 +162,9 @@ impl RoomServiceResponseType {
             RoomServiceResponseType::Init => 1,
             RoomServiceResponseType::UserJoined => 2,
             RoomServiceResponseType::GameStart => 3,
+            RoomServiceResponseType::ServerShutdown => 4,
+            RoomServiceResponseType::UserLeft => 5,
+            RoomServiceResponseType::Chat => 6,
         }
     }
 
@@ -65,6 +173,9 @@ impl RoomServiceResponseType {
             1 => Some(Self::Init),
             2 => Some(Self::UserJoined),
             3 => Some(Self::GameStart),
+            4 => Some(Self::ServerShutdown),
+            5 => Some(Self::UserLeft),
+            6 => Some(Self::Chat),
             _ => None,
         }
     }
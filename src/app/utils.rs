@@ -19,7 +19,8 @@ where
     data.unwrap().try_deserialize().unwrap()
 }
 
-fn replace_home_dir(file_name: &str) -> String {
+/// Expand the `~` in the path to the home directory of the user
+pub fn replace_home_dir(file_name: &str) -> String {
     let path_buf = std::path::PathBuf::from(file_name);
     path_buf
         .iter()
@@ -36,40 +37,36 @@ fn replace_home_dir(file_name: &str) -> String {
 
 /// Read a file from local storage
 ///
-/// Return `None` if file is not present
+/// Return `None` if file is not present or does not contain valid data
 pub async fn read_local_storage<T>(file_name: &str) -> Option<T>
 where
     T: serde::de::DeserializeOwned,
 {
     let file_name = replace_home_dir(file_name);
-    fs::read_to_string(file_name)
-        .await
+    let file_contents = fs::read_to_string(&file_name).await.ok()?;
+
+    toml::from_str::<T>(&file_contents)
+        .map_err(|error| tracing::warn!(?error, "Invalid data in file {file_name}"))
         .ok()
-        .map(|file_contents| toml::from_str::<T>(&file_contents).expect("Invalid data in file"))
 }
 
 /// Write the given string to file in local storage
 ///
-/// Create the file if it does not exist
-pub async fn write_local_storage<T>(file_name: &str, data: T)
+/// Create the file and its directory if they do not exist
+pub async fn write_local_storage<T>(file_name: &str, data: T) -> std::io::Result<()>
 where
     T: serde::Serialize,
 {
     let file_name = replace_home_dir(file_name);
-    let file_contents = toml::to_string(&data).expect("Cannot convert data to toml representation");
-
-    if fs::write(&file_name, file_contents.as_bytes())
-        .await
-        .is_err()
-    {
-        let mut file = fs::File::create(file_name)
-            .await
-            .expect("Cannot create the file");
+    let file_contents = toml::to_string(&data)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
 
-        file.write_all(file_contents.as_bytes())
-            .await
-            .expect("Cannot write to file");
+    if let Some(directory) = std::path::Path::new(&file_name).parent() {
+        fs::create_dir_all(directory).await?;
     }
+
+    let mut file = fs::File::create(file_name).await?;
+    file.write_all(file_contents.as_bytes()).await
 }
 
 /// Generate a time-ordered (time-sortable) unique identifier using the current time
@@ -78,6 +75,14 @@ pub fn generate_time_ordered_id(prefix: &str) -> String {
     format!("{prefix}_{}", uuid::Uuid::now_v7().as_simple())
 }
 
+/// Milliseconds elapsed since the unix epoch
+pub fn current_timestamp_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
 /// Generate a random name
 pub fn generate_name() -> String {
     let random_name_generator = rnglib::RNG::from(&rnglib::Language::Fantasy);
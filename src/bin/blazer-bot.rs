@@ -0,0 +1,25 @@
+use blazer::app::{
+    bot::{self, BotArgs},
+    client::types,
+    utils,
+};
+
+use clap::Parser;
+
+#[tokio::main]
+async fn main() {
+    let args = BotArgs::parse();
+
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
+        .init();
+
+    // The bots connect to the same server as the client by default
+    let config = utils::read_config::<types::ClientConfig>("config/client.toml", Some("BLAZER"));
+    let server_url = args.server_url.clone().unwrap_or(config.server_url);
+
+    bot::run_bots(server_url, config.tls, args).await;
+}
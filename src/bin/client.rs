@@ -14,6 +14,9 @@ fn main() {
     // Read application config
     let config = utils::read_config::<types::ClientConfig>("config/client.toml", Some("BLAZER"));
 
+    #[cfg(feature = "client_logs")]
+    let _log_guard = blazer::app::client::logging::init_tracing(&config.log_level);
+
     // Setup model
     let mut model = Model::new(config, args);
     // Enter alternate screen
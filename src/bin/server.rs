@@ -2,13 +2,9 @@ use blazer::app::{types, utils::read_config};
 
 #[tokio::main(flavor = "current_thread")]
 pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let formatter = tracing_subscriber::fmt::format()
-        .with_file(true)
-        .with_line_number(true);
-
-    tracing_subscriber::fmt().event_format(formatter).init();
-
     let config = read_config::<types::ServerConfig>("config/server.toml", Some("BLAZER_SERVER"));
+    blazer::app::server::logging::init_tracing(&config.logging);
+
     let server_config = config.server.clone().unwrap_or_default();
 
     let server_address = format!("{}:{}", server_config.host, server_config.port);
@@ -18,6 +14,14 @@ pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .await
         .expect("Could not bind to server address {server_address}");
 
-    blazer::app::server::start_server(config, tcp_listener).await;
+    // The server is stopped by SIGINT and SIGTERM
+    let server_handle = blazer::app::server::start_server(
+        config,
+        tcp_listener,
+        blazer::app::server::shutdown::wait_for_signal(),
+    )
+    .await?;
+
+    server_handle.wait().await;
     Ok(())
 }
@@ -1,5 +1,7 @@
+use crate::app::client::network::types::PublicRoom as NetworkPublicRoom;
 use crate::app::client::network::types::UserDetails as NetworkUserDetails;
 use crate::app::client::types::UserDetails as AppUserDetails;
+use crate::app::server::grpc::server::PublicRoom as GrpcPublicRoom;
 use crate::app::server::grpc::server::UserDetails as GrpcUserDetails;
 
 impl From<NetworkUserDetails> for GrpcUserDetails {
@@ -34,3 +36,15 @@ impl From<NetworkUserDetails> for AppUserDetails {
         }
     }
 }
+
+impl From<GrpcPublicRoom> for NetworkPublicRoom {
+    fn from(grpc_room: GrpcPublicRoom) -> Self {
+        Self {
+            room_id: grpc_room.room_id,
+            room_size: grpc_room.room_size,
+            player_count: grpc_room.player_count,
+            host_name: grpc_room.host.map(|host| host.user_name),
+            age_seconds: grpc_room.age_seconds,
+        }
+    }
+}
@@ -0,0 +1,157 @@
+mod common;
+
+use blazer::app::{
+    server::grpc::{
+        server::{admin_client, AdminEntityRequest, AdminListRequest},
+        storage::{
+            interface::{game::GameInterface, user::UserInterface},
+            models,
+        },
+    },
+    types::{AdminConfig, RoomServiceResponseType, ServerConfig},
+};
+use common::{assert_message_type, TestServer};
+
+const ADMIN_TOKEN: &str = "admin-token";
+
+type AdminClient = admin_client::AdminClient<tonic::transport::Channel>;
+
+async fn start_server() -> TestServer {
+    TestServer::start_with_config(ServerConfig {
+        admin: Some(AdminConfig {
+            token: ADMIN_TOKEN.to_string(),
+        }),
+        ..Default::default()
+    })
+    .await
+}
+
+async fn connect_admin(server: &TestServer) -> AdminClient {
+    AdminClient::connect(server.url.clone()).await.unwrap()
+}
+
+fn with_token<T>(message: T, token: &str) -> tonic::Request<T> {
+    let mut request = tonic::Request::new(message);
+    request
+        .metadata_mut()
+        .insert("authorization", format!("Bearer {token}").parse().unwrap());
+    request
+}
+
+fn get_entity_request(id: &str) -> tonic::Request<AdminEntityRequest> {
+    with_token(AdminEntityRequest { id: id.to_string() }, ADMIN_TOKEN)
+}
+
+#[tokio::test]
+async fn requests_without_the_admin_token_are_rejected() {
+    let server = start_server().await;
+    let mut admin = connect_admin(&server).await;
+
+    let status = admin
+        .list_users(AdminListRequest {})
+        .await
+        .expect_err("The request has no token");
+    assert_eq!(status.code(), tonic::Code::Unauthenticated);
+
+    for token in ["admin-tokem", "admin", "admin-token-and-more"] {
+        let status = admin
+            .list_users(with_token(AdminListRequest {}, token))
+            .await
+            .expect_err("The token is invalid");
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+    }
+
+    admin
+        .list_users(with_token(AdminListRequest {}, ADMIN_TOKEN))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn deleted_rooms_close_the_sessions_of_their_users() {
+    let server = start_server().await;
+    let mut admin = connect_admin(&server).await;
+    let mut client = server.connect_client().await;
+
+    server.insert_room("waiting", 3, &[]).await;
+    client.join_room(Some("waiting")).await.unwrap();
+
+    admin
+        .delete_room(get_entity_request("waiting"))
+        .await
+        .unwrap();
+
+    let status = client.wait_for_room_stream_end().await.unwrap();
+    assert_eq!(status.code(), tonic::Code::Aborted);
+
+    assert!(server.find_room("waiting").await.is_none());
+    let user = server.store.find_user(&client.user_id).await.unwrap();
+    assert_eq!(user.room_id, None);
+}
+
+#[tokio::test]
+async fn deleted_users_leave_their_room() {
+    let server = start_server().await;
+    let mut admin = connect_admin(&server).await;
+    let mut clients = server.connect_clients(2).await;
+
+    server.insert_room("waiting", 3, &[]).await;
+    clients[0].join_room(Some("waiting")).await.unwrap();
+    clients[1].join_room(Some("waiting")).await.unwrap();
+
+    admin
+        .delete_user(get_entity_request(&clients[0].user_id))
+        .await
+        .unwrap();
+
+    let status = clients[0].wait_for_room_stream_end().await.unwrap();
+    assert_eq!(status.code(), tonic::Code::Aborted);
+
+    let message = clients[1].next_room_message().await;
+    assert_message_type(&message, RoomServiceResponseType::UserLeft);
+
+    let room = server.find_room("waiting").await.unwrap();
+    assert_eq!(room.users, [clients[1].user_id.clone()]);
+    assert!(server.store.find_user(&clients[0].user_id).await.is_err());
+}
+
+#[tokio::test]
+async fn deleted_games_are_cleared_from_their_users() {
+    let server = start_server().await;
+    let mut admin = connect_admin(&server).await;
+    let client = server.connect_client().await;
+
+    let mut user = server.store.find_user(&client.user_id).await.unwrap();
+    let game = models::Game::new(&[user.clone()], "prompt".to_string());
+    let game_id = game.game_id.clone();
+    server.store.insert_game(game).await.unwrap();
+    user.assign_game_id(game_id.clone());
+    server.store.insert_user(user).await.unwrap();
+
+    admin
+        .delete_game(get_entity_request(&game_id))
+        .await
+        .unwrap();
+
+    assert!(server.store.find_game(&game_id).await.is_err());
+    let user = server.store.find_user(&client.user_id).await.unwrap();
+    assert_eq!(user.game_id, None);
+}
+
+#[tokio::test]
+async fn missing_entities_are_not_found() {
+    let server = start_server().await;
+    let mut admin = connect_admin(&server).await;
+
+    let status = admin
+        .delete_user(get_entity_request("missing"))
+        .await
+        .expect_err("The user does not exist");
+    assert_eq!(status.code(), tonic::Code::NotFound);
+
+    let status = admin
+        .get_room(get_entity_request("missing"))
+        .await
+        .expect_err("The room does not exist");
+    assert_eq!(status.code(), tonic::Code::NotFound);
+}
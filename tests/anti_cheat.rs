@@ -0,0 +1,73 @@
+mod common;
+
+use blazer::app::server::grpc::storage::{interface::game::GameInterface, models::CheatFlagKind};
+use common::{TestGameStream, TestServer};
+
+/// Start a game between two players, returns the game stream of the first player
+async fn start_game(server: &TestServer) -> TestGameStream {
+    let mut clients = server.connect_clients(2).await;
+    clients[0].join_room(None).await.unwrap();
+    clients[1].join_room(None).await.unwrap();
+
+    clients[0].open_game_stream(0).await
+}
+
+#[tokio::test]
+async fn cheated_progress_is_flagged_on_the_game() {
+    let server = TestServer::start().await;
+    let mut game_stream = start_game(&server).await;
+
+    let prompt = game_stream.next_game_message().await.prompt.unwrap();
+    game_stream
+        .send_progress(prompt.chars().count() as u32)
+        .await;
+
+    // The progress is not applied, and the player is marked as unranked for the other players
+    let message = game_stream.next_game_message().await;
+    let status = message.game_user_status.unwrap();
+    assert_eq!(status.status, 0);
+    assert!(status.unranked);
+
+    let game = server.store.find_game(&game_stream.game_id).await.unwrap();
+    assert_eq!(game.cheat_flags.len(), 1);
+    assert_eq!(game.cheat_flags[0].user_id, game_stream.user_id);
+    assert_eq!(game.cheat_flags[0].kind, CheatFlagKind::ImpossibleBurst);
+    assert!(game.get_player(&game_stream.user_id).unranked);
+}
+
+#[tokio::test]
+async fn constant_keystroke_intervals_within_batches_are_flagged() {
+    let server = TestServer::start().await;
+    let mut game_stream = start_game(&server).await;
+
+    game_stream.next_game_message().await;
+
+    // The prompt is long enough for the keystroke intervals to be checked
+    server
+        .store
+        .update_game(&game_stream.game_id, |game| {
+            game.prompt = "a".repeat(60);
+            Some(())
+        })
+        .await
+        .unwrap();
+
+    // Batches of a few characters at a human rate, every character was typed in exactly the same time
+    for progress in (5..=40).step_by(5) {
+        tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        game_stream
+            .send_progress_with_intervals(progress, vec![50; 5])
+            .await;
+        game_stream.next_game_message().await;
+    }
+
+    // The progress is still applied, only the result is flagged
+    let game = server.store.find_game(&game_stream.game_id).await.unwrap();
+    let player = game.get_player(&game_stream.user_id);
+    assert_eq!(player.progress, 40);
+    assert!(player.unranked);
+    assert!(game
+        .cheat_flags
+        .iter()
+        .any(|flag| flag.kind == CheatFlagKind::SuspiciousKeystrokeTiming));
+}
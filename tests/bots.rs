@@ -0,0 +1,43 @@
+mod common;
+
+use blazer::app::{
+    bot::{self, BotArgs},
+    server::grpc::storage::interface::user::UserInterface,
+};
+use clap::Parser;
+use common::{wait_for, TestServer};
+
+#[tokio::test]
+async fn bots_creating_rooms_play_in_pairs() {
+    let server = TestServer::start().await;
+    let args = BotArgs::parse_from([
+        "blazer-bot",
+        "--bots",
+        "2",
+        "--create-room",
+        "--games",
+        "1",
+        "--wpm",
+        "200",
+        "--error-rate",
+        "0",
+        "--spawn-interval-ms",
+        "0",
+        "--progress-interval-ms",
+        "100",
+    ]);
+
+    wait_for(
+        "the bots to play their game",
+        bot::run_bots(server.url.clone(), None, args),
+    )
+    .await;
+
+    // Both bots have played the same game as guests
+    let user_ids = server.store.get_all_user_ids().await.unwrap();
+    assert_eq!(user_ids.len(), 2);
+    for user_id in user_ids {
+        let user = server.store.find_user(&user_id).await.unwrap();
+        assert_eq!(user.games_played, 1);
+    }
+}
@@ -0,0 +1,437 @@
+//! Runs the server inside the test process, on a port picked by the OS and with the data kept in memory
+//!
+//! Every test starts its own server, so the tests do not share any state and can run in parallel
+#![allow(dead_code)]
+
+use std::time::Duration;
+
+use blazer::app::{
+    server::{
+        grpc::{
+            redis_client::RedisClient,
+            server::{
+                grpc_client, GameServiceRequest, GameServiceResponse, GlobalStatsRequest,
+                GlobalStatsResponse, LeaveRoomRequest, LeaveRoomResponse, ListRoomsRequest,
+                PingRequest, PingResponse, PublicRoom, RoomServiceRequest, RoomServiceResponse,
+                SendRoomChatRequest, SendRoomChatResponse,
+            },
+            storage::{
+                interface::{room::RoomInterface, session::SessionInterface},
+                models, Store,
+            },
+        },
+        start_server_with_store, ServerHandle,
+    },
+    types::{self, RoomServiceRequestType, RoomServiceResponseType},
+};
+
+/// The longest time to wait for an event of the server, the test fails instead of hanging
+pub const EVENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+type GrpcClient = grpc_client::GrpcClient<tonic::transport::Channel>;
+
+/// The tracing subscriber is global, it is set by the first test that starts a server
+fn init_tracing() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_test_writer()
+        .try_init();
+}
+
+/// Wait for the future, or fail the test if the server does not respond in time
+pub async fn wait_for<F: std::future::Future>(event: &str, future: F) -> F::Output {
+    tokio::time::timeout(EVENT_TIMEOUT, future)
+        .await
+        .unwrap_or_else(|_| panic!("Timed out waiting for {event}"))
+}
+
+/// Poll the state of the server until the condition holds, for the changes that cannot be watched
+pub async fn wait_until<F, Fut>(event: &str, mut condition: F)
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = bool>,
+{
+    wait_for(event, async {
+        while !condition().await {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+}
+
+pub fn assert_message_type(message: &RoomServiceResponse, message_type: RoomServiceResponseType) {
+    assert_eq!(
+        message.message_type,
+        i32::from(message_type.to_u8()),
+        "Unexpected message {message:?}"
+    );
+}
+
+pub struct TestServer {
+    pub url: String,
+    /// Shares the state of the running server
+    pub store: Store,
+    handle: ServerHandle,
+}
+
+impl TestServer {
+    pub async fn start() -> Self {
+        Self::start_with_config(types::ServerConfig::default()).await
+    }
+
+    /// The redis configuration is not used, the data is always kept in memory
+    pub async fn start_with_config(server_config: types::ServerConfig) -> Self {
+        Self::start_with_store(server_config, Store::new(RedisClient::in_memory())).await
+    }
+
+    /// Start another server on the same data, as another instance of a deployment that shares a redis server
+    ///
+    /// The sessions of the users are only known to the server that they are connected to
+    pub async fn start_sharing_redis(&self) -> Self {
+        let store = Store::new(self.store.redis_client.clone());
+        Self::start_with_store(types::ServerConfig::default(), store).await
+    }
+
+    async fn start_with_store(server_config: types::ServerConfig, store: Store) -> Self {
+        init_tracing();
+
+        let tcp_listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Could not bind to an ephemeral port");
+        let scheme = if server_config.tls.is_some() {
+            "https"
+        } else {
+            "http"
+        };
+
+        let handle = start_server_with_store(
+            server_config,
+            tcp_listener,
+            store.clone(),
+            std::future::pending(),
+        )
+        .await
+        .expect("Could not start the server");
+
+        Self {
+            url: format!("{scheme}://{}", handle.local_address()),
+            store,
+            handle,
+        }
+    }
+
+    /// Stop the server gracefully, the server is stopped along with the runtime of the test otherwise
+    pub async fn shutdown(self) {
+        wait_for("the server to stop", self.handle.shutdown()).await;
+    }
+
+    /// Connect a new guest user
+    pub async fn connect_client(&self) -> TestClient {
+        let mut client = GrpcClient::connect(self.url.clone())
+            .await
+            .expect("Could not connect to the test server");
+
+        let ping_response = client.ping(PingRequest { user_id: None }).await.unwrap();
+
+        TestClient {
+            client,
+            user_id: ping_response.into_inner().user_id,
+            room_stream: None,
+        }
+    }
+
+    pub async fn connect_clients(&self, count: usize) -> Vec<TestClient> {
+        let mut clients = Vec::with_capacity(count);
+        for _ in 0..count {
+            clients.push(self.connect_client().await);
+        }
+        clients
+    }
+
+    /// Create a room directly in the store, for the rooms that cannot be created through the api
+    ///
+    /// The room is public, so that it can be joined with its id
+    pub async fn insert_room(&self, room_id: &str, room_size: u8, user_ids: &[&str]) {
+        let mut room = models::Room::new(room_id.to_string(), room_size);
+        room.is_public = true;
+        for user_id in user_ids {
+            room.add_user(user_id.to_string());
+        }
+
+        self.store.insert_room(room).await.unwrap();
+    }
+
+    pub async fn find_room(&self, room_id: &str) -> Option<models::Room> {
+        self.store.find_room(room_id).await.ok()
+    }
+
+    /// Wait until the server has cleaned up the sessions of the disconnected clients
+    pub async fn wait_for_session_count(&self, session_count: usize) {
+        let mut session_count_receiver = self.store.watch_session_count();
+
+        wait_for(
+            "the session count",
+            session_count_receiver.wait_for(|count| *count == session_count),
+        )
+        .await
+        .unwrap();
+    }
+}
+
+/// A simulated player, that keeps its room stream open like the client does
+pub struct TestClient {
+    pub client: GrpcClient,
+    pub user_id: String,
+    room_stream: Option<tonic::Streaming<RoomServiceResponse>>,
+}
+
+impl TestClient {
+    fn get_room_request(&self, request_type: RoomServiceRequestType) -> RoomServiceRequest {
+        RoomServiceRequest {
+            client_id: self.user_id.clone(),
+            room_id: None,
+            request_type: request_type as i32,
+            invite_code: None,
+            single_use_invite: false,
+            is_public: false,
+        }
+    }
+
+    /// Open the room stream, returns the first message of the stream
+    async fn open_room_stream(
+        &mut self,
+        request: RoomServiceRequest,
+    ) -> Result<RoomServiceResponse, tonic::Status> {
+        let room_stream = self.client.room_service(request).await?.into_inner();
+        self.room_stream = Some(room_stream);
+
+        Ok(self.next_room_message().await)
+    }
+
+    /// Create a private room, whose id is chosen by the server
+    pub async fn create_room(&mut self) -> Result<RoomServiceResponse, tonic::Status> {
+        let request = self.get_room_request(RoomServiceRequestType::CreateRoom);
+        self.open_room_stream(request).await
+    }
+
+    /// Create a room whose invite code can only be used once
+    pub async fn create_room_with_single_use_invite(
+        &mut self,
+    ) -> Result<RoomServiceResponse, tonic::Status> {
+        let request = RoomServiceRequest {
+            single_use_invite: true,
+            ..self.get_room_request(RoomServiceRequestType::CreateRoom)
+        };
+        self.open_room_stream(request).await
+    }
+
+    /// Create a room that is listed in the room browser
+    pub async fn create_public_room(&mut self) -> Result<RoomServiceResponse, tonic::Status> {
+        let request = RoomServiceRequest {
+            is_public: true,
+            ..self.get_room_request(RoomServiceRequestType::CreateRoom)
+        };
+        self.open_room_stream(request).await
+    }
+
+    /// Join the common room if no room id is passed
+    pub async fn join_room(
+        &mut self,
+        room_id: Option<&str>,
+    ) -> Result<RoomServiceResponse, tonic::Status> {
+        let request = RoomServiceRequest {
+            room_id: room_id.map(str::to_string),
+            ..self.get_room_request(RoomServiceRequestType::JoinRoom)
+        };
+        self.open_room_stream(request).await
+    }
+
+    pub async fn join_with_invite(
+        &mut self,
+        invite_code: &str,
+    ) -> Result<RoomServiceResponse, tonic::Status> {
+        let request = RoomServiceRequest {
+            invite_code: Some(invite_code.to_string()),
+            ..self.get_room_request(RoomServiceRequestType::JoinRoom)
+        };
+        self.open_room_stream(request).await
+    }
+
+    pub async fn next_room_message(&mut self) -> RoomServiceResponse {
+        let room_stream = self
+            .room_stream
+            .as_mut()
+            .expect("The room stream is not open");
+
+        wait_for("a room message", room_stream.message())
+            .await
+            .expect("The room stream failed")
+            .expect("The room stream was closed")
+    }
+
+    /// Read the room stream until it ends, returns the status that the stream has ended with
+    pub async fn wait_for_room_stream_end(&mut self) -> Option<tonic::Status> {
+        let room_stream = self
+            .room_stream
+            .as_mut()
+            .expect("The room stream is not open");
+
+        wait_for("the room stream to end", async {
+            loop {
+                match room_stream.message().await {
+                    Ok(Some(_)) => continue,
+                    Ok(None) => return None,
+                    Err(status) => return Some(status),
+                }
+            }
+        })
+        .await
+    }
+
+    pub async fn leave_room(&mut self) -> LeaveRoomResponse {
+        self.client
+            .leave_room(LeaveRoomRequest {
+                client_id: self.user_id.clone(),
+            })
+            .await
+            .unwrap()
+            .into_inner()
+    }
+
+    pub async fn list_rooms(&mut self) -> Vec<PublicRoom> {
+        self.client
+            .list_rooms(ListRoomsRequest {
+                client_id: self.user_id.clone(),
+            })
+            .await
+            .unwrap()
+            .into_inner()
+            .rooms
+    }
+
+    pub async fn send_room_chat(
+        &mut self,
+        message: &str,
+    ) -> Result<SendRoomChatResponse, tonic::Status> {
+        self.client
+            .send_room_chat(SendRoomChatRequest {
+                client_id: self.user_id.clone(),
+                message: message.to_string(),
+            })
+            .await
+            .map(tonic::Response::into_inner)
+    }
+
+    /// Open a global stats stream, the user is online while the stream is open
+    pub async fn open_global_stats_stream(&mut self) -> tonic::Streaming<GlobalStatsResponse> {
+        self.client
+            .global_stats(GlobalStatsRequest {
+                client_id: self.user_id.clone(),
+            })
+            .await
+            .unwrap()
+            .into_inner()
+    }
+
+    pub async fn ping(&mut self) -> PingResponse {
+        self.try_ping().await.unwrap()
+    }
+
+    pub async fn try_ping(&mut self) -> Result<PingResponse, tonic::Status> {
+        self.client
+            .ping(PingRequest {
+                user_id: Some(self.user_id.clone()),
+            })
+            .await
+            .map(tonic::Response::into_inner)
+    }
+
+    /// Close the room stream, as the client does when it quits
+    pub fn disconnect(&mut self) {
+        self.room_stream = None;
+    }
+
+    /// Open the game stream of the game that the user is playing, the first message carries the progress
+    pub async fn open_game_stream(&mut self, progress: u32) -> TestGameStream {
+        let game_id = self
+            .ping()
+            .await
+            .resumable_game
+            .expect("The user is not playing a game")
+            .game_id;
+
+        let (request_sender, request_receiver) = tokio::sync::mpsc::channel(16);
+        let mut game_stream = TestGameStream {
+            user_id: self.user_id.clone(),
+            game_id,
+            request_sender,
+            response_stream: None,
+        };
+        game_stream.send_progress(progress).await;
+
+        let response_stream = self
+            .client
+            .game_service(tokio_stream::wrappers::ReceiverStream::new(
+                request_receiver,
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+
+        game_stream.response_stream = Some(response_stream);
+        game_stream
+    }
+}
+
+pub struct TestGameStream {
+    pub user_id: String,
+    pub game_id: String,
+    request_sender: tokio::sync::mpsc::Sender<GameServiceRequest>,
+    response_stream: Option<tonic::Streaming<GameServiceResponse>>,
+}
+
+impl TestGameStream {
+    pub async fn send_progress(&self, progress: u32) {
+        self.send_progress_with_intervals(progress, vec![]).await
+    }
+
+    /// Send the progress along with the time taken to type each of the characters, as the bots do
+    pub async fn send_progress_with_intervals(
+        &self,
+        progress: u32,
+        keystroke_intervals_ms: Vec<u32>,
+    ) {
+        self.request_sender
+            .send(GameServiceRequest {
+                client_id: self.user_id.clone(),
+                game_id: self.game_id.clone(),
+                progress,
+                keystroke_intervals_ms,
+            })
+            .await
+            .expect("The game stream was closed");
+    }
+
+    /// Close the stream as the client does, returns once the server has handled the end of the stream
+    pub async fn close(self) {
+        drop(self.request_sender);
+        let mut response_stream = self.response_stream.expect("The game stream is not open");
+
+        wait_for("the game stream to end", async {
+            while let Ok(Some(_)) = response_stream.message().await {}
+        })
+        .await;
+    }
+
+    pub async fn next_game_message(&mut self) -> GameServiceResponse {
+        let response_stream = self
+            .response_stream
+            .as_mut()
+            .expect("The game stream is not open");
+
+        wait_for("a game message", response_stream.message())
+            .await
+            .expect("The game stream failed")
+            .expect("The game stream was closed")
+    }
+}
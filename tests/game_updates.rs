@@ -0,0 +1,72 @@
+mod common;
+
+use blazer::app::server::grpc::storage::{interface::game::GameInterface, models};
+use common::TestServer;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_updates_of_a_game_are_all_kept() {
+    let server = TestServer::start().await;
+    let users = ["first", "second", "third", "fourth"].map(|user_id| models::User {
+        user_id: user_id.to_string(),
+        ..models::User::new()
+    });
+    let game = models::Game::new(&users, "a".repeat(100));
+    let game_id = game.game_id.clone();
+    server.store.insert_game(game).await.unwrap();
+
+    let updates = users.iter().map(|user| {
+        let store = server.store.clone();
+        let game_id = game_id.clone();
+        let user_id = user.user_id.clone();
+
+        tokio::spawn(async move {
+            for progress in 1..=50 {
+                store
+                    .update_game(&game_id, |game| {
+                        Some(game.update_progress(&user_id, progress))
+                    })
+                    .await
+                    .unwrap();
+            }
+        })
+    });
+
+    for update in updates.collect::<Vec<_>>() {
+        update.await.unwrap();
+    }
+
+    let game = server.store.find_game(&game_id).await.unwrap();
+    for user in &users {
+        assert_eq!(game.get_player(&user.user_id).progress, 50);
+    }
+}
+
+#[tokio::test]
+async fn updates_that_change_nothing_are_not_written() {
+    let server = TestServer::start().await;
+    let users = [models::User::new()];
+    let game = models::Game::new(&users, "prompt".to_string());
+    let game_id = game.game_id.clone();
+    server.store.insert_game(game).await.unwrap();
+
+    let (game, outcome) = server
+        .store
+        .update_game(&game_id, |game| {
+            game.game_status = models::GameStatus::End;
+            None::<()>
+        })
+        .await
+        .unwrap();
+    assert!(outcome.is_none());
+    assert!(!game.is_in_progress());
+
+    let game = server.store.find_game(&game_id).await.unwrap();
+    assert!(game.is_in_progress());
+
+    let missing = server
+        .store
+        .update_game("missing", |_| Some(()))
+        .await
+        .expect_err("The game does not exist");
+    assert!(missing.is_not_found());
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use blazer::app::server::grpc::{
+    server::GlobalStatsResponse,
+    storage::interface::stats::StatsInterface,
+    types::{ACTIVE_GAMES_KEY, ACTIVE_PLAYERS_KEY},
+};
+use common::{wait_for, wait_until, TestServer};
+
+async fn next_stats(stream: &mut tonic::Streaming<GlobalStatsResponse>) -> GlobalStatsResponse {
+    wait_for("the global stats", stream.message())
+        .await
+        .expect("The stats stream failed")
+        .expect("The stats stream was closed")
+}
+
+#[tokio::test]
+async fn players_are_active_while_their_stream_is_open() {
+    let server = TestServer::start().await;
+    let mut clients = server.connect_clients(2).await;
+
+    let mut first_stream = clients[0].open_global_stats_stream().await;
+    let mut second_stream = clients[1].open_global_stats_stream().await;
+
+    assert!(next_stats(&mut first_stream).await.active_players >= 1);
+    assert_eq!(next_stats(&mut second_stream).await.active_players, 2);
+
+    drop(first_stream);
+
+    let store = server.store.clone();
+    let user_id = clients[0].user_id.clone();
+    wait_until("the player to go offline", || async {
+        !store.is_player_active(&user_id).await.unwrap()
+    })
+    .await;
+
+    assert_eq!(store.get_global_stats().await.unwrap().active_players, 1);
+}
+
+#[tokio::test]
+async fn players_stay_active_while_another_stream_is_open() {
+    let server = TestServer::start().await;
+    let mut client = server.connect_client().await;
+
+    // A reconnecting client opens a new stream before the previous one is closed
+    let first_stream = client.open_global_stats_stream().await;
+    let mut second_stream = client.open_global_stats_stream().await;
+    next_stats(&mut second_stream).await;
+
+    drop(first_stream);
+
+    let store = server.store.clone();
+    let user_id = client.user_id.clone();
+    wait_until("the first stream to be closed", || async {
+        store.global_stats_streams.lock().unwrap().get(&user_id) == Some(&1)
+    })
+    .await;
+    assert!(store.is_player_active(&user_id).await.unwrap());
+
+    drop(second_stream);
+
+    wait_until("the player to go offline", || async {
+        !store.is_player_active(&user_id).await.unwrap()
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn expired_entries_are_not_counted() {
+    let server = TestServer::start().await;
+    let redis_client = &server.store.redis_client;
+
+    // The entries of an instance that has stopped without removing them
+    redis_client
+        .add_to_sorted_set(ACTIVE_PLAYERS_KEY, "crashed_player", 0.0)
+        .await
+        .unwrap();
+    redis_client
+        .add_to_sorted_set(ACTIVE_GAMES_KEY, "crashed_game", 0.0)
+        .await
+        .unwrap();
+    server.store.add_active_game("running_game").await.unwrap();
+
+    assert!(!server
+        .store
+        .is_player_active("crashed_player")
+        .await
+        .unwrap());
+
+    let global_stats = server.store.get_global_stats().await.unwrap();
+    assert_eq!(global_stats.active_players, 0);
+    assert_eq!(global_stats.active_games, 1);
+
+    // The expired entries are removed once they are counted
+    assert!(redis_client
+        .get_sorted_set_score(ACTIVE_PLAYERS_KEY, "crashed_player")
+        .await
+        .unwrap()
+        .is_none());
+}
@@ -0,0 +1,308 @@
+mod common;
+
+use blazer::app::{
+    client::model::ClientArgs,
+    server::{
+        errors::DbError,
+        grpc::{
+            invite_code::{self, INVITE_CODE_LENGTH},
+            storage::{
+                interface::{invite::InviteInterface, room::RoomInterface},
+                models,
+            },
+        },
+    },
+    types::RoomServiceResponseType,
+};
+use clap::Parser;
+use common::{assert_message_type, TestServer};
+
+#[test]
+fn invite_codes_are_normalized() {
+    let invite_code = invite_code::generate_invite_code();
+    assert_eq!(invite_code.len(), INVITE_CODE_LENGTH);
+    assert_eq!(
+        invite_code::normalize_invite_code(&invite_code),
+        Some(invite_code)
+    );
+
+    assert_eq!(
+        invite_code::normalize_invite_code(" abc-def "),
+        Some("ABCDEF".to_string())
+    );
+    // The characters that are easily mistaken for one another are not used
+    assert_eq!(invite_code::normalize_invite_code("ABCDE0"), None);
+    assert_eq!(invite_code::normalize_invite_code("ABCDEFG"), None);
+}
+
+#[test]
+fn join_links_are_parsed_from_the_command_line() {
+    let link = invite_code::get_join_link("ABC-DEF");
+    let args = ClientArgs::try_parse_from(["client", &link]).unwrap();
+    assert_eq!(args.join_link, Some("ABCDEF".to_string()));
+
+    let args = ClientArgs::try_parse_from(["client", "abcdef"]).unwrap();
+    assert_eq!(args.join_link, Some("ABCDEF".to_string()));
+
+    assert!(ClientArgs::try_parse_from(["client", "blazer://join/nope"]).is_err());
+    assert!(ClientArgs::try_parse_from(["client"])
+        .unwrap()
+        .join_link
+        .is_none());
+}
+
+#[tokio::test]
+async fn users_join_a_room_with_its_invite_code() {
+    let server = TestServer::start().await;
+    let mut clients = server.connect_clients(2).await;
+
+    let message = clients[0].create_room().await.unwrap();
+    assert_message_type(&message, RoomServiceResponseType::Init);
+    let invite_code = message.invite_code.expect("The room has no invite code");
+
+    // The code is typed the way it is read out
+    let typed_code = format!(
+        "{}-{}",
+        &invite_code[..3],
+        invite_code[3..].to_ascii_lowercase()
+    );
+    let message = clients[1].join_with_invite(&typed_code).await.unwrap();
+    assert_message_type(&message, RoomServiceResponseType::GameStart);
+    assert_eq!(message.user_details.len(), 2);
+
+    let message = clients[0].next_room_message().await;
+    assert_message_type(&message, RoomServiceResponseType::GameStart);
+
+    // The invite is removed along with the room once the game starts
+    assert!(server.store.find_invite(&invite_code).await.is_err());
+}
+
+#[tokio::test]
+async fn unknown_invite_codes_are_not_found() {
+    let server = TestServer::start().await;
+    let mut client = server.connect_client().await;
+
+    let status = client.join_with_invite("ABCDEF").await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+
+    let status = client.join_with_invite("not a code").await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+}
+
+#[tokio::test]
+async fn expired_invites_are_removed() {
+    let server = TestServer::start().await;
+    let mut client = server.connect_client().await;
+    server.insert_room("private", 2, &[]).await;
+
+    let mut invite = models::Invite::new("ABCDEF".to_string(), "private".to_string(), false);
+    invite.expires_at = 0;
+    server.store.insert_invite(invite).await.unwrap();
+
+    let status = client.join_with_invite("ABCDEF").await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+    assert!(server.store.find_invite("ABCDEF").await.is_err());
+}
+
+/// Create a private room directly in the store, along with an invite code for it
+async fn insert_private_room(
+    server: &TestServer,
+    room_id: &str,
+    invite_code: &str,
+    is_single_use: bool,
+) {
+    let mut room = models::Room::new(room_id.to_string(), 3);
+    room.invite_code = Some(invite_code.to_string());
+    server.store.insert_room(room).await.unwrap();
+
+    let invite = models::Invite::new(invite_code.to_string(), room_id.to_string(), is_single_use);
+    server.store.insert_invite(invite).await.unwrap();
+}
+
+#[tokio::test]
+async fn single_use_invites_are_used_once() {
+    let server = TestServer::start().await;
+    let mut clients = server.connect_clients(3).await;
+    insert_private_room(&server, "large", "ABCDEF", true).await;
+
+    let message = clients[0].join_with_invite("ABCDEF").await.unwrap();
+    assert_eq!(message.room_id, "large");
+    let invite = server.store.find_invite("ABCDEF").await.unwrap();
+    assert_eq!(invite.used_by, Some(clients[0].user_id.clone()));
+    assert_eq!(server.find_room("large").await.unwrap().invite_code, None);
+
+    let status = clients[1].join_with_invite("ABCDEF").await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+
+    // The id of the room cannot be used in place of the invite code
+    let status = clients[2].join_room(Some("large")).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn single_use_invites_are_used_once_by_concurrent_joins() {
+    let server = TestServer::start().await;
+    let clients = server.connect_clients(2).await;
+    insert_private_room(&server, "large", "ABCDEF", true).await;
+
+    // The clients are kept, so that the user who joined stays in the room
+    let joins = clients.into_iter().map(|mut client| {
+        tokio::spawn(async move {
+            let is_joined = client.join_with_invite("ABCDEF").await.is_ok();
+            (client, is_joined)
+        })
+    });
+
+    let mut clients = Vec::new();
+    for join in joins.collect::<Vec<_>>() {
+        clients.push(join.await.unwrap());
+    }
+
+    let successful_joins = clients.iter().filter(|(_, is_joined)| *is_joined).count();
+    assert_eq!(successful_joins, 1);
+    assert_eq!(server.find_room("large").await.unwrap().users.len(), 1);
+}
+
+#[tokio::test]
+async fn private_rooms_are_only_joined_with_their_invite_code() {
+    let server = TestServer::start().await;
+    let mut clients = server.connect_clients(3).await;
+    insert_private_room(&server, "private", "ABCDEF", false).await;
+
+    let status = clients[0].join_room(Some("private")).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+
+    clients[0].join_with_invite("ABCDEF").await.unwrap();
+
+    // A user still in the room rejoins it with its id, before the previous session is cleaned up
+    let message = clients[0].join_room(Some("private")).await.unwrap();
+    assert_message_type(&message, RoomServiceResponseType::Init);
+    assert_eq!(server.find_room("private").await.unwrap().users.len(), 1);
+}
+
+#[tokio::test]
+async fn users_rejoin_a_private_room_with_their_invite_code_once_disconnected() {
+    let server = TestServer::start().await;
+    let mut clients = server.connect_clients(3).await;
+    insert_private_room(&server, "large", "ABCDEF", true).await;
+    let host_id = clients[1].user_id.clone();
+    server
+        .store
+        .update_room("large", |room| {
+            room.host_id = Some(host_id.clone());
+            Some(())
+        })
+        .await
+        .unwrap();
+
+    // The host joins the room with its id
+    clients[1].join_room(Some("large")).await.unwrap();
+    clients[0].join_with_invite("ABCDEF").await.unwrap();
+
+    // The connection is lost, and the user is removed from the room once the session is cleaned up
+    clients[0].disconnect();
+    server.wait_for_session_count(1).await;
+    assert_eq!(server.find_room("large").await.unwrap().users, [host_id]);
+
+    // The user who used the single use code rejoins with it, as the client does once reconnected
+    let message = clients[0].join_with_invite("ABCDEF").await.unwrap();
+    assert_message_type(&message, RoomServiceResponseType::Init);
+    assert_eq!(server.find_room("large").await.unwrap().users.len(), 2);
+
+    let status = clients[2].join_with_invite("ABCDEF").await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+}
+
+#[tokio::test]
+async fn failed_joins_do_not_use_single_use_invites() {
+    let server = TestServer::start().await;
+    let mut client = server.connect_client().await;
+    insert_private_room(&server, "full", "ABCDEF", true).await;
+    server
+        .store
+        .update_room("full", |room| {
+            for user_id in ["first", "second", "third"] {
+                room.add_user(user_id.to_string());
+            }
+            Some(())
+        })
+        .await
+        .unwrap();
+
+    let status = client.join_with_invite("ABCDEF").await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+    let invite = server.store.find_invite("ABCDEF").await.unwrap();
+    assert_eq!(invite.used_by, None);
+    assert!(server
+        .find_room("full")
+        .await
+        .unwrap()
+        .invite_code
+        .is_some());
+}
+
+#[tokio::test]
+async fn invite_codes_are_not_reused() {
+    let server = TestServer::start().await;
+
+    let invite = models::Invite::new("ABCDEF".to_string(), "first".to_string(), false);
+    server.store.insert_invite(invite).await.unwrap();
+
+    let invite = models::Invite::new("ABCDEF".to_string(), "second".to_string(), false);
+    let error = server.store.insert_invite(invite).await.unwrap_err();
+    assert!(matches!(error, DbError::DuplicateValue));
+
+    let invite = server.store.find_invite("ABCDEF").await.unwrap();
+    assert_eq!(invite.room_id, "first");
+}
+
+#[tokio::test]
+async fn invites_are_removed_by_the_store_once_expired() {
+    let server = TestServer::start().await;
+
+    let mut invite = models::Invite::new("ABCDEF".to_string(), "waiting".to_string(), false);
+    invite.expires_at = blazer::app::utils::current_timestamp_millis() + 50;
+    server.store.insert_invite(invite).await.unwrap();
+    assert!(server.store.find_invite("ABCDEF").await.is_ok());
+
+    // The room is never filled, the invite is removed without being used
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert!(server
+        .store
+        .find_invite("ABCDEF")
+        .await
+        .unwrap_err()
+        .is_not_found());
+}
+
+#[tokio::test]
+async fn single_use_invites_are_created_on_request() {
+    let server = TestServer::start().await;
+    let mut client = server.connect_client().await;
+
+    let message = client.create_room_with_single_use_invite().await.unwrap();
+    let invite = server
+        .store
+        .find_invite(&message.invite_code.unwrap())
+        .await
+        .unwrap();
+
+    assert!(invite.is_single_use);
+    assert_eq!(invite.room_id, message.room_id);
+}
+
+#[tokio::test]
+async fn invites_are_removed_along_with_their_room() {
+    let server = TestServer::start().await;
+    let mut client = server.connect_client().await;
+
+    let message = client.create_room().await.unwrap();
+    let invite_code = message.invite_code.unwrap();
+    assert!(server.store.find_invite(&invite_code).await.is_ok());
+
+    client.leave_room().await;
+
+    assert!(server.find_room(&message.room_id).await.is_none());
+    assert!(server.store.find_invite(&invite_code).await.is_err());
+}
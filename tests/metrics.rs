@@ -0,0 +1,125 @@
+mod common;
+
+use blazer::app::{
+    server::{
+        self,
+        grpc::{
+            redis_client::RedisClient,
+            storage::{interface::room::RoomInterface, Store},
+        },
+        metrics,
+    },
+    types::{MetricsConfig, ServerConfig},
+};
+use common::{wait_for, TestServer};
+use tokio_util::sync::CancellationToken;
+
+/// Serve the metrics of the store on a port picked by the OS
+async fn serve_metrics(
+    store: Store,
+    shutdown_token: &CancellationToken,
+) -> (String, tokio::task::JoinHandle<()>) {
+    let tcp_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", tcp_listener.local_addr().unwrap());
+
+    let join_handle = tokio::spawn(metrics::serve_metrics(
+        tcp_listener,
+        store,
+        shutdown_token.clone().cancelled_owned(),
+    ));
+
+    (url, join_handle)
+}
+
+async fn get(url: &str) -> (hyper::StatusCode, String) {
+    let response = hyper::Client::new()
+        .get(url.parse().unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn metrics_are_served_until_the_shutdown() {
+    let server = TestServer::start().await;
+    let shutdown_token = CancellationToken::new();
+    let (url, join_handle) = serve_metrics(server.store.clone(), &shutdown_token).await;
+
+    let mut client = server.connect_client().await;
+    client.ping().await;
+
+    let (status, body) = get(&format!("{url}/metrics")).await;
+    assert_eq!(status, hyper::StatusCode::OK);
+    assert!(body.contains("blazer_rooms_waiting"));
+    assert!(body.contains(r#"method="/server.Grpc/Ping""#));
+
+    let (status, _) = get(&format!("{url}/other")).await;
+    assert_eq!(status, hyper::StatusCode::NOT_FOUND);
+
+    shutdown_token.cancel();
+    wait_for("the metrics server to stop", join_handle)
+        .await
+        .unwrap();
+
+    // The port is released once the server has stopped
+    let address = url.trim_start_matches("http://");
+    assert!(tokio::net::TcpStream::connect(address).await.is_err());
+}
+
+#[tokio::test]
+async fn only_the_rooms_with_players_waiting_are_counted() {
+    let server = TestServer::start().await;
+
+    server.insert_room("waiting", 3, &["first"]).await;
+    server.insert_room("empty", 3, &[]).await;
+    server.insert_room("full", 2, &["second", "third"]).await;
+    assert_eq!(server.store.get_waiting_rooms_count().await.unwrap(), 1);
+
+    // The room is no longer waiting once it is full
+    let mut room = server.find_room("waiting").await.unwrap();
+    room.add_user("fourth".to_string());
+    room.add_user("fifth".to_string());
+    server.store.insert_room(room).await.unwrap();
+    assert_eq!(server.store.get_waiting_rooms_count().await.unwrap(), 0);
+
+    server.insert_room("other", 3, &["sixth"]).await;
+    server.store.delete_room("other").await.unwrap();
+    assert_eq!(server.store.get_waiting_rooms_count().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn the_metrics_port_is_released_once_the_server_has_stopped() {
+    // The port is picked by the OS, then released for the server to bind it
+    let metrics_port = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let metrics_url = format!("http://127.0.0.1:{metrics_port}/metrics");
+
+    let tcp_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let server_config = ServerConfig {
+        metrics: Some(MetricsConfig { port: metrics_port }),
+        ..Default::default()
+    };
+    let handle = server::start_server_with_store(
+        server_config,
+        tcp_listener,
+        Store::new(RedisClient::in_memory()),
+        std::future::pending(),
+    )
+    .await
+    .unwrap();
+
+    let (status, _) = get(&metrics_url).await;
+    assert_eq!(status, hyper::StatusCode::OK);
+
+    wait_for("the server to stop", handle.shutdown()).await;
+
+    let address = format!("127.0.0.1:{metrics_port}");
+    assert!(tokio::net::TcpStream::connect(address).await.is_err());
+}
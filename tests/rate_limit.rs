@@ -0,0 +1,146 @@
+mod common;
+
+use blazer::app::{
+    server::grpc::{server::PingRequest, storage::interface::room::RoomInterface},
+    types::{MethodRateLimitConfig, RateLimitConfig, ServerConfig, TokenBucketConfig},
+};
+use common::TestServer;
+
+/// The buckets are not refilled during a test
+const NO_REFILL: f64 = 0.000_1;
+
+fn get_bucket(burst: u32) -> TokenBucketConfig {
+    TokenBucketConfig {
+        burst,
+        per_second: NO_REFILL,
+    }
+}
+
+fn get_server_config(rate_limit: RateLimitConfig) -> ServerConfig {
+    ServerConfig {
+        rate_limit: Some(rate_limit),
+        ..Default::default()
+    }
+}
+
+fn get_rate_limit(per_ip: u32, per_user: u32) -> RateLimitConfig {
+    RateLimitConfig {
+        per_ip: get_bucket(per_ip),
+        per_user: get_bucket(per_user),
+        methods: Default::default(),
+        max_rooms_per_user: 10,
+    }
+}
+
+#[tokio::test]
+async fn guest_pings_are_limited_by_ip() {
+    let server = TestServer::start_with_config(get_server_config(get_rate_limit(3, 100))).await;
+
+    // Every guest ping is made on the same connection, from the same ip address
+    let mut client = server.connect_client().await;
+    client
+        .client
+        .ping(PingRequest { user_id: None })
+        .await
+        .unwrap();
+    client
+        .client
+        .ping(PingRequest { user_id: None })
+        .await
+        .unwrap();
+
+    let status = client
+        .client
+        .ping(PingRequest { user_id: None })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+}
+
+#[tokio::test]
+async fn calls_are_limited_by_user() {
+    let server = TestServer::start_with_config(get_server_config(get_rate_limit(100, 2))).await;
+    let mut clients = server.connect_clients(2).await;
+
+    clients[0].ping().await;
+    clients[0].ping().await;
+
+    let status = clients[0].try_ping().await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+
+    // The other users have buckets of their own
+    clients[1].ping().await;
+}
+
+#[tokio::test]
+async fn methods_have_their_own_limits() {
+    let mut rate_limit = get_rate_limit(100, 100);
+    rate_limit.methods.insert(
+        "RoomService".to_string(),
+        MethodRateLimitConfig {
+            per_ip: None,
+            per_user: Some(get_bucket(1)),
+        },
+    );
+
+    let server = TestServer::start_with_config(get_server_config(rate_limit)).await;
+    let mut client = server.connect_client().await;
+
+    client.join_room(None).await.unwrap();
+
+    let status = client.join_room(None).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+
+    // The other rpcs keep the default limits
+    client.ping().await;
+}
+
+#[tokio::test]
+async fn rooms_per_user_are_capped() {
+    let mut rate_limit = get_rate_limit(100, 100);
+    rate_limit.max_rooms_per_user = 1;
+
+    let server = TestServer::start_with_config(get_server_config(rate_limit)).await;
+    let mut clients = server.connect_clients(2).await;
+
+    let message = clients[0].create_room().await.unwrap();
+    let invite_code = message.invite_code.unwrap();
+
+    let status = clients[0].create_room().await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+
+    // The room is no longer held once its game starts
+    clients[1].join_with_invite(&invite_code).await.unwrap();
+    clients[0].next_room_message().await;
+
+    clients[0].create_room().await.unwrap();
+
+    // Or once it is left
+    clients[0].leave_room().await;
+    clients[0].create_room().await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_rooms_of_a_user_are_capped() {
+    let server = TestServer::start().await;
+
+    // The room stream of a user is replaced by the next request, so the reservations are made on the store
+    let reservations = (0..8).map(|index| {
+        let store = server.store.clone();
+        tokio::spawn(async move {
+            store
+                .reserve_hosted_room("host", &format!("room-{index}"), 2)
+                .await
+                .unwrap()
+        })
+    });
+
+    let mut reserved_rooms = 0;
+    for reservation in reservations.collect::<Vec<_>>() {
+        if reservation.await.unwrap() {
+            reserved_rooms += 1;
+        }
+    }
+
+    assert_eq!(reserved_rooms, 2);
+}
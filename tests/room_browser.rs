@@ -0,0 +1,140 @@
+mod common;
+
+use blazer::app::{
+    server::grpc::{
+        storage::{
+            interface::room::RoomInterface,
+            models::{self, Room},
+        },
+        types::PUBLIC_ROOMS_KEY,
+    },
+    types::RoomServiceResponseType,
+    utils,
+};
+use common::{assert_message_type, TestServer};
+
+/// A public room created directly in the store, at a given age
+async fn insert_public_room(server: &TestServer, room_id: &str, user_ids: &[&str], age_ms: u64) {
+    let mut room = Room::new(room_id.to_string(), 3);
+    room.is_public = true;
+    room.created_at = utils::current_timestamp_millis() - age_ms;
+    for user_id in user_ids {
+        room.add_user(user_id.to_string());
+    }
+
+    server.store.insert_room(room).await.unwrap();
+}
+
+#[tokio::test]
+async fn public_rooms_are_listed() {
+    let server = TestServer::start().await;
+    let mut clients = server.connect_clients(2).await;
+
+    let message = clients[0].create_public_room().await.unwrap();
+    let host_name = clients[0].ping().await.user_name;
+
+    let rooms = clients[1].list_rooms().await;
+    assert_eq!(rooms.len(), 1);
+
+    let room = &rooms[0];
+    assert_eq!(room.room_id, message.room_id);
+    assert_eq!(room.room_size, 2);
+    assert_eq!(room.player_count, 1);
+    assert_eq!(room.host.as_ref().unwrap().user_name, host_name);
+    assert!(room.age_seconds < 5);
+}
+
+#[tokio::test]
+async fn private_rooms_are_not_listed() {
+    let server = TestServer::start().await;
+    let mut clients = server.connect_clients(3).await;
+
+    clients[0].create_room().await.unwrap();
+    // The common room is joined through the matchmaking
+    clients[1].join_room(None).await.unwrap();
+
+    assert!(clients[2].list_rooms().await.is_empty());
+}
+
+#[tokio::test]
+async fn public_rooms_are_joined_from_the_list() {
+    let server = TestServer::start().await;
+    let mut clients = server.connect_clients(2).await;
+
+    clients[0].create_public_room().await.unwrap();
+    let room_id = clients[1].list_rooms().await[0].room_id.clone();
+
+    let message = clients[1].join_room(Some(&room_id)).await.unwrap();
+    assert_message_type(&message, RoomServiceResponseType::GameStart);
+
+    // The room is removed from the list along with the room once the game starts
+    assert!(clients[1].list_rooms().await.is_empty());
+    assert!(server.store.get_public_rooms().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn left_rooms_are_removed_from_the_list() {
+    let server = TestServer::start().await;
+    let mut client = server.connect_client().await;
+
+    client.create_public_room().await.unwrap();
+    client.leave_room().await;
+
+    assert!(client.list_rooms().await.is_empty());
+    assert!(server.store.get_public_rooms().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn newest_rooms_that_can_be_joined_are_listed_first() {
+    let server = TestServer::start().await;
+    let mut client = server.connect_client().await;
+
+    insert_public_room(&server, "old", &["first"], 120_000).await;
+    insert_public_room(&server, "new", &["second"], 0).await;
+    insert_public_room(&server, "full", &["third", "fourth", "fifth"], 0).await;
+    insert_public_room(&server, "empty", &[], 0).await;
+
+    let rooms = client.list_rooms().await;
+    let room_ids = rooms
+        .iter()
+        .map(|room| room.room_id.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(room_ids, ["new", "old"]);
+
+    assert!(rooms[1].age_seconds >= 120);
+    // The users of the rooms created in the store do not exist
+    assert!(rooms.iter().all(|room| room.host.is_none()));
+}
+
+#[tokio::test]
+async fn rooms_stay_public_when_updated() {
+    let server = TestServer::start().await;
+    insert_public_room(&server, "public", &["first"], 0).await;
+
+    let mut room: models::Room = server.find_room("public").await.unwrap();
+    room.add_user("second".to_string());
+    server.store.insert_room(room).await.unwrap();
+
+    let rooms = server.store.get_public_rooms().await.unwrap();
+    assert_eq!(rooms.len(), 1);
+    assert_eq!(rooms[0].users.len(), 2);
+}
+
+#[tokio::test]
+async fn rooms_deleted_while_listed_are_skipped() {
+    let server = TestServer::start().await;
+    let mut client = server.connect_client().await;
+
+    insert_public_room(&server, "waiting", &["first"], 0).await;
+    // A room that was deleted between reading the ids of the public rooms and reading the rooms
+    server
+        .store
+        .redis_client
+        .add_to_set(PUBLIC_ROOMS_KEY, "deleted")
+        .await
+        .unwrap();
+
+    let rooms = client.list_rooms().await;
+    assert_eq!(rooms.len(), 1);
+    assert_eq!(rooms[0].room_id, "waiting");
+}
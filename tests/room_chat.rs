@@ -0,0 +1,104 @@
+mod common;
+
+use blazer::app::{
+    server::grpc::storage::interface::{room::RoomInterface, user::UserInterface},
+    types::RoomServiceResponseType,
+};
+use common::{assert_message_type, TestServer};
+
+#[tokio::test]
+async fn chat_is_sent_to_the_users_of_the_room() {
+    let server = TestServer::start().await;
+    let mut clients = server.connect_clients(3).await;
+    server.insert_room("large", 3, &[]).await;
+
+    clients[0].join_room(Some("large")).await.unwrap();
+    clients[1].join_room(Some("large")).await.unwrap();
+    let message = clients[0].next_room_message().await;
+    assert_message_type(&message, RoomServiceResponseType::UserJoined);
+
+    // The third user is not in the room and does not receive the chat
+    clients[2].join_room(None).await.unwrap();
+
+    let response = clients[1]
+        .send_room_chat("glhf, you bastard")
+        .await
+        .unwrap();
+    assert_eq!(response.message, "glhf, you *******");
+
+    let sender_name = clients[1].ping().await.user_name;
+
+    for client in &mut clients[..2] {
+        let message = client.next_room_message().await;
+        assert_message_type(&message, RoomServiceResponseType::Chat);
+        assert_eq!(message.room_id, "large");
+
+        let chat_message = message.chat_message.unwrap();
+        assert_eq!(chat_message.user_name, sender_name);
+        assert_eq!(chat_message.message, "glhf, you *******");
+    }
+
+    // The next message of the other user is a chat message of its own room
+    clients[2].send_room_chat("anyone?").await.unwrap();
+    let message = clients[2].next_room_message().await;
+    assert_eq!(message.chat_message.unwrap().message, "anyone?");
+}
+
+#[tokio::test]
+async fn chat_requires_a_room() {
+    let server = TestServer::start().await;
+    let mut client = server.connect_client().await;
+
+    let status = client.send_room_chat("hello").await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn invalid_chat_is_not_sent() {
+    let server = TestServer::start().await;
+    let mut client = server.connect_client().await;
+    client.create_room().await.unwrap();
+
+    let status = client.send_room_chat("").await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+    client.send_room_chat("ready").await.unwrap();
+    let message = client.next_room_message().await;
+    assert_eq!(message.chat_message.unwrap().message, "ready");
+}
+
+#[tokio::test]
+async fn chat_is_sent_to_the_users_connected_to_other_servers() {
+    let server = TestServer::start().await;
+    let other_server = server.start_sharing_redis().await;
+
+    let mut client = server.connect_client().await;
+    let mut other_client = other_server.connect_client().await;
+    let room_id = client.create_room().await.unwrap().room_id;
+
+    // The other user is placed in the room directly, the chat is the only message sent across the servers
+    server
+        .store
+        .update_room(&room_id, |room| {
+            room.add_user(other_client.user_id.clone());
+            Some(())
+        })
+        .await
+        .unwrap();
+    server
+        .store
+        .update_user(&other_client.user_id, |user| {
+            user.assign_room_id(room_id.clone());
+            Some(())
+        })
+        .await
+        .unwrap();
+
+    other_client.send_room_chat("gg").await.unwrap();
+
+    let message = client.next_room_message().await;
+    assert_message_type(&message, RoomServiceResponseType::Chat);
+    let chat_message = message.chat_message.unwrap();
+    assert_eq!(chat_message.user_id, other_client.user_id);
+    assert_eq!(chat_message.message, "gg");
+}
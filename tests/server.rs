@@ -1,56 +1,444 @@
-use ::blazer::app::{
-    client::types::ClientConfig,
-    server::grpc::server::{grpc_client, PingRequest, RoomServiceRequest},
-    utils,
+mod common;
+
+use blazer::app::server::grpc::storage::{
+    interface::{game::GameInterface, session::SessionInterface, user::UserInterface},
+    models,
+};
+use blazer::app::{
+    server::grpc::{
+        server::{game_service_response, PingRequest, RoomServiceRequest},
+        types::COMMON_ROOM_KEY,
+    },
+    types::{
+        RoomServiceRequestType, RoomServiceResponseType, ServerConfig, ShutdownConfig,
+        REQUEST_ID_METADATA_KEY,
+    },
 };
-use blazer::app::{server::start_server, types};
+use common::{assert_message_type, wait_for, wait_until, TestServer};
+
+#[tokio::test]
+async fn connect() {
+    let server = TestServer::start().await;
+    let mut client = server.connect_client().await;
+
+    let message = client.join_room(None).await.unwrap();
+    assert_message_type(&message, RoomServiceResponseType::Init);
+    assert_eq!(message.room_id, COMMON_ROOM_KEY);
+    server.wait_for_session_count(1).await;
 
-async fn configure_server(config: &types::ServerConfig) -> tokio::net::TcpListener {
-    let formatter = tracing_subscriber::fmt::format()
-        .with_file(true)
-        .with_line_number(true);
+    // Simulate a client disconnect, the server removes the user from the room
+    client.disconnect();
+    server.wait_for_session_count(0).await;
 
-    let server_config = config.server.clone().unwrap_or_default();
+    let common_room = server.find_room(COMMON_ROOM_KEY).await.unwrap();
+    assert!(common_room.users.is_empty());
+}
 
-    tracing_subscriber::fmt().event_format(formatter).init();
+#[tokio::test]
+async fn create_room() {
+    let server = TestServer::start().await;
+    let mut client = server.connect_client().await;
 
-    let server_address = format!("{}:{}", server_config.host, server_config.port);
-    let tcp_listener = tokio::net::TcpListener::bind(server_address)
+    let message = client.create_room().await.unwrap();
+    assert_message_type(&message, RoomServiceResponseType::Init);
+    assert!(message.room_id.starts_with("room_"));
+    assert_eq!(message.user_details.len(), 1);
+    assert_eq!(message.user_details[0].user_id, client.user_id);
+
+    let room = server.find_room(&message.room_id).await.unwrap();
+    assert_eq!(room.users, vec![client.user_id.clone()]);
+}
+
+#[tokio::test]
+async fn room_ids_are_chosen_by_the_server() {
+    let server = TestServer::start().await;
+    let mut client = server.connect_client().await;
+
+    let status = client
+        .client
+        .room_service(RoomServiceRequest {
+            client_id: client.user_id.clone(),
+            room_id: Some("chosen".to_string()),
+            request_type: RoomServiceRequestType::CreateRoom as i32,
+            invite_code: None,
+            single_use_invite: false,
+            is_public: false,
+        })
         .await
-        .expect("Could not bind to server address {server_address}");
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    assert!(server.find_room("chosen").await.is_none());
+}
+
+#[tokio::test]
+async fn join_missing_room() {
+    let server = TestServer::start().await;
+    let mut client = server.connect_client().await;
 
-    tcp_listener
+    let status = client.join_room(Some("missing")).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
 }
 
 #[tokio::test]
-async fn connect() {
-    let config =
-        utils::read_config::<types::ServerConfig>("config/server.toml", Some("BLAZER_SERVER"));
-    let tcp_listener = configure_server(&config).await;
-    let server_handle = tokio::spawn(async { start_server(config, tcp_listener).await });
+async fn join_full_room() {
+    let server = TestServer::start().await;
+    let mut clients = server.connect_clients(3).await;
 
-    let config = utils::read_config::<ClientConfig>("config/client.toml", Some("BLAZER"));
+    // The game starts as soon as a room is full, a full room is only seen while the game is being started
+    let user_ids = [clients[0].user_id.as_str(), clients[1].user_id.as_str()];
+    server.insert_room("full", 2, &user_ids).await;
 
-    let mut client = grpc_client::GrpcClient::connect(config.server_url.clone())
-        .await
-        .unwrap();
+    let status = clients[2].join_room(Some("full")).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+    let room = server.find_room("full").await.unwrap();
+    assert_eq!(room.users.len(), 2);
+}
+
+#[tokio::test]
+async fn join_created_room_starts_the_game() {
+    let server = TestServer::start().await;
+    let mut clients = server.connect_clients(2).await;
 
-    let ping_response = client.ping(PingRequest { user_id: None }).await.unwrap();
-    let user_id = ping_response.into_inner().user_id;
+    let message = clients[0].create_room().await.unwrap();
+    let room_id = message.room_id;
+    let invite_code = message.invite_code.unwrap();
 
-    let room_service_response = client
-        .room_service(RoomServiceRequest {
-            client_id: user_id,
-            room_id: None,
-            request_type: 2,
+    let message = clients[1].join_with_invite(&invite_code).await.unwrap();
+    assert_message_type(&message, RoomServiceResponseType::GameStart);
+    assert_eq!(message.user_details.len(), 2);
+
+    let message = clients[0].next_room_message().await;
+    assert_message_type(&message, RoomServiceResponseType::GameStart);
+    assert_eq!(message.room_id, room_id);
+
+    // The room is closed once the game starts
+    assert!(server.find_room(&room_id).await.is_none());
+    let status = clients[1].join_room(Some(&room_id)).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+
+    // Both players are offered the same game
+    let first_game = clients[0].ping().await.resumable_game.unwrap();
+    let second_game = clients[1].ping().await.resumable_game.unwrap();
+    assert_eq!(first_game.game_id, second_game.game_id);
+    assert!(!first_game.prompt.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_joins_are_all_kept() {
+    let server = TestServer::start().await;
+    let clients = server.connect_clients(4).await;
+    server.insert_room("waiting", 4, &[]).await;
+
+    let joins = clients.into_iter().map(|mut client| {
+        tokio::spawn(async move {
+            client.join_room(Some("waiting")).await.unwrap();
+            client
         })
+    });
+
+    let mut clients = Vec::new();
+    for join in joins.collect::<Vec<_>>() {
+        clients.push(join.await.unwrap());
+    }
+
+    // The game starts once the last of the users has joined
+    let mut game_ids = Vec::new();
+    for client in &mut clients {
+        let store = server.store.clone();
+        let user_id = client.user_id.clone();
+        wait_until("the user to be in the game", || async {
+            store.find_user(&user_id).await.unwrap().game_id.is_some()
+        })
+        .await;
+
+        game_ids.push(client.ping().await.resumable_game.unwrap().game_id);
+    }
+
+    game_ids.dedup();
+    assert_eq!(game_ids.len(), 1);
+}
+
+#[tokio::test]
+async fn rejoining_a_room_keeps_the_user_in_the_room() {
+    let server = TestServer::start().await;
+    let mut clients = server.connect_clients(2).await;
+    server.insert_room("waiting", 3, &[]).await;
+
+    clients[0].join_room(Some("waiting")).await.unwrap();
+
+    // A reconnecting client opens a new room stream, before the previous session is cleaned up
+    let message = clients[0].join_room(Some("waiting")).await.unwrap();
+    assert_message_type(&message, RoomServiceResponseType::Init);
+    assert_eq!(message.user_details.len(), 1);
+
+    // The new stream receives the messages of the room
+    clients[1].join_room(Some("waiting")).await.unwrap();
+    let message = clients[0].next_room_message().await;
+    assert_message_type(&message, RoomServiceResponseType::UserJoined);
+
+    let room = server.find_room("waiting").await.unwrap();
+    assert_eq!(
+        room.users,
+        [clients[0].user_id.clone(), clients[1].user_id.clone()]
+    );
+}
+
+#[tokio::test]
+async fn a_failed_join_keeps_the_current_room_stream() {
+    let server = TestServer::start().await;
+    let mut clients = server.connect_clients(2).await;
+    server.insert_room("waiting", 3, &[]).await;
+
+    clients[0].join_room(Some("waiting")).await.unwrap();
+
+    let status = clients[0].join_room(Some("missing")).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+
+    // The user is still informed about the room that the user is waiting in
+    clients[1].join_room(Some("waiting")).await.unwrap();
+    let message = clients[0].next_room_message().await;
+    assert_message_type(&message, RoomServiceResponseType::UserJoined);
+}
+
+#[tokio::test]
+async fn users_without_a_session_do_not_fail_the_join() {
+    let server = TestServer::start().await;
+    let mut clients = server.connect_clients(2).await;
+
+    // The first user has no room stream open on this server, such as a user who is disconnecting
+    server
+        .insert_room("waiting", 3, &[&clients[0].user_id])
+        .await;
+
+    let message = clients[1].join_room(Some("waiting")).await.unwrap();
+    assert_message_type(&message, RoomServiceResponseType::Init);
+    assert_eq!(message.user_details.len(), 2);
+}
+
+#[tokio::test]
+async fn disconnected_players_rejoin_their_game() {
+    let server = TestServer::start().await;
+    let mut clients = server.connect_clients(2).await;
+
+    clients[0].join_room(None).await.unwrap();
+    clients[1].join_room(None).await.unwrap();
+
+    let mut game_stream = clients[0].open_game_stream(0).await;
+    let message = game_stream.next_game_message().await;
+    assert_eq!(
+        message.message_type(),
+        game_service_response::MessageType::Init
+    );
+
+    // The connection of the player is lost
+    let game_id = game_stream.game_id.clone();
+    drop(game_stream);
+
+    let store = server.store.clone();
+    let user_id = clients[0].user_id.clone();
+    wait_until("the player to be disconnected", || async {
+        let game = store.find_game(&game_id).await.unwrap();
+        matches!(
+            game.get_player(&user_id).status,
+            models::PlayerStatus::Disconnected { .. }
+        )
+    })
+    .await;
+
+    // The client rejoins the game offered on ping once it has reconnected
+    let mut game_stream = clients[0].open_game_stream(0).await;
+    let message = game_stream.next_game_message().await;
+    assert_eq!(
+        message.message_type(),
+        game_service_response::MessageType::Resume
+    );
+
+    let game = store.find_game(&game_id).await.unwrap();
+    assert_eq!(
+        game.get_player(&user_id).status,
+        models::PlayerStatus::Playing
+    );
+}
+
+#[tokio::test]
+async fn closing_a_replaced_game_stream_keeps_the_player_playing() {
+    let server = TestServer::start().await;
+    let mut clients = server.connect_clients(2).await;
+
+    clients[0].join_room(None).await.unwrap();
+    clients[1].join_room(None).await.unwrap();
+
+    let mut first_stream = clients[0].open_game_stream(0).await;
+    first_stream.next_game_message().await;
+
+    // The client resumes the game before the previous stream is closed
+    let mut second_stream = clients[0].open_game_stream(0).await;
+    second_stream.next_game_message().await;
+    first_stream.close().await;
+
+    let game = server
+        .store
+        .find_game(&second_stream.game_id)
         .await
         .unwrap();
+    assert_eq!(
+        game.get_player(&clients[0].user_id).status,
+        models::PlayerStatus::Playing
+    );
+
+    // The progress sent on the new stream is still applied
+    second_stream.send_progress(1).await;
+    let message = second_stream.next_game_message().await;
+    assert_eq!(message.game_user_status.unwrap().status, 1);
+}
+
+#[tokio::test]
+async fn matchmaking_starts_the_game() {
+    let server = TestServer::start().await;
+    let mut clients = server.connect_clients(2).await;
+
+    let message = clients[0].join_room(None).await.unwrap();
+    assert_message_type(&message, RoomServiceResponseType::Init);
+
+    let message = clients[1].join_room(None).await.unwrap();
+    assert_message_type(&message, RoomServiceResponseType::GameStart);
+
+    let message = clients[0].next_room_message().await;
+    assert_message_type(&message, RoomServiceResponseType::GameStart);
+
+    // The common room is emptied for the next players instead of being closed
+    let common_room = server.find_room(COMMON_ROOM_KEY).await.unwrap();
+    assert!(common_room.users.is_empty());
+}
+
+#[tokio::test]
+async fn users_are_informed_of_joins_and_disconnects() {
+    let server = TestServer::start().await;
+    let mut clients = server.connect_clients(3).await;
+    server.insert_room("large", 3, &[]).await;
+
+    let message = clients[0].join_room(Some("large")).await.unwrap();
+    assert_message_type(&message, RoomServiceResponseType::Init);
+    assert_eq!(message.user_details.len(), 1);
+
+    let message = clients[1].join_room(Some("large")).await.unwrap();
+    assert_message_type(&message, RoomServiceResponseType::Init);
+    assert_eq!(message.user_details.len(), 2);
+
+    let message = clients[0].next_room_message().await;
+    assert_message_type(&message, RoomServiceResponseType::UserJoined);
+    assert_eq!(message.user_details.len(), 2);
+
+    clients[1].disconnect();
+
+    let message = clients[0].next_room_message().await;
+    assert_message_type(&message, RoomServiceResponseType::UserLeft);
+    assert_eq!(message.user_details.len(), 1);
+    assert_eq!(message.user_details[0].user_id, clients[0].user_id);
+
+    server.wait_for_session_count(1).await;
+    let room = server.find_room("large").await.unwrap();
+    assert_eq!(room.users, vec![clients[0].user_id.clone()]);
+}
+
+#[tokio::test]
+async fn leave_room() {
+    let server = TestServer::start().await;
+    let mut client = server.connect_client().await;
+
+    let room_id = client.create_room().await.unwrap().room_id;
+
+    let response = client.leave_room().await;
+    assert_eq!(response.room_id, Some(room_id.clone()));
+
+    // Nobody is waiting in the room any more, so it is closed
+    assert!(server.find_room(&room_id).await.is_none());
+
+    // Leaving again is a no-op, the user is no longer in a room
+    assert_eq!(client.leave_room().await.room_id, None);
+}
+
+#[tokio::test]
+async fn shutdown() {
+    let server = TestServer::start().await;
+    let mut client = server.connect_client().await;
+    client.join_room(None).await.unwrap();
+
+    let store = server.store.clone();
+    server.shutdown().await;
+
+    // The waiting players are removed from the room before the server stops
+    let status = client.wait_for_room_stream_end().await.unwrap();
+    assert_eq!(status.code(), tonic::Code::Unavailable);
+    assert_eq!(store.get_session_count(), 0);
+    assert!(store.is_draining());
+}
+
+#[tokio::test]
+async fn shutdown_waits_for_the_games_in_progress() {
+    let server = TestServer::start_with_config(ServerConfig {
+        shutdown: ShutdownConfig {
+            grace_period_seconds: 60,
+        },
+        ..Default::default()
+    })
+    .await;
+    let mut clients = server.connect_clients(2).await;
+
+    clients[0].join_room(None).await.unwrap();
+    let message = clients[1].join_room(None).await.unwrap();
+    assert_message_type(&message, RoomServiceResponseType::GameStart);
+
+    let store = server.store.clone();
+    let address = server.url.trim_start_matches("http://").to_string();
+    let shutdown = tokio::spawn(server.shutdown());
+
+    // New connections are refused while the players are finishing their game
+    wait_until("the listener to be closed", || async {
+        tokio::net::TcpStream::connect(&address).await.is_err()
+    })
+    .await;
+    wait_until("the server to drain", || async { store.is_draining() }).await;
+    assert_eq!(store.get_session_count(), 2);
+
+    // The players are removed once their game is over
+    let user = store.find_user(&clients[0].user_id).await.unwrap();
+    let mut game = store.find_game(&user.game_id.unwrap()).await.unwrap();
+    game.game_status = models::GameStatus::End;
+    store.insert_game(game).await.unwrap();
+
+    wait_for("the server to stop", shutdown).await.unwrap();
+    assert_eq!(store.get_session_count(), 0);
+}
+
+#[tokio::test]
+async fn valid_request_ids_are_sent_back() {
+    let server = TestServer::start().await;
+    let mut client = server.connect_client().await;
+
+    let ping_with_request_id = |request_id: &str| {
+        let mut request = tonic::Request::new(PingRequest {
+            user_id: Some(client.user_id.clone()),
+        });
+        request
+            .metadata_mut()
+            .insert(REQUEST_ID_METADATA_KEY, request_id.parse().unwrap());
+        request
+    };
+
+    let valid_request = ping_with_request_id("req_from-the.client");
+    let invalid_request = ping_with_request_id(&"a".repeat(65));
 
-    // Simulate a client disconnect
-    drop(room_service_response);
+    let response = client.client.ping(valid_request).await.unwrap();
+    assert_eq!(
+        response.metadata().get(REQUEST_ID_METADATA_KEY).unwrap(),
+        "req_from-the.client"
+    );
 
-    // This sleep is necessary for the server to do some cleanup activities as soon as the function ends
-    // If some sort of signaling mechanism is implemented for the server, this can be avoided
-    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    // The ids that are too long are replaced by a generated id
+    let response = client.client.ping(invalid_request).await.unwrap();
+    let request_id = response.metadata().get(REQUEST_ID_METADATA_KEY).unwrap();
+    assert!(request_id.to_str().unwrap().starts_with("req"));
+    assert_ne!(request_id.len(), 65);
 }
@@ -0,0 +1,201 @@
+mod common;
+
+use std::path::PathBuf;
+
+use blazer::app::{
+    client::{network, types::ClientTlsConfig},
+    server::{
+        self,
+        errors::ServerError,
+        grpc::{redis_client::RedisClient, server::PingRequest, storage::Store},
+    },
+    types,
+};
+use common::{wait_for, TestServer};
+
+/// Name in the certificate of the server, the server is reached through its ip address
+const SERVER_DOMAIN: &str = "localhost";
+
+/// Self-signed certificates, written to a directory of their own for every test
+struct TestCertificates {
+    directory: PathBuf,
+}
+
+impl TestCertificates {
+    fn generate() -> Self {
+        let directory = std::env::temp_dir().join(format!("blazer-tls-{}", uuid::Uuid::now_v7()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let certificates = Self { directory };
+
+        let ca = certificates.write_ca("ca");
+        certificates.write_certificate("server", SERVER_DOMAIN, &ca);
+        certificates.write_certificate("client", "blazer-client", &ca);
+
+        // Has not signed the certificate of the server, the clients that trust only this CA must fail
+        certificates.write_ca("other_ca");
+        certificates
+    }
+
+    fn write_ca(&self, name: &str) -> rcgen::Certificate {
+        let mut params = rcgen::CertificateParams::new(vec![]);
+        params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        let ca = rcgen::Certificate::from_params(params).unwrap();
+
+        std::fs::write(
+            self.path(&format!("{name}.pem")),
+            ca.serialize_pem().unwrap(),
+        )
+        .unwrap();
+        ca
+    }
+
+    fn write_certificate(&self, name: &str, domain: &str, ca: &rcgen::Certificate) {
+        let params = rcgen::CertificateParams::new(vec![domain.to_string()]);
+        let certificate = rcgen::Certificate::from_params(params).unwrap();
+
+        std::fs::write(
+            self.path(&format!("{name}.pem")),
+            certificate.serialize_pem_with_signer(ca).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(
+            self.path(&format!("{name}.key")),
+            certificate.serialize_private_key_pem(),
+        )
+        .unwrap();
+    }
+
+    fn path(&self, file_name: &str) -> String {
+        self.directory.join(file_name).to_str().unwrap().to_string()
+    }
+
+    fn get_server_config(&self, is_client_certificate_required: bool) -> types::ServerConfig {
+        types::ServerConfig {
+            tls: Some(types::TlsConfig {
+                certificate_path: self.path("server.pem"),
+                key_path: self.path("server.key"),
+                client_ca_path: is_client_certificate_required.then(|| self.path("ca.pem")),
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn get_client_config(&self, ca_name: &str, has_client_certificate: bool) -> ClientTlsConfig {
+        ClientTlsConfig {
+            ca_certificate_path: self.path(&format!("{ca_name}.pem")),
+            domain: Some(SERVER_DOMAIN.to_string()),
+            certificate_path: has_client_certificate.then(|| self.path("client.pem")),
+            key_path: has_client_certificate.then(|| self.path("client.key")),
+        }
+    }
+}
+
+impl Drop for TestCertificates {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.directory);
+    }
+}
+
+/// Connect and ping the server as a new guest, which fails if the TLS handshake fails
+async fn ping(server_url: &str, tls_config: Option<&ClientTlsConfig>) -> Result<(), String> {
+    let mut client = wait_for("the connection", network::connect(server_url, tls_config))
+        .await
+        .map_err(|error| error.to_string())?;
+
+    wait_for("the ping", client.ping(PingRequest { user_id: None }))
+        .await
+        .map(|_| ())
+        .map_err(|status| status.to_string())
+}
+
+#[tokio::test]
+async fn connect_over_tls() {
+    let certificates = TestCertificates::generate();
+    let server = TestServer::start_with_config(certificates.get_server_config(false)).await;
+
+    let tls_config = certificates.get_client_config("ca", false);
+    ping(&server.url, Some(&tls_config)).await.unwrap();
+}
+
+#[tokio::test]
+async fn plaintext_client_is_rejected() {
+    let certificates = TestCertificates::generate();
+    let server = TestServer::start_with_config(certificates.get_server_config(false)).await;
+
+    let plaintext_url = server.url.replace("https://", "http://");
+    assert!(ping(&plaintext_url, None).await.is_err());
+}
+
+#[tokio::test]
+async fn untrusted_server_is_rejected() {
+    let certificates = TestCertificates::generate();
+    let server = TestServer::start_with_config(certificates.get_server_config(false)).await;
+
+    let tls_config = certificates.get_client_config("other_ca", false);
+    assert!(ping(&server.url, Some(&tls_config)).await.is_err());
+}
+
+#[tokio::test]
+async fn mtls_requires_a_client_certificate() {
+    let certificates = TestCertificates::generate();
+    let server = TestServer::start_with_config(certificates.get_server_config(true)).await;
+
+    let tls_config = certificates.get_client_config("ca", false);
+    assert!(ping(&server.url, Some(&tls_config)).await.is_err());
+
+    let tls_config = certificates.get_client_config("ca", true);
+    ping(&server.url, Some(&tls_config)).await.unwrap();
+}
+
+#[tokio::test]
+async fn calls_over_tls_are_limited_by_ip() {
+    let certificates = TestCertificates::generate();
+    let no_refill = types::TokenBucketConfig {
+        burst: 2,
+        per_second: 0.000_1,
+    };
+    let server_config = types::ServerConfig {
+        rate_limit: Some(types::RateLimitConfig {
+            per_ip: no_refill,
+            per_user: types::TokenBucketConfig {
+                burst: 100,
+                ..no_refill
+            },
+            methods: Default::default(),
+            max_rooms_per_user: 10,
+        }),
+        ..certificates.get_server_config(false)
+    };
+    let server = TestServer::start_with_config(server_config).await;
+
+    let tls_config = certificates.get_client_config("ca", false);
+    ping(&server.url, Some(&tls_config)).await.unwrap();
+    ping(&server.url, Some(&tls_config)).await.unwrap();
+
+    let error = ping(&server.url, Some(&tls_config)).await.unwrap_err();
+    assert!(error.contains("ResourceExhausted"), "{error}");
+}
+
+#[tokio::test]
+async fn missing_certificates_are_reported_on_start() {
+    let tcp_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let server_config = types::ServerConfig {
+        tls: Some(types::TlsConfig {
+            certificate_path: "missing/server.pem".to_string(),
+            key_path: "missing/server.key".to_string(),
+            client_ca_path: None,
+        }),
+        ..Default::default()
+    };
+
+    let result = server::start_server_with_store(
+        server_config,
+        tcp_listener,
+        Store::new(RedisClient::in_memory()),
+        std::future::pending(),
+    )
+    .await;
+
+    assert!(matches!(result, Err(ServerError::TlsCertificates(_))));
+}